@@ -0,0 +1,176 @@
+"""
+Library interface to a Macolint snippet store, for use from other Python code.
+
+Every function takes an explicit Config and returns data or raises
+(SnippetNotFoundError, SnippetExistsError, DecryptionError or ValueError);
+nothing prints or prompts. The `snip` CLI works on the same databases, so snippets
+saved here show up there (and the other way round) when the Config points at
+a vault's files:
+
+    from macolint.api import Config, get_snippet, save_snippet
+
+    config = Config.for_vault()
+    save_snippet(config, "ssh/login", "ssh {{user}}@{{host}}")
+    print(get_snippet(config, "ssh/login", fill={"user": "me", "host": "prod01"}).content)
+"""
+
+from dataclasses import dataclass
+from pathlib import Path
+from typing import Dict, List, Optional
+
+from macolint.config import DEFAULT_SETTINGS
+from macolint.crypto import DecryptionError, is_protected, unprotect
+from macolint.database import Database
+from macolint.models import Snippet, validate_name
+from macolint.templates import fill_placeholders, find_placeholders
+
+
+class SnippetNotFoundError(LookupError):
+    """No live snippet has the given name."""
+
+
+class SnippetExistsError(ValueError):
+    """A snippet with the name exists and overwrite was not asked for."""
+
+
+@dataclass
+class Config:
+    """
+    Where a snippet store lives and how it is opened.
+    max_snippet_bytes of 0 means no size limit.
+    """
+    db_path: Path
+    master_key: bytes
+    encrypt_names: bool = False
+    encrypt_database: bool = False
+    max_snippet_bytes: int = DEFAULT_SETTINGS["max_snippet_bytes"]
+
+    @classmethod
+    def for_vault(cls, vault: Optional[str] = None) -> "Config":
+        """
+        The configuration the CLI uses for `vault` (the active vault, normally
+        'default', when None): its database, its master key (created on first
+        use, as `snip` does) and the user's settings.json.
+        """
+        from macolint import config
+
+        previous = config.get_active_vault()
+        if vault is not None:
+            config.set_active_vault(vault)
+        try:
+            settings = config.load_settings()
+            limit = settings["max_snippet_bytes"]
+            if isinstance(limit, bool) or not isinstance(limit, int) or limit < 0:
+                limit = DEFAULT_SETTINGS["max_snippet_bytes"]
+            return cls(
+                db_path=config.get_db_path(),
+                master_key=config.get_master_key(),
+                encrypt_names=bool(settings["encrypt_names"]),
+                encrypt_database=bool(settings["encrypt_database"]),
+                max_snippet_bytes=limit,
+            )
+        finally:
+            config.set_active_vault(previous)
+
+
+def open_database(config: Config) -> Database:
+    """
+    Open (creating or migrating) the database described by config.
+
+    Raises:
+        DecryptionError: If config.master_key is not the database's key
+    """
+    Path(config.db_path).parent.mkdir(parents=True, exist_ok=True)
+    return Database(
+        db_path=config.db_path,
+        master_key=config.master_key,
+        encrypt_names=config.encrypt_names,
+        encrypt_database=config.encrypt_database,
+    )
+
+
+def save_snippet(
+    config: Config,
+    name: str,
+    content: str,
+    *,
+    kind: Optional[str] = None,
+    tags: Optional[List[str]] = None,
+    language: Optional[str] = None,
+    description: Optional[str] = None,
+    overwrite: bool = False,
+) -> bool:
+    """
+    Save content under name (a '/'-separated path; modules are created as
+    needed). A replaced snippet keeps its description unless one is given.
+    Returns True if the snippet was created, False if replaced.
+
+    Raises:
+        ValueError: If the name is invalid, or the content is empty or over
+            config.max_snippet_bytes
+        SnippetExistsError: If the snippet exists and overwrite is False
+    """
+    name = validate_name(name)
+    if not content.strip():
+        raise ValueError("Snippet content cannot be empty.")
+    size = len(content.encode("utf-8"))
+    if config.max_snippet_bytes and size > config.max_snippet_bytes:
+        raise ValueError(
+            f"Content is {size} bytes, over the limit of {config.max_snippet_bytes}."
+        )
+
+    db = open_database(config)
+    if not overwrite and db.snippet_exists(name):
+        raise SnippetExistsError(f"Snippet '{name}' already exists.")
+    return db.save_snippet(
+        name, content, kind=kind, tags=tags, language=language, description=description
+    )
+
+
+def get_snippet(
+    config: Config,
+    name: str,
+    *,
+    passphrase: Optional[str] = None,
+    fill: Optional[Dict[str, str]] = None,
+) -> Snippet:
+    """
+    Return a snippet with its content decrypted. A protected snippet needs
+    its passphrase. With fill, {{placeholders}} in the content are replaced
+    (see macolint.templates); every placeholder needs a value.
+
+    Raises:
+        SnippetNotFoundError: If there is no such snippet
+        DecryptionError: If the master key or passphrase is wrong
+        ValueError: If the passphrase is missing, or fill leaves a
+            placeholder without a value
+    """
+    snippet = open_database(config).get_snippet(name)
+    if snippet is None:
+        raise SnippetNotFoundError(f"Snippet '{name}' not found.")
+    if is_protected(snippet.content):
+        if passphrase is None:
+            raise ValueError(f"Snippet '{name}' is protected; a passphrase is required.")
+        snippet.content = unprotect(snippet.content, passphrase)
+    if fill is not None:
+        missing = [key for key in find_placeholders(snippet.content) if key not in fill]
+        if missing:
+            raise ValueError(f"No value for placeholder(s): {', '.join(missing)}.")
+        snippet.content = fill_placeholders(snippet.content, fill)
+    return snippet
+
+
+def list_snippets(config: Config) -> List[str]:
+    """Full paths of every live snippet (nothing is decrypted but the names)."""
+    return open_database(config).get_all_snippet_names()
+
+
+def delete_snippet(config: Config, name: str) -> None:
+    """
+    Delete a snippet.
+
+    Raises:
+        SnippetNotFoundError: If there is no such snippet
+    """
+    if not open_database(config).delete_snippet(name):
+        raise SnippetNotFoundError(f"Snippet '{name}' not found.")
@@ -0,0 +1,112 @@
+"""Portable encrypted archives for moving snippets between machines."""
+
+from datetime import datetime
+from typing import List
+
+from cryptography.exceptions import InvalidTag
+from cryptography.fernet import Fernet
+
+from macolint.crypto import b64, ub64, decrypt, derive_key, encrypt, gen_salt, unpack_content
+
+ARCHIVE_FORMAT = "macolint-archive"
+ARCHIVE_VERSION = 1
+KDF_ITERATIONS = 200_000
+
+
+def build_archive(records: List[dict], master_key: bytes, passphrase: str) -> dict:
+    """
+    Build a JSON-serializable archive from Database.get_export_records().
+
+    Snippet content and attachments stay encrypted under the master key,
+    exactly as stored. The master key itself is wrapped with AES-GCM under a
+    key derived from `passphrase` (PBKDF2-SHA256, random salt), and the KDF
+    parameters are recorded so the archive can be opened anywhere.
+    """
+    salt = gen_salt()
+    wrapping_key = derive_key(passphrase, salt, iterations=KDF_ITERATIONS)
+    wrapped_key, nonce = encrypt(master_key, wrapping_key)
+
+    return {
+        "format": ARCHIVE_FORMAT,
+        "version": ARCHIVE_VERSION,
+        "exported_at": datetime.now().isoformat(),
+        "kdf": {
+            "algorithm": "pbkdf2-sha256",
+            "iterations": KDF_ITERATIONS,
+            "salt": b64(salt),
+        },
+        "key": {"wrapped": b64(wrapped_key), "nonce": b64(nonce)},
+        "snippets": [
+            {
+                "name": record["name"],
+                "content_encrypted": record["content_encrypted"].decode("ascii"),
+                "content_type": record["content_type"],
+                "content_type_explicit": record["content_type_explicit"],
+                "kind": record["kind"],
+                "language": record["language"],
+                "description": record["description"],
+                "tags": record["tags"],
+                "created_at": record["created_at"],
+                "updated_at": record["updated_at"],
+                "attachments": [
+                    {"filename": filename, "data_encrypted": data.decode("ascii")}
+                    for filename, data in record["attachments"]
+                ],
+            }
+            for record in records
+        ],
+    }
+
+
+def open_archive(archive: dict, passphrase: str) -> Fernet:
+    """
+    Unwrap the master key of an archive made by build_archive.
+    Returns a Fernet for decrypting the archive's snippets and attachments.
+
+    Raises:
+        ValueError: If the archive is not recognized or the passphrase is wrong
+    """
+    if not isinstance(archive, dict) or archive.get("format") != ARCHIVE_FORMAT:
+        raise ValueError("Not a Macolint archive.")
+    if archive.get("version") != ARCHIVE_VERSION:
+        raise ValueError(f"Unsupported archive version: {archive.get('version')}.")
+
+    kdf = archive["kdf"]
+    if kdf.get("algorithm") != "pbkdf2-sha256":
+        raise ValueError(f"Unsupported key derivation: {kdf.get('algorithm')}.")
+
+    wrapping_key = derive_key(passphrase, ub64(kdf["salt"]), iterations=kdf["iterations"])
+    try:
+        master_key = decrypt(ub64(archive["key"]["wrapped"]), ub64(archive["key"]["nonce"]),
+                             wrapping_key)
+    except InvalidTag:
+        raise ValueError("Wrong passphrase or corrupted archive.")
+    return Fernet(master_key)
+
+
+def read_archive_snippets(archive: dict, fernet: Fernet) -> List[dict]:
+    """
+    Decrypt (in memory) the snippets of an opened archive into records for
+    Database.import_snippets.
+    """
+    records = []
+    for entry in archive.get("snippets", []):
+        records.append({
+            "name": entry["name"],
+            "content": unpack_content(fernet.decrypt(entry["content_encrypted"].encode("ascii"))),
+            "content_type": entry.get("content_type"),
+            # None in archives from before it was recorded
+            "content_type_explicit": entry.get("content_type_explicit"),
+            "kind": entry.get("kind") or "note",
+            "language": entry.get("language"),
+            "description": entry.get("description"),
+            "tags": entry.get("tags") or [],
+            "created_at": entry["created_at"],
+            "updated_at": entry["updated_at"],
+            "attachments": [
+                (attachment["filename"],
+                 fernet.decrypt(attachment["data_encrypted"].encode("ascii")))
+                for attachment in entry.get("attachments", [])
+            ],
+        })
+    return records
@@ -0,0 +1,92 @@
+"""Automatic copies of the snippet database (in backups/ beside it) and restoring them."""
+
+import os
+import time
+from datetime import datetime
+from pathlib import Path
+from typing import Callable, List, Optional
+
+from macolint.database import Database
+
+BACKUP_DIR_NAME = "backups"
+# Backup names sort oldest to newest: snippets-20240501-143000.db
+BACKUP_PREFIX = "snippets-"
+BACKUP_TIME_FORMAT = "%Y%m%d-%H%M%S"
+
+
+def backup_dir(db_path) -> Path:
+    """Directory holding the backups of the database at db_path."""
+    return Path(db_path).parent / BACKUP_DIR_NAME
+
+
+def list_backups(db_path) -> List[Path]:
+    """Backups of the database at db_path, newest first."""
+    directory = backup_dir(db_path)
+    if not directory.is_dir():
+        return []
+    return sorted(directory.glob(f"{BACKUP_PREFIX}*.db"), reverse=True)
+
+
+def make_backup(db: Database, keep: int, now: Optional[datetime] = None) -> Path:
+    """
+    Copy db into its backups directory under a timestamped name, then delete
+    all but the newest `keep` backups. Returns the new backup's path.
+    """
+    directory = backup_dir(db.db_path)
+    directory.mkdir(exist_ok=True, mode=0o700)
+    stamp = (now or datetime.now()).strftime(BACKUP_TIME_FORMAT)
+    path = directory / f"{BACKUP_PREFIX}{stamp}.db"
+    count = 1
+    while path.exists():
+        count += 1
+        path = directory / f"{BACKUP_PREFIX}{stamp}-{count}.db"
+
+    # Written under a temporary name so a crash never leaves a half backup
+    partial = path.with_name(path.name + ".tmp")
+    db.backup_to(partial)
+    os.chmod(partial, 0o600)
+    os.replace(partial, path)
+
+    for old in list_backups(db.db_path)[keep:]:
+        old.unlink()
+    return path
+
+
+def backup_if_due(db: Database, interval: int, keep: int) -> Optional[Path]:
+    """
+    Back up db (see make_backup) unless the newest backup is less than
+    `interval` seconds old. An interval of 0 turns backups off. Returns the
+    new backup's path, or None if none was made.
+    """
+    if interval <= 0 or keep <= 0 or not Path(db.db_path).exists():
+        return None
+    backups = list_backups(db.db_path)
+    if backups and time.time() - backups[0].stat().st_mtime < interval:
+        return None
+    return make_backup(db, keep)
+
+
+def restore_backup(db_path, source: Path, verify: Callable[[Path], None]) -> None:
+    """
+    Replace the database at db_path with a copy of source. The copy is
+    passed to verify (which raises if it cannot be opened) before anything
+    is replaced, and the swap is a single rename.
+    """
+    db_path = Path(db_path)
+    staged = db_path.with_name(db_path.name + ".restore")
+    staged.write_bytes(Path(source).read_bytes())
+    os.chmod(staged, 0o600)
+    try:
+        verify(staged)
+    except BaseException:
+        for path in (staged, Path(f"{staged}-wal"), Path(f"{staged}-shm")):
+            if path.exists():
+                path.unlink()
+        raise
+
+    # A WAL left by the current database must not be applied to the backup
+    for suffix in ("-wal", "-shm"):
+        stale = Path(f"{db_path}{suffix}")
+        if stale.exists():
+            stale.unlink()
+    os.replace(staged, db_path)
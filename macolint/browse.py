@@ -0,0 +1,182 @@
+"""Full-screen snippet browser with a live filter and preview (snip browse)."""
+
+from typing import Callable, List, Optional, Tuple
+
+from prompt_toolkit.application import Application
+from prompt_toolkit.buffer import Buffer
+from prompt_toolkit.data_structures import Point
+from prompt_toolkit.document import Document
+from prompt_toolkit.filters import has_focus
+from prompt_toolkit.key_binding import KeyBindings
+from prompt_toolkit.layout import HSplit, Layout, VSplit, Window
+from prompt_toolkit.layout.controls import BufferControl, FormattedTextControl
+from prompt_toolkit.layout.dimension import Dimension
+
+from macolint.interactive import fuzzy_match
+
+# (action, name, filter text) where action is "copy", "edit" or "delete"
+BrowseResult = Tuple[str, str, str]
+
+HELP_LINE = " ↑/↓ move  / filter  Enter copy  e edit  d delete  q quit "
+PAGE_SIZE = 10
+
+
+class SnippetBrowser:
+    """
+    Scrollable list of snippet names with a filter box and a preview pane.
+    `load_preview(name)` is only called for the highlighted name, and the
+    last result is kept, so moving through the list decrypts one snippet
+    at a time.
+    """
+
+    def __init__(
+        self,
+        names: List[str],
+        load_preview: Callable[[str], str],
+        filter_text: str = "",
+        selected: Optional[str] = None,
+    ):
+        self.names = names
+        self.load_preview = load_preview
+        self.matches: List[str] = fuzzy_match(filter_text, names)
+        self.index = self.matches.index(selected) if selected in self.matches else 0
+        self._preview: Tuple[Optional[str], str] = (None, "")
+
+        self.filter = Buffer(
+            document=Document(filter_text), multiline=False, on_text_changed=self._refilter
+        )
+        self.list_window = Window(
+            FormattedTextControl(
+                self._list_text,
+                focusable=True,
+                show_cursor=False,
+                get_cursor_position=lambda: Point(0, self.index),
+            ),
+            width=Dimension(weight=1),
+        )
+        self.app = Application(
+            layout=self._layout(),
+            key_bindings=self._key_bindings(),
+            full_screen=True,
+            mouse_support=False,
+        )
+
+    @property
+    def current(self) -> Optional[str]:
+        return self.matches[self.index] if self.matches else None
+
+    def _refilter(self, _buffer=None):
+        current = self.current
+        self.matches = fuzzy_match(self.filter.text.strip(), self.names)
+        self.index = self.matches.index(current) if current in self.matches else 0
+
+    def _move(self, offset: int):
+        if self.matches:
+            self.index = max(0, min(self.index + offset, len(self.matches) - 1))
+
+    def _list_text(self):
+        if not self.matches:
+            return [("class:empty", "  (no matches)")]
+        fragments = []
+        for idx, name in enumerate(self.matches):
+            style = "reverse" if idx == self.index else ""
+            fragments.append((style, f" {name} "))
+            fragments.append(("", "\n"))
+        return fragments[:-1]
+
+    def _preview_text(self):
+        name = self.current
+        if name is None:
+            return ""
+        if self._preview[0] != name:
+            self._preview = (name, self.load_preview(name))
+        return self._preview[1]
+
+    def _layout(self) -> Layout:
+        filter_row = VSplit([
+            Window(FormattedTextControl(" Filter: "), width=9, height=1),
+            Window(BufferControl(self.filter), height=1),
+        ])
+        body = VSplit([
+            self.list_window,
+            Window(width=1, char="│"),
+            Window(
+                FormattedTextControl(self._preview_text),
+                wrap_lines=True,
+                width=Dimension(weight=2),
+            ),
+        ])
+        status = Window(FormattedTextControl(HELP_LINE), height=1, style="reverse")
+        return Layout(
+            HSplit([filter_row, Window(height=1, char="─"), body, status]),
+            focused_element=self.list_window,
+        )
+
+    def _key_bindings(self) -> KeyBindings:
+        kb = KeyBindings()
+        on_list = has_focus(self.list_window)
+        on_filter = has_focus(self.filter)
+
+        @kb.add("up")
+        def _(event):
+            self._move(-1)
+
+        @kb.add("down")
+        def _(event):
+            self._move(1)
+
+        @kb.add("pageup")
+        def _(event):
+            self._move(-PAGE_SIZE)
+
+        @kb.add("pagedown")
+        def _(event):
+            self._move(PAGE_SIZE)
+
+        @kb.add("c-c")
+        def _(event):
+            event.app.exit(result=None)
+
+        @kb.add("/", filter=on_list)
+        def _(event):
+            event.app.layout.focus(self.filter)
+
+        @kb.add("enter", filter=on_filter)
+        @kb.add("escape", filter=on_filter)
+        def _(event):
+            event.app.layout.focus(self.list_window)
+
+        def finish(action: str):
+            def handler(event):
+                if self.current is not None:
+                    event.app.exit(result=(action, self.current, self.filter.text))
+            return handler
+
+        kb.add("enter", filter=on_list)(finish("copy"))
+        kb.add("e", filter=on_list)(finish("edit"))
+        kb.add("d", filter=on_list)(finish("delete"))
+
+        @kb.add("q", filter=on_list)
+        @kb.add("escape", filter=on_list)
+        def _(event):
+            event.app.exit(result=None)
+
+        return kb
+
+    def run(self) -> Optional[BrowseResult]:
+        return self.app.run()
+
+
+def browse_snippets(
+    names: List[str],
+    load_preview: Callable[[str], str],
+    filter_text: str = "",
+    selected: Optional[str] = None,
+) -> Optional[BrowseResult]:
+    """
+    Run the full-screen browser over `names`.
+    Returns (action, name, filter text) for the key pressed on a snippet, so
+    the caller can carry it out and reopen the browser where it left off, or
+    None if the user quit.
+    """
+    return SnippetBrowser(names, load_preview, filter_text, selected).run()
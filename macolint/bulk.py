@@ -0,0 +1,272 @@
+"""Parsing of bulk snippet records read from stdin, a batch document or a directory of files."""
+
+import json
+import os
+from dataclasses import dataclass
+from typing import Iterable, List, Optional, Tuple
+
+from macolint.detect import CONTENT_TYPES, language_from_filename
+from macolint.models import SNIPPET_KINDS, validate_name
+
+
+# Formats accepted by parse_document; 'auto' picks JSON or YAML from the text
+DOCUMENT_FORMATS = ("auto", "json", "yaml")
+
+
+@dataclass
+class BulkRecord:
+    """
+    One snippet to upsert, with the input line it came from (for a batch
+    document, its entry number).
+    """
+    line_number: int
+    name: str
+    content: str
+    content_type: Optional[str] = None
+    kind: Optional[str] = None
+    tags: Optional[List[str]] = None
+    language: Optional[str] = None
+    description: Optional[str] = None
+
+
+def parse_records(
+    lines: Iterable[str], jsonl: bool = False
+) -> Tuple[List[BulkRecord], List[Tuple[int, str]]]:
+    """
+    Parse bulk input into records.
+
+    Each non-blank line is either `name<TAB>content` or, with jsonl=True, a
+    JSON object with "name" and "content" plus optional "type" and "kind".
+    Tab-separated content is taken literally; use JSONL for multi-line content.
+
+    Returns (records, errors) where errors are (line_number, message) pairs.
+    """
+    records: List[BulkRecord] = []
+    errors: List[Tuple[int, str]] = []
+
+    for line_number, line in enumerate(lines, start=1):
+        line = line.rstrip("\r\n")
+        if not line.strip():
+            continue
+
+        try:
+            record = _parse_json_line(line) if jsonl else _parse_tsv_line(line)
+        except ValueError as e:
+            errors.append((line_number, str(e)))
+            continue
+
+        record.line_number = line_number
+        records.append(record)
+
+    return records, errors
+
+
+def _parse_tsv_line(line: str) -> BulkRecord:
+    if "\t" not in line:
+        raise ValueError("Expected 'name<TAB>content'.")
+    name, content = line.split("\t", 1)
+    return _validated(name.strip(), content)
+
+
+def _parse_json_line(line: str) -> BulkRecord:
+    try:
+        data = json.loads(line)
+    except ValueError as e:
+        raise ValueError(f"Invalid JSON: {e}")
+    if not isinstance(data, dict):
+        raise ValueError("Expected a JSON object.")
+
+    name = data.get("name")
+    content = data.get("content")
+    if not isinstance(name, str) or not isinstance(content, str):
+        raise ValueError("'name' and 'content' must be strings.")
+
+    content_type = data.get("type")
+    if content_type is not None and content_type not in CONTENT_TYPES:
+        raise ValueError(f"Unknown type '{content_type}'.")
+    kind = data.get("kind")
+    if kind is not None and kind not in SNIPPET_KINDS:
+        raise ValueError(f"Unknown kind '{kind}'.")
+
+    record = _validated(name.strip(), content)
+    record.content_type = content_type
+    record.kind = kind
+    return record
+
+
+def parse_document(
+    text: str, document_format: str = "auto"
+) -> Tuple[List[BulkRecord], List[Tuple[int, str]]]:
+    """
+    Parse a batch document: a JSON or YAML list of entries (or a mapping
+    with such a list under "snippets"). Each entry has "name" and "content",
+    plus optional "tags" (a list, or a comma-separated string), "language",
+    "description", "type" and "kind". Every entry is validated, so all problems are found
+    before anything is saved; a name used twice is an error too.
+
+    Returns (records, errors) where errors are (entry_number, message)
+    pairs; entry 0 means the document as a whole.
+    """
+    try:
+        data = _load_document(text, document_format)
+    except ValueError as e:
+        return [], [(0, str(e))]
+    if isinstance(data, dict) and "snippets" in data:
+        data = data["snippets"]
+    if not isinstance(data, list):
+        return [], [(0, "Expected a list of snippets (or a 'snippets' list).")]
+
+    records: List[BulkRecord] = []
+    errors: List[Tuple[int, str]] = []
+    names = set()
+    for number, entry in enumerate(data, start=1):
+        try:
+            record = _parse_entry(entry)
+        except ValueError as e:
+            errors.append((number, str(e)))
+            continue
+        if record.name in names:
+            errors.append((number, f"'{record.name}' appears more than once."))
+            continue
+        names.add(record.name)
+        record.line_number = number
+        records.append(record)
+
+    return records, errors
+
+
+def _load_document(text: str, document_format: str):
+    if document_format == "auto":
+        # JSON is a subset of YAML, but its own parser gives better errors
+        document_format = "json" if text.lstrip()[:1] in ("[", "{") else "yaml"
+    if document_format == "json":
+        try:
+            return json.loads(text)
+        except ValueError as e:
+            raise ValueError(f"Invalid JSON: {e}")
+
+    try:
+        import yaml
+    except ImportError:
+        raise ValueError("YAML input needs the PyYAML package (pip install pyyaml).")
+    try:
+        return yaml.safe_load(text)
+    except yaml.YAMLError as e:
+        raise ValueError(f"Invalid YAML: {e}")
+
+
+def _parse_entry(entry) -> BulkRecord:
+    if not isinstance(entry, dict):
+        raise ValueError("Expected an object with 'name' and 'content'.")
+
+    name = entry.get("name")
+    content = entry.get("content")
+    if not isinstance(name, str) or not isinstance(content, str):
+        raise ValueError("'name' and 'content' must be strings.")
+
+    tags = entry.get("tags")
+    if isinstance(tags, str):
+        tags = tags.split(",")
+    if tags is not None and (
+        not isinstance(tags, list) or not all(isinstance(tag, str) for tag in tags)
+    ):
+        raise ValueError("'tags' must be a list of strings.")
+    language = entry.get("language")
+    if language is not None and not isinstance(language, str):
+        raise ValueError("'language' must be a string.")
+    description = entry.get("description")
+    if description is not None and (not isinstance(description, str) or "\n" in description):
+        raise ValueError("'description' must be a single-line string.")
+    content_type = entry.get("type")
+    if content_type is not None and content_type not in CONTENT_TYPES:
+        raise ValueError(f"Unknown type '{content_type}'.")
+    kind = entry.get("kind")
+    if kind is not None and kind not in SNIPPET_KINDS:
+        raise ValueError(f"Unknown kind '{kind}'.")
+
+    record = _validated(name.strip(), content)
+    record.content_type = content_type
+    record.kind = kind
+    record.tags = tags
+    record.language = language.strip().lower() or None if language else None
+    record.description = description
+    return record
+
+
+def _validated(name: str, content: str) -> BulkRecord:
+    name = validate_name(name)
+    if not content.strip():
+        raise ValueError(f"Snippet '{name}' has empty content.")
+    return BulkRecord(line_number=0, name=name, content=content)
+
+
+@dataclass
+class FileRecord:
+    """One file to save as a snippet."""
+    path: str
+    name: str
+    content: str
+    language: Optional[str] = None
+
+
+def read_directory(
+    directory: str, recursive: bool = False, prefix: Optional[str] = None
+) -> Tuple[List[FileRecord], List[Tuple[str, str]]]:
+    """
+    Read the files in `directory` as snippets, sorted by path.
+
+    A file is named after its path relative to `directory` without the
+    extension (so 'db/users.sql' becomes 'db/users'), under `prefix` if
+    given. Hidden files and directories are ignored; subdirectories are
+    only read with recursive=True. Binary, non-UTF-8 and empty files and
+    invalid or duplicate names are skipped.
+
+    Returns (records, skipped) where skipped are (path, reason) pairs.
+    """
+    records: List[FileRecord] = []
+    skipped: List[Tuple[str, str]] = []
+    names = set()
+    prefix = prefix.strip().strip("/") if prefix else ""
+
+    for root, dirs, files in os.walk(directory):
+        dirs[:] = sorted(d for d in dirs if not d.startswith(".")) if recursive else []
+        for filename in sorted(files):
+            if filename.startswith("."):
+                continue
+            path = os.path.join(root, filename)
+            relative = os.path.relpath(path, directory)
+            stem, _ = os.path.splitext(relative)
+            name = "/".join(part for part in (prefix, stem.replace(os.sep, "/")) if part)
+
+            try:
+                with open(path, "rb") as f:
+                    data = f.read()
+            except OSError as e:
+                skipped.append((path, e.strerror or str(e)))
+                continue
+            if b"\0" in data:
+                skipped.append((path, "binary file"))
+                continue
+            try:
+                content = data.decode("utf-8")
+            except UnicodeDecodeError:
+                skipped.append((path, "not UTF-8 text"))
+                continue
+
+            try:
+                record = _validated(name, content)
+            except ValueError as e:
+                skipped.append((path, str(e)))
+                continue
+            if record.name in names:
+                skipped.append((path, f"another file is also named '{record.name}'"))
+                continue
+            names.add(record.name)
+            records.append(FileRecord(
+                path=path,
+                name=record.name,
+                content=content.rstrip("\n"),
+                language=language_from_filename(filename),
+            ))
+
+    return records, skipped
@@ -2,31 +2,157 @@
 
 import sys
 import os
+import json
 import shutil
+import sqlite3
 import click
+from dataclasses import dataclass
+from datetime import datetime, timedelta
 from pathlib import Path
+from typing import NoReturn, Optional, Tuple
 from rich.console import Console
-from macolint.database import Database
+from macolint import __version__
+from macolint.database import SYNC_STATUSES, SYNC_STRATEGIES, Database
+from macolint.detect import CONTENT_TYPES
+from macolint.models import SNIPPET_KINDS, parse_date_bound, parse_duration, validate_name
+from macolint.ranking import SEARCH_FIELDS
 from macolint.interactive import (
     prompt_snippet_name_simple,
+    prompt_snippet_names_multi,
     prompt_snippet_content,
     display_snippet_list,
     browse_module_tree,
     prompt_save_location,
     console,
+    NoSelectionError,
 )
 
 
-db = Database()
+err_console = Console(stderr=True)
+# Commands that honour the global --dry-run flag ("group command" for a subcommand)
+DRY_RUN_COMMANDS = {
+    'save', 'save-batch', 'delete', 'prune', 'import', 'import-dir', 'change-key',
+    'clip-history prune',
+}
+# Commands that can change snippets; an automatic backup is made first when due
+BACKUP_COMMANDS = {
+    'save', 'save-batch', 'edit', 'append', 'delete', 'prune', 'rename', 'copy', 'pin', 'unpin',
+    'restore', 'browse', 'clip-history', 'import', 'import-dir', 'sync', 'resolve', 'change-key',
+}
+
+
+# Exit codes by error kind (see _fail)
+ERROR_EXIT_CODES = {
+    "error": 1, "usage": 2, "not_found": 3, "conflict": 4, "decrypt": 5, "internal": 70,
+}
+
+
+@dataclass
+class RunContext:
+    """Global options shared by every command (the Click context object)."""
+    dry_run: bool = False
+    json: bool = False
+    clipboard_backend: Optional[str] = None
+    no_clipboard: bool = False
+
+
+def _json_mode(ctx: Optional[click.Context] = None) -> bool:
+    """True when the global --json flag was given."""
+    ctx = ctx or click.get_current_context(silent=True)
+    run = ctx.find_object(RunContext) if ctx is not None else None
+    return run is not None and run.json
+
+
+def _emit_error(message: str, kind: str) -> NoReturn:
+    """Write a --json error object to stderr and exit with the kind's code."""
+    if message.startswith("Error: "):
+        message = message[len("Error: "):]
+    click.echo(json.dumps({"error": message, "kind": kind}), err=True)
+    sys.exit(ERROR_EXIT_CODES[kind])
+
+
+def _fail(message: str, kind: str = "error", err: bool = False) -> NoReturn:
+    """
+    Report an error and exit with the code for `kind` (see ERROR_EXIT_CODES):
+    in red (on stderr with err=True), or under --json as
+    {"error": ..., "kind": ...} on stderr.
+    """
+    if _json_mode():
+        _emit_error(message, kind)
+    (err_console if err else console).print(f"[red]{message}[/red]")
+    sys.exit(ERROR_EXIT_CODES[kind])
+
+
+def _error_kind(error: Exception) -> str:
+    """The ERROR_EXIT_CODES kind for an exception caught by a command."""
+    from macolint.crypto import DecryptionError
+
+    return "decrypt" if isinstance(error, DecryptionError) else "error"
+
+
+def _report(message: str, **result) -> None:
+    """Print a success message, or under --json the result as a JSON object."""
+    if _json_mode():
+        click.echo(json.dumps({"ok": True, **result}))
+    else:
+        console.print(message)
+
+
+def _open_database() -> Optional[Database]:
+    """Open the active vault's database, exiting with an error if it can't be."""
+    try:
+        return Database()
+    except ValueError as e:
+        # e.g. an invalid MACOLINT_DATA_DIR, or a master key that doesn't match
+        # the database; report it instead of a traceback. `snip doctor` still
+        # runs, to show where the key comes from.
+        if "doctor" not in sys.argv[1:] or _error_kind(e) != "decrypt":
+            _fail(f"Error: {e}", _error_kind(e), err=True)
+        return None
+    except sqlite3.DatabaseError as e:
+        # A damaged database file; `snip doctor` opens it on its own to diagnose it
+        if "doctor" not in sys.argv[1:]:
+            _fail(
+                f"Error: Cannot open the snippet database ({e}). "
+                f"Run 'snip doctor' to check it.",
+                err=True
+            )
+        return None
+
+
+# With --data-dir the default location may not even be usable; the chosen
+# one is opened once the flag has been read (see _use_selected_database)
+if any(arg == "--data-dir" or arg.startswith("--data-dir=") for arg in sys.argv[1:]):
+    db = None
+else:
+    db = _open_database()
+
+
+def _use_selected_database():
+    """
+    Reopen `db` if --data-dir or --vault chose another database than the
+    one opened at startup.
+    """
+    global db
+    from macolint.config import get_db_path
+
+    if db is None or Path(db.db_path) != get_db_path():
+        db = _open_database()
 
 
 class MacolintGroup(click.Group):
     """Custom Click Group that organizes commands into sections in help output."""
     
     # Define command categories
-    SNIPPET_COMMANDS = {'save', 'get', 'edit', 'delete', 'rename', 'list'}
-    SETUP_COMMANDS = {'setup', 'doctor', 'update'}
-    CLOUD_SYNC_COMMANDS = {'auth', 'sync', 'set-passphrase'}
+    SNIPPET_COMMANDS = {
+        'save', 'save-batch', 'get', 'show', 'edit', 'append', 'delete', 'prune', 'rename', 'copy', 'pin', 'unpin', 'history',
+        'restore', 'list', 'recent', 'browse', 'clip-history', 'run', 'search', 'grep', 'stats', 'export', 'import', 'import-dir',
+    }
+    SETUP_COMMANDS = {
+        'setup', 'doctor', 'which', 'update', 'compact', 'restore-backup', 'change-key', 'completions', 'vault',
+        'serve', 'config', 'info',
+    }
+    CLOUD_SYNC_COMMANDS = {'auth', 'sync', 'conflicts', 'resolve', 'set-passphrase'}
     TEAM_COMMANDS = {'team', 'share', 'unshare'}
     
     def format_commands(self, ctx, formatter):
@@ -87,6 +213,34 @@ class MacolintGroup(click.Group):
             help = cmd.get_short_help_str(limit)
             formatter.write_dl([(name, help)])
 
+    def invoke(self, ctx):
+        """Under --json, report usage errors and unexpected exceptions as JSON too."""
+        try:
+            return super().invoke(ctx)
+        except click.UsageError as e:
+            if not _json_mode(ctx):
+                raise
+            _emit_error(e.format_message(), "usage")
+        except (click.exceptions.Exit, click.Abort):
+            raise
+        except click.ClickException as e:
+            if not _json_mode(ctx):
+                raise
+            _emit_error(e.format_message(), "error")
+        except Exception as e:
+            if not _json_mode(ctx):
+                raise
+            _emit_error(str(e) or type(e).__name__, "internal")
+
+
+def _complete_snippet_names(ctx, param, incomplete):
+    """Shell-completion callback offering snippet names."""
+    try:
+        _use_selected_database()
+        return [name for name in db.get_all_snippet_names() if name.startswith(incomplete)]
+    except Exception:
+        return []
+
 
 def output_snippet_for_shell_wrapper(content: str):
     """
@@ -188,11 +342,102 @@ def output_snippet_to_terminal(content: str, had_interactive_prompt: bool = Fals
         sys.stdout.flush()
 
 
+def _parse_fill(ctx, param, values):
+    """Turn repeated --fill NAME=VALUE options into a dict."""
+    filled = {}
+    for item in values:
+        key, sep, value = item.partition("=")
+        if not sep or not key.strip():
+            raise click.BadParameter(f"expected NAME=VALUE, got '{item}'.")
+        filled[key.strip()] = value
+    return filled
+
+
+def _select_vault(ctx, param, value):
+    """Switch every command to the chosen vault (--vault / SNIP_VAULT)."""
+    if not value:
+        return
+    from macolint.config import set_active_vault
+    try:
+        set_active_vault(value)
+    except ValueError as e:
+        raise click.BadParameter(str(e), ctx=ctx, param=param)
+
+
+def _select_data_dir(ctx, param, value):
+    """Keep all local data in the --data-dir directory for this run."""
+    if value:
+        from macolint.config import set_data_dir
+        set_data_dir(value)
+
+
 @click.group(cls=MacolintGroup)
-@click.version_option(version="0.1.0")
-def cli():
+@click.version_option(version=__version__)
+@click.option(
+    '--data-dir',
+    type=click.Path(file_okay=False),
+    callback=_select_data_dir,
+    expose_value=False,
+    is_eager=True,
+    help="Keep all local data (database, key, settings) in this directory for "
+         "this run. Overrides MACOLINT_DATA_DIR.",
+)
+@click.option(
+    '--vault',
+    envvar='SNIP_VAULT',
+    callback=_select_vault,
+    expose_value=False,
+    is_eager=True,
+    help="Use a separate vault (own database and master key). Env: SNIP_VAULT.",
+)
+@click.option(
+    '--dry-run',
+    is_flag=True,
+    help="Show what a save, save-batch, delete, prune, import, import-dir or change-key "
+         "would do without writing anything.",
+)
+@click.option(
+    '--json',
+    'json_output',
+    is_flag=True,
+    help='Report errors as {"error", "kind"} JSON on stderr (with an exit code per '
+         'kind), and results of save, delete, rename, copy, pin and list as JSON.',
+)
+@click.option(
+    '--clipboard-backend',
+    type=click.Choice(['auto', 'x11', 'wayland', 'osc52']),
+    help="Clipboard to copy to and read from: the platform's tool (auto), "
+         "x11 or wayland tools, or the terminal via OSC 52 (works over SSH). "
+         "Overrides clipboard_backend in settings.json.",
+)
+@click.option(
+    '--no-clipboard',
+    is_flag=True,
+    envvar='SNIP_NO_CLIPBOARD',
+    help="Never use a clipboard (servers, CI): get prints instead, and options "
+         "that need one are refused. Env: SNIP_NO_CLIPBOARD=1.",
+)
+@click.pass_context
+def cli(ctx, dry_run, json_output, clipboard_backend, no_clipboard):
     """Macolint - A cloud-synced terminal snippet manager."""
-    pass
+    ctx.obj = RunContext(
+        dry_run=dry_run, json=json_output, clipboard_backend=clipboard_backend,
+        no_clipboard=no_clipboard,
+    )
+    _use_selected_database()
+    if ctx.invoked_subcommand in BACKUP_COMMANDS and not dry_run and db is not None:
+        _auto_backup()
+    groups = {command.split()[0] for command in DRY_RUN_COMMANDS}
+    if dry_run and ctx.invoked_subcommand not in groups:
+        _refuse_dry_run(ctx.invoked_subcommand)
+
+
+def _refuse_dry_run(command: str):
+    """Stop with a usage error: the global --dry-run flag is not supported by command."""
+    raise click.UsageError(
+        f"--dry-run is not supported by 'snip {command}'. "
+        f"It works with: {', '.join(sorted(DRY_RUN_COMMANDS))}."
+    )
 
 
 @cli.command()
@@ -204,7 +449,101 @@ def cli():
     required=False,
     help="Create an empty module (or nested modules) without saving a snippet.",
 )
-def save(name, module_path):
+@click.option(
+    "--type",
+    "content_type",
+    type=click.Choice(CONTENT_TYPES, case_sensitive=False),
+    required=False,
+    help="Override the auto-detected content type.",
+)
+@click.option(
+    "--kind",
+    type=click.Choice(SNIPPET_KINDS, case_sensitive=False),
+    required=False,
+    help="Snippet kind: 'note' (default) or 'secret'.",
+)
+@click.option(
+    "--secret",
+    is_flag=True,
+    help="Save as a secret (same as --kind secret).",
+)
+@click.option(
+    "--tags",
+    required=False,
+    help="Comma-separated tags, e.g. 'rust,cli'. Replaces the snippet's existing tags.",
+)
+@click.option(
+    "--lang",
+    "language",
+    required=False,
+    help="Language of the content, e.g. 'rust' or 'sql' (guessed when omitted).",
+)
+@click.option(
+    "--desc",
+    "description",
+    required=False,
+    help="A short description shown by list and show (never copied by get).",
+)
+@click.option(
+    "--attach",
+    "attachments",
+    multiple=True,
+    type=click.Path(exists=True, dir_okay=False),
+    help="Attach a file to the snippet (encrypted). Can be repeated.",
+)
+@click.option(
+    "--from-file",
+    "from_file",
+    required=False,
+    type=click.Path(dir_okay=False),
+    help="Use the contents of a UTF-8 text file as the snippet body.",
+)
+@click.option(
+    "--replace-from-stdin",
+    "from_stdin",
+    is_flag=True,
+    help="Upsert many snippets from 'name<TAB>content' lines on stdin.",
+)
+@click.option(
+    "--jsonl",
+    is_flag=True,
+    help="With --replace-from-stdin, read JSON lines instead of tab-separated lines.",
+)
+@click.option(
+    "-f",
+    "--force",
+    is_flag=True,
+    help="Overwrite the snippet if it already exists.",
+)
+@click.option(
+    "--expires-in",
+    "expires_in",
+    required=False,
+    help="Expire the snippet after this long, e.g. 30m, 1h or 7d.",
+)
+@click.option(
+    "--protected",
+    is_flag=True,
+    help="Also encrypt the content under a passphrase, asked for on every get.",
+)
+@click.option(
+    "--allow-duplicate",
+    is_flag=True,
+    help="Don't warn when another snippet already has identical content.",
+)
+@click.option(
+    "--allow-large",
+    is_flag=True,
+    help="Save content larger than max_snippet_bytes (settings.json).",
+)
+@click.option(
+    "--raw",
+    is_flag=True,
+    help="Store the content byte for byte, without trimming whitespace or newlines.",
+)
+@click.pass_obj
+def save(run, name, module_path, content_type, kind, secret, tags, language, description,
+         attachments, from_file, from_stdin, jsonl, force, expires_in, protected, allow_duplicate, allow_large, raw):
     """
     Save a snippet or create an empty module.
     
@@ -221,46 +560,200 @@ def save(name, module_path):
         snip save
         # Navigate through modules, type snippet name to save
     
+      Save piped input (stdin is used as the content, no prompt):
+        kubectl get deploy -o yaml | snip save k8s/deploy_dump
+
+      Save a file's contents (no piping needed, e.g. on Windows):
+        snip save k8s/deploy --from-file deploy.yaml
+    
     \b
     CREATE MODULES:
       Create an empty module:
         snip save -m module1
         snip save -m module1/module2
     
+    \b
+    BULK SAVE:
+      Upsert many snippets from stdin in a single transaction:
+        generate-snippets | snip save --replace-from-stdin
+        generate-snippets-jsonl | snip save --replace-from-stdin --jsonl
+    
     \b
     OPTIONS:
       -m, --module MODULE_PATH    Create an empty module path instead of saving a snippet.
                                   Cannot be used together with NAME argument.
+      --type TYPE                 Override the auto-detected content type
+                                  (json, yaml, shell, sql, url, base64, binary, plaintext).
+      --kind KIND                 'note' (default) or 'secret'. Secrets are copied to the
+                                  clipboard and auto-cleared by `snip get` instead of printed.
+                                  Updating a snippet keeps its kind unless --kind is given.
+                                  `snip show`, pickers and previews display a secret as
+                                  "•••••• (hidden, use get to copy)".
+      --secret                    Same as --kind secret.
+      --tags TAGS                 Comma-separated tags (stored lowercase). Replaces the
+                                  snippet's tags; without it, existing tags are kept.
+                                  Filter with `snip list --tag TAG`.
+      --lang LANG                 Language of the content (e.g. rust, sql, bash). When
+                                  omitted it is guessed from a shebang line or common
+                                  keywords; once set it is kept until --lang changes it.
+      --desc TEXT                 A one-line reminder of what the snippet is for, shown by
+                                  `snip list` and `snip show` and matched by
+                                  `snip get --search description`. It is metadata only:
+                                  never part of the content, so never copied or printed
+                                  by `snip get`. Stored unencrypted, like tags. Updating a
+                                  snippet keeps its description; --desc "" removes it.
+      --attach FILE               Store FILE encrypted alongside the snippet. Repeat for
+                                  several files; a file with the same name replaces the
+                                  existing attachment. Extract with `snip get --extract-attachments`.
+      --from-file PATH            Use the contents of PATH (UTF-8 text) as the snippet
+                                  body instead of prompting; stdin is not read. When
+                                  --lang is omitted, the language comes from the file's
+                                  extension. Unlike --attach, the file becomes the
+                                  content itself. Cannot be used with -m/--module or
+                                  --replace-from-stdin.
+      --replace-from-stdin        Read one snippet per line as 'name<TAB>content' and save
+                                  them all at once. Existing snippets are overwritten.
+                                  --type and --kind apply to every record.
+      --jsonl                     Read {"name", "content", "type"?, "kind"?} JSON objects,
+                                  one per line (use this for multi-line content).
+      -f, --force                 Overwrite an existing snippet. Without it, saving to an
+                                  existing name is refused (use `snip edit` to change it).
+                                  The previous content stays in `snip history`.
+      --expires-in DURATION       Expire the snippet after DURATION (s, m, h or d units,
+                                  e.g. 30m, 1h, 7d, 1h30m). Expired snippets are treated
+                                  as deleted and removed by `snip prune`. Updating a
+                                  snippet keeps its expiry unless --expires-in is given.
+      --protected                 Ask for a passphrase and encrypt the content under it as
+                                  well as the master key. `snip get` and `snip edit` ask
+                                  for it again; the master key alone can't read it.
+                                  The passphrase cannot be recovered.
+      --allow-duplicate           Skip the warning printed when another snippet already
+                                  has exactly this content (also skipped with --force).
+      --allow-large               Save content over max_snippet_bytes (1 MB by default,
+                                  set in ~/.macolint/settings.json; 0 = no limit).
+                                  Without it, such a save is refused with its size.
+      --raw                       Store the content exactly as given: piped input keeps
+                                  its trailing newlines and line endings, and typed
+                                  content keeps surrounding whitespace. Without it,
+                                  trailing newlines of piped input (and whitespace around
+                                  typed content) are trimmed.
     
     \b
     EXAMPLES:
       # Save snippet at root
       snip save deploy_staging
+
+      # Save an API token as a secret
+      snip save tokens/github --secret
+
+      # Tag a snippet
+      snip save cargo_release --tags rust,cli
+
+      # Remind yourself what a cryptic name is for
+      snip save k8s/pfw --desc "Port-forward the staging database"
+
+      # Bundle a certificate and its key with a snippet
+      snip save certs/staging --attach ./cert.pem --attach ./key.pem
     
       # Save snippet in module (auto-creates module1 if needed)
       snip save git/commit/template
+
+      # Replace an existing snippet
+      snip save deploy_staging --force
+
+      # Keep a file's final newline
+      snip save configs/editorconfig --raw < .editorconfig
+
+      # Save a script; its language comes from the .py extension
+      snip save scripts/cleanup --from-file cleanup.py
+
+      # A one-off token that expires in an hour
+      snip save tokens/tmp --kind secret --expires-in 1h
+
+      # A recovery code that needs a passphrase on top of the master key
+      snip save backup/recovery_codes --kind secret --protected
     
       # Interactive: browse to save location
       snip save
     
       # Create empty module structure
       snip save -m project/frontend
+
+      # Check whether a save would create or overwrite
+      echo "new body" | snip --dry-run save deploy_staging --force
     """
     try:
+        if secret:
+            if kind and kind.lower() != "secret":
+                _fail("Error: --secret cannot be used with --kind note.", "usage")
+            kind = "secret"
+        if jsonl and not from_stdin:
+            _fail("Error: --jsonl requires --replace-from-stdin.", "usage")
+        if raw and (from_stdin or module_path is not None):
+            _fail("Error: --raw cannot be used with --replace-from-stdin or -m/--module.", "usage")
+        if from_file is not None and (from_stdin or module_path is not None):
+            _fail(
+                "Error: --from-file cannot be used with --replace-from-stdin or -m/--module.",
+                "usage"
+            )
+
+        # Bulk upsert: snip save --replace-from-stdin [--jsonl]
+        if from_stdin:
+            if (name or module_path is not None or attachments or tags is not None
+                    or language is not None or description is not None
+                    or expires_in is not None or protected):
+                _fail(
+                    "Error: --replace-from-stdin cannot be used with a NAME, -m/--module, "
+                    "--tags, --lang, --desc, --expires-in, --protected or --attach.",
+                    "usage"
+                )
+            _save_from_stdin(
+                jsonl,
+                content_type.lower() if content_type else None,
+                kind.lower() if kind else None,
+                dry_run=run.dry_run,
+                max_bytes=0 if allow_large else _max_snippet_bytes(),
+            )
+            return
+
         # Module-only creation: snip save -m module1/module2
         if module_path is not None:
             if name:
-                console.print(
-                    "[red]Error: When using -m/--module, do not also pass a snippet NAME.[/red]"
-                )
-                sys.exit(1)
+                _fail("Error: When using -m/--module, do not also pass a snippet NAME.", "usage")
 
+            module_path = validate_name(module_path)
+            if run.dry_run:
+                if db.get_module_by_path(module_path) is not None:
+                    console.print(f"[cyan]Module '{module_path}' already exists.[/cyan]")
+                else:
+                    console.print(f"[cyan]Would create module '{module_path}'.[/cyan]")
+                return
             db.create_module_path(module_path)
             console.print(
                 f"[green]Module '{module_path}' created successfully.[/green]"
             )
             return
 
+        # Parse the expiry before asking for content so a typo costs nothing
+        lifetime = parse_duration(expires_in) if expires_in is not None else None
+        if description is not None and "\n" in description:
+            _fail("Error: --desc must be a single line.", "usage")
+
+        # Read the file before asking for anything, so a bad path costs nothing
+        file_content = None
+        if from_file is not None:
+            file_content = _read_text_file(from_file)
+            if not name:
+                _fail("Error: Provide a snippet NAME when saving with --from-file.", "usage")
+            if language is None:
+                from macolint.detect import language_from_filename
+                language = language_from_filename(os.path.basename(from_file))
+
+        # Piped input (e.g. `make_config | snip save name`) is the snippet body
+        piped = file_content is None and not sys.stdin.isatty()
+        if piped and not name:
+            _fail("Error: Provide a snippet NAME when piping content into save.", "usage")
+
         # Snippet save (existing behaviour, now with path-aware names)
         # If name not provided, prompt for it with module navigation
         if not name:
@@ -268,23 +761,112 @@ def save(name, module_path):
             if not name:
                 console.print("[yellow]Cancelled.[/yellow]")
                 return
+        name = validate_name(name)
 
-        # Prompt for snippet content
-        content = prompt_snippet_content()
-        if content is None:
-            console.print("[yellow]Cancelled.[/yellow]")
-            return
+        # Refuse to clobber an existing snippet before asking for content
+        exists = db.snippet_exists(name)
+        if not force and exists:
+            _fail(
+                f"Error: Snippet '{name}' already exists. Use --force to overwrite it, "
+                f"or 'snip edit {name}' to change it.",
+                "conflict"
+            )
+
+        if file_content is not None:
+            content = file_content if raw else file_content.rstrip("\n")
+        elif piped and raw:
+            # Bytes as given: text-mode stdin translates line endings on Windows
+            try:
+                content = sys.stdin.buffer.read().decode("utf-8")
+            except UnicodeDecodeError:
+                _fail("Error: --raw content must be UTF-8 text; use --attach for binary files.")
+        elif piped:
+            content = sys.stdin.read().rstrip("\n")
+        else:
+            # Prompt for snippet content
+            content = prompt_snippet_content(strip=not raw)
+            if content is None:
+                console.print("[yellow]Cancelled.[/yellow]")
+                return
 
         if not content.strip():
-            console.print("[red]Error: Snippet content cannot be empty.[/red]")
+            _fail("Error: Snippet content cannot be empty.")
+
+        size = len(content.encode("utf-8"))
+        max_bytes = 0 if allow_large else _max_snippet_bytes()
+        if max_bytes and size > max_bytes:
+            _fail(
+                f"Error: Content is {_format_size(size)} ({size} bytes), over the "
+                f"max_snippet_bytes limit of {max_bytes}. Nothing was saved; "
+                f"use --allow-large to save it anyway."
+            )
+
+        if not (force or allow_duplicate):
+            # A warning only: saving the same content twice is sometimes intended
+            duplicates = [path for path in db.find_identical(content) if path != name]
+            if duplicates:
+                err_console.print(
+                    f"[yellow]Identical content already saved as "
+                    f"{', '.join(repr(path) for path in duplicates)}.[/yellow]"
+                )
+
+        if run.dry_run:
+            action = "overwrite" if exists else "create"
+            console.print(
+                f"[cyan]Would {action} snippet '{name}' "
+                f"({len(content.encode('utf-8'))} bytes).[/cyan]"
+            )
+            for path in attachments:
+                console.print(f"[cyan]Would attach '{os.path.basename(path)}'.[/cyan]")
             return
 
+        detected_type = None
+        if protected:
+            from macolint.crypto import protect
+            from macolint.detect import detect_content_type, detect_language
+
+            # Detect from the plaintext; the stored content is ciphertext
+            detected_type = detect_content_type(content)
+            language = language or detect_language(content)
+            content = protect(content, _prompt_passphrase(confirm=True))
+
         # Save the snippet (name may be a simple name or a module path)
-        created = db.save_snippet(name, content)
+        expires_at = datetime.now() + lifetime if lifetime else None
+        created = db.save_snippet(
+            name,
+            content,
+            content_type=content_type.lower() if content_type else None,
+            kind=kind.lower() if kind else None,
+            tags=tags.split(",") if tags is not None else None,
+            language=language.strip().lower() if language else None,
+            expires_at=expires_at,
+            description=description,
+            detected_type=detected_type,
+        )
+        files = []
+        for path in attachments:
+            with open(path, "rb") as f:
+                files.append((os.path.basename(path), f.read()))
+        if files:
+            db.set_attachments(name, files)
+
+        if _json_mode():
+            _report(
+                "",
+                name=name,
+                created=created,
+                expires_at=expires_at.isoformat(timespec="seconds") if expires_at else None,
+                attachments=[filename for filename, _ in files],
+            )
+            return
         if created:
             console.print(f"[green]Snippet '{name}' saved successfully.[/green]")
         else:
             console.print(f"[yellow]Snippet '{name}' updated successfully.[/yellow]")
+        if expires_at:
+            console.print(f"[dim]Expires at {expires_at.strftime('%Y-%m-%d %H:%M')}.[/dim]")
+        for filename, _ in files:
+            console.print(f"[green]✓ Attached '{filename}'[/green]")
         
         # Show sync hint if authenticated
         try:
@@ -296,12 +878,111 @@ def save(name, module_path):
             pass
 
     except Exception as e:
-        console.print(f"[red]Error: {e}[/red]")
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+def _read_text_file(path: str) -> str:
+    """
+    The contents of a UTF-8 text file given to save --from-file, read as
+    bytes so line endings are kept as they are on disk.
+    """
+    if not os.path.isfile(path):
+        _fail(f"Error: File not found: {path}", "not_found")
+    with open(path, "rb") as f:
+        data = f.read()
+    try:
+        return data.decode("utf-8")
+    except UnicodeDecodeError:
+        _fail(
+            f"Error: {path} is not UTF-8 text; use --attach to store binary files.",
+            "usage"
+        )
+
+
+def _prompt_passphrase(confirm: bool = False) -> str:
+    """
+    Ask for a protected snippet's passphrase on the terminal (not stdin,
+    which may carry piped content). With confirm, ask twice.
+    """
+    import getpass
+
+    passphrase = getpass.getpass("Snippet passphrase: ")
+    if not passphrase:
+        raise ValueError("A passphrase is required.")
+    if confirm and getpass.getpass("Repeat passphrase: ") != passphrase:
+        raise ValueError("Passphrases do not match.")
+    return passphrase
+
+
+def _unlock(snippet) -> Optional[str]:
+    """
+    Replace a protected snippet's content with its plaintext, asking for the
+    passphrase. Returns the passphrase, or None if the snippet isn't protected.
+
+    Raises:
+        ValueError: If the passphrase is wrong
+    """
+    if not snippet.protected:
+        return None
+    from macolint.crypto import unprotect
+
+    passphrase = _prompt_passphrase()
+    snippet.content = unprotect(snippet.content, passphrase)
+    return passphrase
+
+
+def _save_from_stdin(jsonl: bool, content_type, kind, dry_run: bool = False, max_bytes: int = 0):
+    """
+    Upsert every record read from stdin in one transaction and report a summary.
+    With dry_run the transaction is rolled back. Records over max_bytes
+    (if non-zero) are reported as failed. Exits with status 1 if any record
+    failed.
+    """
+    from macolint.bulk import parse_records
+
+    records, errors = parse_records(sys.stdin, jsonl=jsonl)
+    if max_bytes:
+        kept = []
+        for record in records:
+            size = len(record.content.encode("utf-8"))
+            if size > max_bytes:
+                errors.append((
+                    record.line_number,
+                    f"{record.name}: content is {_format_size(size)}, "
+                    f"over the {_format_size(max_bytes)} limit",
+                ))
+            else:
+                kept.append(record)
+        records = kept
+    results = db.save_snippets_bulk([
+        (record.name, record.content, record.content_type or content_type,
+         record.kind or kind, None, None, None)
+        for record in records
+    ], dry_run=dry_run)
+
+    created = updated = 0
+    for record, (was_created, error) in zip(records, results):
+        if error is not None:
+            errors.append((record.line_number, f"{record.name}: {error}"))
+        elif was_created:
+            created += 1
+        else:
+            updated += 1
+
+    for line_number, message in sorted(errors):
+        console.print(f"[red]Line {line_number}: {message}[/red]")
+
+    verb = "Would save" if dry_run else "Saved"
+    console.print(
+        f"[green]{verb} {created + updated} snippets ({created} created, {updated} updated)"
+        f"[/green]" + (f", [red]{len(errors)} failed[/red]" if errors else "")
+    )
+    if errors:
         sys.exit(1)
 
 
 @cli.command()
-@click.argument("name", required=False)
+@click.argument("name", required=False, shell_complete=_complete_snippet_names)
 @click.option(
     "--raw",
     is_flag=True,
@@ -319,7 +1000,98 @@ def save(name, module_path):
     required=False,
     help="Open an interactive browser inside a module (folders for snippets).",
 )
-def get(name, raw, interactive_name, module_path):
+@click.option(
+    "--clipboard",
+    "to_clipboard",
+    is_flag=True,
+    help="Copy the snippet to the system clipboard.",
+)
+@click.option(
+    "--osc52",
+    "to_osc52",
+    is_flag=True,
+    help="Copy the snippet to the terminal clipboard via OSC 52 (works over SSH).",
+)
+@click.option(
+    "-p",
+    "--print",
+    "--stdout",
+    "to_stdout",
+    is_flag=True,
+    help="Print the snippet to stdout (combine with --clipboard/--osc52).",
+)
+@click.option(
+    "--reveal",
+    is_flag=True,
+    help="Allow a secret snippet to be printed to the terminal.",
+)
+@click.option(
+    "--extract-attachments",
+    "extract_dir",
+    type=click.Path(file_okay=False),
+    required=False,
+    help="Write the snippet's attachments into this directory.",
+)
+@click.option(
+    "--clear",
+    "clear_secs",
+    type=click.IntRange(min=0),
+    required=False,
+    help="Copy to the system clipboard and clear it after this many seconds (0 = never).",
+)
+@click.option(
+    "--no-color",
+    is_flag=True,
+    help="Print without syntax highlighting (also: NO_COLOR environment variable).",
+)
+@click.option(
+    "--multi",
+    is_flag=True,
+    help="Pick several snippets (comma-separated) and output them joined together.",
+)
+@click.option(
+    "--separator",
+    default="\n\n",
+    show_default="blank line",
+    help="Text placed between snippets with --multi.",
+)
+@click.option(
+    "-o",
+    "--output",
+    "output_path",
+    type=click.Path(dir_okay=False),
+    required=False,
+    help="Write the snippet's content to this file.",
+)
+@click.option(
+    "--force",
+    is_flag=True,
+    help="Overwrite the --output file if it already exists.",
+)
+@click.option(
+    "--fill",
+    "fill_values",
+    multiple=True,
+    metavar="NAME=VALUE",
+    callback=_parse_fill,
+    help="Value for a {{NAME}} placeholder; the rest are prompted for. Can be repeated.",
+)
+@click.option(
+    "--show-placeholders",
+    is_flag=True,
+    help="List the snippet's {{placeholders}} instead of retrieving it.",
+)
+@click.option(
+    "--search",
+    "search_fields",
+    type=click.Choice(SEARCH_FIELDS),
+    default="name",
+    show_default=True,
+    help="What the fuzzy search matches: names, tags (and language), descriptions, or all.",
+)
+def get(name, raw, interactive_name, module_path, to_clipboard, to_osc52, to_stdout, reveal,
+        extract_dir, clear_secs, no_color, multi, separator, output_path, force, fill_values,
+        show_placeholders, search_fields):
     """
     Retrieve a snippet by name or browse modules interactively.
     
@@ -337,12 +1109,17 @@ def get(name, raw, interactive_name, module_path):
       Fuzzy search (shows all snippets):
         snip get
         # Type to filter, tab to complete, select snippet
+        # Ranked by match, recency and usage (weights in ~/.macolint/settings.json)
     
       Browse modules (folder-style navigation):
         snip get -m              # Start at root
         snip get -m module1      # Start inside module1
         # Select modules (ending with /) to enter, select snippets to retrieve
         # Press Esc to go up one level
+
+      Pick several snippets:
+        snip get --multi
+        # Enter names separated by commas; each entry completes on its own
     
     \b
     OPTIONS:
@@ -356,6 +1133,70 @@ def get(name, raw, interactive_name, module_path):
       --interactive-name             Output only the selected name (for shell wrapper).
                                      Used internally, not typically used directly.
     
+      --clipboard                    Copy the snippet to the system clipboard.
+      --osc52                        Copy the snippet to the terminal clipboard (OSC 52).
+      -p, --print, --stdout          Print the snippet to stdout. No clipboard tool is
+                                     touched unless --clipboard is also given, so this
+                                     works on headless servers and over SSH.
+                                     These can be combined; each target is applied in turn.
+                                     Without any of them the snippet is printed.
+
+      --reveal                       Print a secret snippet to the terminal.
+
+      --extract-attachments DIR      Write the snippet's attachments into DIR (created if
+                                     needed). Existing files are never overwritten.
+
+      --clear SECS                   Clear the system clipboard SECS seconds after copying,
+                                     for any snippet (0 = never). Implies --clipboard when
+                                     no other target is given.
+
+      --no-color                     Don't syntax-highlight printed snippets. Highlighting
+                                     uses the snippet's language and only happens when
+                                     stdout is a terminal; NO_COLOR also disables it.
+
+      --multi                        Pick several snippets in the fuzzy finder and output
+                                     them joined together. If any of them is a secret the
+                                     result is handled as a secret.
+      --separator TEXT               Text between snippets with --multi (default: blank line).
+
+      -o, --output FILE              Write the content to FILE, byte for byte (trailing
+                                     newlines included), instead of printing it. Refuses
+                                     to replace an existing file unless --force is given.
+                                     Combine with --clipboard/--print to do both.
+
+      --fill NAME=VALUE              Replace {{NAME}} in the content with VALUE before it
+                                     is output. Repeat for several placeholders; values
+                                     for the rest are asked for on the terminal (without
+                                     one, get fails). Without --fill the content is
+                                     output unchanged, {{ }} and all.
+      --show-placeholders            List the {{placeholders}} the snippet contains and
+                                     exit without outputting or copying it.
+
+      --search name|tags|description|all
+                                     What the fuzzy search (snip get with no NAME) matches:
+                                     names (default), tags and language, descriptions
+                                     (snip save --desc), or all of them. Except with
+                                     name, suggestions show
+                                     'name  [tags]  (lang)  - description'; a name match
+                                     still ranks above a tag or description match. The
+                                     description is never part of what get outputs.
+    
+    \b
+    SECRETS:
+      Snippets saved with --kind secret are copied to the system clipboard by
+      default and the clipboard is cleared again after clipboard_clear_secs
+      seconds (default 30, set in ~/.macolint/settings.json). They are never
+      printed to a terminal unless --reveal is given (piping with --print is allowed).
+      Notes print by default and are only cleared from the clipboard with --clear.
+      The clipboard is not cleared if you copied something else in the meantime.
+
+    \b
+    NO CLIPBOARD:
+      Without a usable system clipboard (headless/SSH sessions, or no wl-copy,
+      xclip or xsel installed), --clipboard prints the snippet to stdout instead
+      and warns on stderr. Secrets are not printed to a terminal this way; use
+      --osc52 or --reveal.
+    
     \b
     EXAMPLES:
       # Direct retrieval
@@ -364,33 +1205,76 @@ def get(name, raw, interactive_name, module_path):
     
       # Interactive fuzzy search
       snip get
+      snip get --search all     # typing 'rust' also finds snippets tagged rust
+      snip get --search description   # find a snippet by what it is for
     
       # Browse modules interactively
       snip get -m
+
+      # Copy several snippets at once
+      snip get --multi --clipboard
       snip get -m git
+    
+      # Copy to every clipboard at once and print
+      snip get deploy_staging --clipboard --osc52 --print
+
+      # Show a secret on screen
+      snip get tokens/github --reveal
+
+      # Copy a note and clear the clipboard after 10 seconds
+      snip get db/password --clear 10
+
+      # Extract bundled files
+      snip get certs/staging --extract-attachments ./certs
+
+      # Materialize a config template
+      snip get dockerfile --output Dockerfile
+
+      # Fill in a template ("ssh {{user}}@{{host}}")
+      snip get ssh/login --fill host=prod01 --fill user=deploy
+      snip get ssh/login --show-placeholders
     """
     try:
+        if to_clipboard:
+            _refuse_clipboard("--clipboard")
+        if to_osc52:
+            _refuse_clipboard("--osc52")
+        if clear_secs is not None:
+            _refuse_clipboard("--clear")
+        if multi:
+            if name or module_path is not None or raw or interactive_name or extract_dir:
+                _fail(
+                    "Error: --multi cannot be used with NAME, --module, --raw, "
+                    "--interactive-name or --extract-attachments.",
+                    "usage"
+                )
+            if output_path or fill_values or show_placeholders:
+                _fail(
+                    "Error: --multi cannot be used with --output, --fill or --show-placeholders.",
+                    "usage"
+                )
+            _get_multi(to_clipboard, to_osc52, to_stdout, reveal, clear_secs, separator)
+            return
+
+        if output_path and (raw or interactive_name):
+            _fail("Error: --output cannot be used with --raw or --interactive-name.", "usage")
+        if force and not output_path:
+            _fail("Error: --force only applies to --output.", "usage")
+        if search_fields != "name" and (name or module_path is not None or multi):
+            _fail("Error: --search only applies to the fuzzy search (no NAME, -m or --multi).", "usage")
+
         # Module-browsing mode: snip get -m [module_path]
         if module_path is not None:
             if raw or interactive_name:
-                console.print(
-                    "[red]Error: --module cannot be used with --raw or --interactive-name.[/red]"
-                )
-                sys.exit(1)
+                _fail("Error: --module cannot be used with --raw or --interactive-name.", "usage")
             if name:
-                console.print(
-                    "[red]Error: When using -m/--module, do not pass a snippet NAME.[/red]"
-                )
-                sys.exit(1)
+                _fail("Error: When using -m/--module, do not pass a snippet NAME.", "usage")
 
             # Validate starting module path (if provided)
             if module_path:
                 start_module = db.get_module_by_path(module_path)
                 if start_module is None:
-                    console.print(
-                        f"[red]Module '{module_path}' not found.[/red]"
-                    )
-                    sys.exit(1)
+                    _fail(f"Module '{module_path}' not found.", "not_found")
 
             selected_path = browse_module_tree(
                 db, root_module_path=module_path if module_path else None
@@ -415,7 +1299,22 @@ def get(name, raw, interactive_name, module_path):
             # When --interactive-name is set, the shell wrapper is calling this via command substitution
             # The prompt should still work because stdin is still the terminal
             try:
-                name = prompt_snippet_name_simple(snippet_names)
+                from macolint.config import load_settings
+                from macolint.ranking import DEFAULT_AUTO_SELECT_GAP
+                settings = load_settings()
+                ranking = (db.get_ranking_metadata(), settings["fuzzy_weights"])
+                auto_select_gap = settings["fuzzy_auto_select_gap"]
+                if not isinstance(auto_select_gap, (int, float)) or isinstance(auto_select_gap, bool):
+                    auto_select_gap = DEFAULT_AUTO_SELECT_GAP
+                name = prompt_snippet_name_simple(
+                    snippet_names, ranking=ranking, preview=_snippet_preview,
+                    pinned=set(db.get_pinned_snippets()),
+                    auto_select_gap=auto_select_gap,
+                    terms=db.get_search_terms() if search_fields != "name" else None,
+                    search=search_fields,
+                )
+            except NoSelectionError:
+                raise
             except Exception as prompt_error:
                 # If interactive prompt fails, check if it's a TTY issue
                 error_str = str(prompt_error).lower()
@@ -470,11 +1369,12 @@ def get(name, raw, interactive_name, module_path):
                 return
 
         # Retrieve the snippet (supports hierarchical module paths)
+        name = _lookup_name(name)
         snippet = db.get_snippet(name)
         if snippet is None:
             if raw:
                 # In raw mode, output nothing on error
-                sys.exit(1)
+                sys.exit(ERROR_EXIT_CODES["not_found"])
             
             # Check if user is authenticated and prompt for cloud pull
             try:
@@ -494,8 +1394,10 @@ def get(name, raw, interactive_name, module_path):
                                     # Try to get the snippet again
                                     snippet = db.get_snippet(name)
                                     if snippet is None:
-                                        console.print(f"[red]Snippet '{name}' still not found after sync.[/red]")
-                                        sys.exit(1)
+                                        _fail(
+                                            f"Snippet '{name}' still not found after sync.",
+                                            "not_found"
+                                        )
                                     # Continue to output snippet below
                                 else:
                                     console.print(f"[yellow]No snippets pulled. Snippet '{name}' not found.[/yellow]")
@@ -504,41 +1406,621 @@ def get(name, raw, interactive_name, module_path):
                                 console.print("[yellow]Passphrase required. Cancelled.[/yellow]")
                                 sys.exit(1)
                         except Exception as e:
-                            console.print(f"[red]Error pulling from cloud: {e}[/red]")
-                            sys.exit(1)
+                            _fail(f"Error pulling from cloud: {e}")
                     else:
-                        console.print(f"[red]Snippet '{name}' not found.[/red]")
-                        sys.exit(1)
+                        _fail(f"Snippet '{name}' not found.", "not_found")
                 else:
-                    console.print(f"[red]Snippet '{name}' not found.[/red]")
-                    sys.exit(1)
+                    _fail(f"Snippet '{name}' not found.", "not_found")
             except Exception:
                 # If auth check fails, just show not found
-                console.print(f"[red]Snippet '{name}' not found.[/red]")
-                sys.exit(1)
+                _fail(f"Snippet '{name}' not found.", "not_found")
+
+        _unlock(snippet)
+
+        if show_placeholders:
+            _print_placeholders(snippet.content)
+            return
+        # Only on request: {{ }} is common in content that is not a template
+        # (GitHub Actions, Jinja, Helm), which must come out unchanged
+        if fill_values:
+            snippet.content = _fill_template(name, snippet.content, fill_values, quiet=raw)
 
         # Output the snippet content
         # If --raw flag is set, output without newline (for shell wrapper)
         # Otherwise, output with newline for direct use
-        if raw:
+        if extract_dir and not raw:
+            _extract_attachments(name, extract_dir)
+
+        is_secret = snippet.kind == "secret"
+        if output_path:
+            _write_output_file(name, snippet.content, output_path, force, private=is_secret)
+        if clear_secs is not None:
+            clear_after = clear_secs
+        elif is_secret:
+            clear_after = _secret_clear_seconds()
+        else:
+            clear_after = 0
+        if clear_secs is not None and not raw and not (to_clipboard or to_osc52 or to_stdout):
+            to_clipboard = True
+
+        if raw and is_secret and not reveal:
+            # Never insert a secret into the command line (and shell history)
+            _copy_secret(name, snippet.content, clear_after, raw=True)
+        elif raw:
             output_snippet_for_shell_wrapper(snippet.content)
+        elif to_clipboard or to_osc52 or to_stdout:
+            if is_secret and to_stdout and sys.stdout.isatty() and not reveal:
+                _fail(f"Error: '{name}' is a secret; use --reveal to print it to the terminal.")
+            _output_to_sinks(
+                name, snippet.content, to_clipboard, to_osc52, to_stdout,
+                clear_after=clear_after, language=None if no_color else snippet.language,
+                fallback_print=not is_secret or reveal or not sys.stdout.isatty(),
+            )
+        elif output_path:
+            # Written to the file only
+            pass
+        elif is_secret and not reveal:
+            _copy_secret(name, snippet.content, clear_after)
         else:
             # When called directly (not through shell wrapper), print with newline
             # so the content is visible after the interactive prompt
-            content = snippet.content.rstrip()
-            print(content)
+            _print_content(snippet.content.rstrip(), None if no_color else snippet.language)
+
+        try:
+            db.record_snippet_use(name, track_access=_track_access())
+        except Exception:
+            # Usage tracking only affects search ranking; never fail a get over it
+            pass
 
+    except NoSelectionError as e:
+        if not (raw or interactive_name):
+            _fail(f"Error: {e}")
+        sys.exit(1)
     except Exception as e:
         if raw or interactive_name:
             # In raw/interactive-name mode, don't output error messages
+            sys.exit(ERROR_EXIT_CODES[_error_kind(e)])
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+def _get_multi(to_clipboard, to_osc52, to_stdout, reveal, clear_secs, separator):
+    """
+    `snip get --multi`: pick several snippets and output their contents joined
+    by `separator`. The result counts as a secret if any picked snippet is one.
+    """
+    from macolint.config import load_settings
+
+    snippet_names = db.get_all_snippet_names()
+    if not snippet_names:
+        console.print("[yellow]No snippets found.[/yellow]")
+        return
+
+    ranking = (db.get_ranking_metadata(), load_settings()["fuzzy_weights"])
+    names = prompt_snippet_names_multi(
+        snippet_names, ranking=ranking, preview=_snippet_preview,
+        pinned=set(db.get_pinned_snippets()),
+    )
+    if not names:
+        console.print("[yellow]Cancelled.[/yellow]")
+        return
+
+    snippets = []
+    for selected in names:
+        snippet = db.get_snippet(selected)
+        if snippet is None:
+            _fail(f"Snippet '{selected}' not found.", "not_found")
+        _unlock(snippet)
+        snippets.append(snippet)
+
+    content = separator.join(snippet.content.rstrip("\n") for snippet in snippets)
+    label = ", ".join(names)
+    is_secret = any(snippet.kind == "secret" for snippet in snippets)
+    if clear_secs is not None:
+        clear_after = clear_secs
+    elif is_secret:
+        clear_after = _secret_clear_seconds()
+    else:
+        clear_after = 0
+    if clear_secs is not None and not (to_clipboard or to_osc52 or to_stdout):
+        to_clipboard = True
+
+    if to_clipboard or to_osc52 or to_stdout:
+        if is_secret and to_stdout and sys.stdout.isatty() and not reveal:
+            _fail(
+                "Error: the selection includes a secret; use --reveal to print it "
+                "to the terminal."
+            )
+        _output_to_sinks(
+            label, content, to_clipboard, to_osc52, to_stdout, clear_after=clear_after,
+            fallback_print=not is_secret or reveal or not sys.stdout.isatty(),
+        )
+    elif is_secret and not reveal:
+        _copy_secret(label, content, clear_after)
+    else:
+        _print_content(content)
+
+    track_access = _track_access()
+    for selected in names:
+        try:
+            db.record_snippet_use(selected, track_access=track_access)
+        except Exception:
+            pass
+
+
+def _snippet_preview(name: str) -> str:
+    """First lines of a snippet for the interactive picker ('' if unavailable)."""
+    from macolint.formatting import preview_text
+    try:
+        snippet = db.get_snippet(name)
+    except Exception:
+        return ""
+    if snippet is None:
+        return ""
+    return preview_text(snippet.content, snippet.kind)
+
+
+def _extract_attachments(name: str, directory: str):
+    """
+    Write a snippet's attachments into directory, refusing to overwrite files.
+    Status messages go to stderr. Exits with status 1 on any failure.
+    """
+    attachments = db.get_attachments(name)
+    if not attachments:
+        err_console.print(f"[yellow]Snippet '{name}' has no attachments.[/yellow]")
+        return
+
+    target_dir = Path(directory)
+    target_dir.mkdir(parents=True, exist_ok=True)
+
+    failed = False
+    for filename, data in attachments:
+        target = target_dir / os.path.basename(filename)
+        try:
+            fd = os.open(target, os.O_WRONLY | os.O_CREAT | os.O_EXCL, 0o600)
+        except FileExistsError:
+            failed = True
+            err_console.print(f"[red]Error: '{target}' already exists; not overwriting.[/red]")
+            continue
+        with os.fdopen(fd, "wb") as f:
+            f.write(data)
+        err_console.print(f"[green]✓ Extracted '{target}'[/green]")
+
+    if failed:
+        sys.exit(1)
+
+
+def _print_placeholders(content: str):
+    """Print the {{placeholder}} names in content, one per line (a JSON list with --json)."""
+    from macolint.templates import find_placeholders
+
+    placeholders = find_placeholders(content)
+    if _json_mode():
+        print(json.dumps(placeholders))
+    elif placeholders:
+        for placeholder in placeholders:
+            print(placeholder)
+    else:
+        err_console.print("[yellow]No placeholders.[/yellow]")
+
+
+def _fill_template(name: str, content: str, values: dict, quiet: bool = False) -> str:
+    """
+    Substitute {{placeholders}} in content from values, asking on the terminal
+    (via stderr) for any without a value. Content without placeholders is
+    returned unchanged. Exits
+    with an error for a value no placeholder uses, or when a value is missing
+    and there is no terminal to ask on.
+    """
+    from macolint.templates import fill_placeholders, find_placeholders
+
+    placeholders = find_placeholders(content)
+    unknown = [key for key in values if key not in placeholders]
+    if unknown:
+        if quiet:
+            sys.exit(1)
+        _fail(f"Error: '{name}' has no placeholder {', '.join(unknown)}.", "usage")
+    if not placeholders:
+        return content
+
+    missing = [placeholder for placeholder in placeholders if placeholder not in values]
+    if missing and not sys.stdin.isatty():
+        if quiet:
             sys.exit(1)
-        console.print(f"[red]Error: {e}[/red]")
+        _fail(
+            f"Error: No value for {', '.join(missing)}; pass --fill NAME=VALUE.",
+            "usage"
+        )
+    filled = dict(values)
+    for placeholder in missing:
+        filled[placeholder] = click.prompt(placeholder, err=True)
+    return fill_placeholders(content, filled)
+
+
+def _write_output_file(name: str, content: str, path: str, force: bool, private: bool = False):
+    """
+    Write snippet content to path exactly as stored (no newline is added or
+    stripped). An existing file is only replaced with force; private content
+    (secrets) gets a file only the owner can read. Status goes to stderr.
+    """
+    flags = os.O_WRONLY | os.O_CREAT | (os.O_TRUNC if force else os.O_EXCL)
+    try:
+        fd = os.open(path, flags, 0o600 if private else 0o666)
+    except FileExistsError:
+        _fail(f"Error: '{path}' already exists; use --force to overwrite it.", "conflict")
+    except OSError as e:
+        _fail(f"Error: Could not write '{path}': {e.strerror}")
+    with os.fdopen(fd, "wb") as f:
+        f.write(content.encode("utf-8"))
+    err_console.print(f"[green]✓ Wrote '{name}' to {path}[/green]")
+
+
+def _time_format(relative: bool = False) -> str:
+    """Timestamp style for output: 'relative' if asked for, else time_format from settings.json."""
+    from macolint.config import load_settings
+    from macolint.formatting import TIME_FORMATS
+
+    if relative:
+        return "relative"
+    style = load_settings().get("time_format")
+    return style if style in TIME_FORMATS else "local"
+
+
+def _track_access() -> bool:
+    """Whether gets record a last-access time ("track_access" in settings.json)."""
+    from macolint.config import load_settings
+
+    return load_settings().get("track_access") is not False
+
+
+def _max_snippet_bytes() -> int:
+    """Size limit for saved content ("max_snippet_bytes" in settings.json, 0 = none)."""
+    from macolint.config import DEFAULT_SETTINGS, load_settings
+
+    limit = load_settings().get("max_snippet_bytes")
+    if isinstance(limit, bool) or not isinstance(limit, int) or limit < 0:
+        return DEFAULT_SETTINGS["max_snippet_bytes"]
+    return limit
+
+
+def _no_clipboard() -> bool:
+    """True when the global --no-clipboard flag (or SNIP_NO_CLIPBOARD) is set."""
+    ctx = click.get_current_context(silent=True)
+    run = ctx.find_object(RunContext) if ctx is not None else None
+    return run is not None and run.no_clipboard
+
+
+def _refuse_clipboard(option: str) -> None:
+    """Exit with a usage error if `option` needs a clipboard under --no-clipboard."""
+    if _no_clipboard():
+        _fail(f"Error: {option} needs a clipboard, but --no-clipboard is set.", "usage")
+
+
+def _copy_secret(name: str, content: str, clear_after: int, raw: bool = False) -> None:
+    """
+    Send a secret where secrets go by default: the clipboard. Under
+    --no-clipboard it is printed instead, but only to a pipe or file, and
+    never for --raw (the shell wrapper would put it on the command line).
+    """
+    if not _no_clipboard():
+        _output_to_sinks(name, content, True, False, False, clear_after=clear_after)
+    elif raw or sys.stdout.isatty():
+        _fail(
+            f"Error: '{name}' is a secret and --no-clipboard is set; use --reveal to print it.",
+            err=raw,
+        )
+    else:
+        _print_content(content.rstrip())
+
+
+def _clipboard_backend() -> str:
+    """
+    The clipboard backend to use: the global --clipboard-backend flag, else
+    "clipboard_backend" in settings.json (see clipboard.CLIPBOARD_BACKENDS).
+    """
+    from macolint.clipboard import CLIPBOARD_BACKENDS
+    from macolint.config import DEFAULT_SETTINGS, load_settings
+
+    ctx = click.get_current_context(silent=True)
+    run = ctx.find_object(RunContext) if ctx is not None else None
+    if run is not None and run.clipboard_backend:
+        return run.clipboard_backend
+    backend = load_settings().get("clipboard_backend")
+    if backend not in CLIPBOARD_BACKENDS:
+        return DEFAULT_SETTINGS["clipboard_backend"]
+    return backend
+
+
+def _clip_history_limits() -> Tuple[int, Optional[timedelta]]:
+    """
+    Retention of snip clip-history: ("clip_history_size", and
+    "clip_history_max_age_secs" as a timedelta or None) from settings.json.
+    """
+    from macolint.config import DEFAULT_SETTINGS, load_settings
+
+    settings = load_settings()
+    size = settings.get("clip_history_size")
+    if isinstance(size, bool) or not isinstance(size, int) or size < 1:
+        size = DEFAULT_SETTINGS["clip_history_size"]
+    max_age = settings.get("clip_history_max_age_secs")
+    if isinstance(max_age, bool) or not isinstance(max_age, int) or max_age < 0:
+        max_age = DEFAULT_SETTINGS["clip_history_max_age_secs"]
+    return size, timedelta(seconds=max_age) if max_age else None
+
+
+def _backup_settings() -> Tuple[int, int]:
+    """(backup_interval_secs, backup_keep) from settings.json."""
+    from macolint.config import DEFAULT_SETTINGS, load_settings
+
+    settings = load_settings()
+    values = []
+    for key in ("backup_interval_secs", "backup_keep"):
+        value = settings.get(key)
+        if isinstance(value, bool) or not isinstance(value, int) or value < 0:
+            value = DEFAULT_SETTINGS[key]
+        values.append(value)
+    return values[0], values[1]
+
+
+def _auto_backup():
+    """Back up the database before a write when one is due (see macolint.backup)."""
+    from macolint.backup import backup_if_due
+
+    interval, keep = _backup_settings()
+    try:
+        backup_if_due(db, interval, keep)
+    except Exception as e:
+        # A failed backup must not stop the command itself
+        err_console.print(f"[yellow]Warning: Automatic backup failed: {e}[/yellow]")
+
+
+def _lookup_name(name: str) -> str:
+    """
+    The snippet meant by a typed name. Names match exactly unless the
+    name_lookup setting is "insensitive": then a name with no exact match
+    finds the snippet equal to it ignoring case and accents, and when there
+    are several, asks which one (a usage error without a terminal).
+    Returns `name` itself when nothing matches, for the caller to report.
+    """
+    from macolint.config import load_settings
+
+    if load_settings().get("name_lookup") != "insensitive" or db.snippet_exists(name):
+        return name
+    variants = db.find_name_variants(name)
+    if len(variants) <= 1:
+        return variants[0] if variants else name
+    if not sys.stdin.isatty():
+        _fail(
+            f"Error: '{name}' matches several snippets: {', '.join(variants)}. "
+            "Give the exact name.",
+            "usage"
+        )
+    from macolint.interactive import prompt_name_variant
+
+    chosen = prompt_name_variant(name, variants)
+    if chosen is None:
+        console.print("[yellow]Cancelled.[/yellow]")
+        sys.exit(1)
+    return chosen
+
+
+def _secret_clear_seconds() -> int:
+    """Seconds before a copied secret is cleared, from settings.json."""
+    from macolint.clipboard import DEFAULT_CLEAR_SECONDS
+    from macolint.config import load_settings
+
+    seconds = load_settings().get("clipboard_clear_secs")
+    if isinstance(seconds, bool) or not isinstance(seconds, int) or seconds < 0:
+        return DEFAULT_CLEAR_SECONDS
+    return seconds
+
+
+def _print_content(content: str, language: Optional[str] = None):
+    """
+    Print snippet content to stdout, syntax-highlighted for `language` when
+    stdout is a terminal and NO_COLOR is not set. Pipes get plain text.
+    """
+    if language and sys.stdout.isatty() and not os.environ.get("NO_COLOR"):
+        from rich.syntax import Syntax
+        console.print(Syntax(content, language, background_color="default"))
+    else:
+        print(content)
+
+
+def _output_to_sinks(
+    name: str,
+    content: str,
+    to_clipboard: bool,
+    to_osc52: bool,
+    to_stdout: bool,
+    clear_after: int = 0,
+    language: Optional[str] = None,
+    fallback_print: bool = False,
+):
+    """
+    Send snippet content to each enabled output target.
+    Status messages go to stderr so stdout only carries the snippet.
+    With clear_after > 0, the system clipboard is cleared again after that
+    many seconds. Printed content is highlighted for `language` (see
+    _print_content).
+    With fallback_print, a session without a usable system clipboard
+    (headless, missing tools) prints the snippet instead, with a warning.
+    Exits with status 1 if any target failed.
+    """
+    from macolint.clipboard import (
+        ClipboardUnavailable,
+        copy_to_clipboard,
+        copy_via_osc52,
+        schedule_clipboard_clear,
+    )
+
+    content = content.rstrip()
+    failed = False
+    backend = _clipboard_backend()
+
+    sinks = []
+    if to_clipboard and backend != "osc52":
+        sinks.append(("system clipboard", lambda text: copy_to_clipboard(text, backend)))
+    if to_osc52 or (to_clipboard and backend == "osc52"):
+        sinks.append(("terminal clipboard (OSC 52)", copy_via_osc52))
+
+    for label, sink in sinks:
+        try:
+            sink(content)
+            suffix = ""
+            if clear_after and sink is not copy_via_osc52:
+                schedule_clipboard_clear(content, clear_after, backend)
+                suffix = f" (clears in {clear_after}s)"
+            elif clear_after:
+                suffix = " (not cleared automatically)"
+            err_console.print(f"[green]✓ Copied '{name}' to {label}{suffix}[/green]")
+        except ClipboardUnavailable as e:
+            if not fallback_print:
+                failed = True
+                err_console.print(f"[red]Error: Could not copy to {label}: {e}[/red]")
+                continue
+            err_console.print(f"[yellow]Warning: {e} Printing '{name}' instead.[/yellow]")
+            to_stdout = True
+        except Exception as e:
+            failed = True
+            err_console.print(f"[red]Error: Could not copy to {label}: {e}[/red]")
+
+    if to_stdout:
+        _print_content(content, language)
+
+    if failed:
         sys.exit(1)
 
 
 @cli.command()
-@click.argument('name', required=False)
-def edit(name):
+@click.argument('name', shell_complete=_complete_snippet_names)
+@click.option(
+    '--no-content',
+    is_flag=True,
+    help='Show only the metadata.',
+)
+@click.option(
+    '--reveal',
+    is_flag=True,
+    help='Show the content of a secret too.',
+)
+@click.option(
+    '--relative',
+    is_flag=True,
+    help='Show dates as "3 hours ago".',
+)
+def show(name, no_content, reveal, relative):
+    """
+    Print a snippet's metadata and content.
+
+    \b
+    Shows the description, type, kind, language, tags, size and dates, then
+    the content.
+    Nothing is copied to the clipboard, and the access time used by
+    'snip recent' is not updated. Secrets only show their content with
+    --reveal; protected snippets ask for their passphrase unless
+    --no-content is given. With the global --json flag the same fields
+    are printed as a JSON object.
+
+    \b
+    EXAMPLES:
+      snip show deploy_staging
+      snip show tokens/github --no-content
+      snip --json show deploy_staging
+    """
+    try:
+        from rich.markup import escape
+        from macolint.formatting import format_timestamp
+
+        name = _lookup_name(name)
+        snippet = db.get_snippet(name)
+        if snippet is None:
+            _fail(f"Snippet '{name}' not found.", "not_found")
+        with_content = not no_content and (snippet.kind != "secret" or reveal)
+        if with_content:
+            _unlock(snippet)
+
+        if _json_mode():
+            def iso(value):
+                return value.isoformat() if value else None
+
+            result = {
+                "name": name,
+                "type": snippet.content_type,
+                "kind": snippet.kind,
+                "language": snippet.language,
+                "tags": snippet.tags,
+                "description": snippet.description,
+                "shared": snippet.is_shared,
+                "pinned": snippet.pinned,
+                "protected": snippet.protected,
+                "bytes": snippet.byte_len,
+                "lines": snippet.line_count,
+                "created_at": iso(snippet.created_at),
+                "updated_at": iso(snippet.updated_at),
+                "expires_at": iso(snippet.expires_at),
+                "last_accessed_at": iso(snippet.last_accessed_at),
+            }
+            if with_content:
+                result["content"] = snippet.content
+            print(json.dumps(result, indent=2))
+            return
+
+        time_format = _time_format(relative)
+        flags = [
+            label for label, on in (
+                ("pinned", snippet.pinned), ("protected", snippet.protected),
+                ("shared", snippet.is_shared),
+            ) if on
+        ]
+        size = "unknown" if snippet.byte_len is None else (
+            f"{_format_size(snippet.byte_len)}, {snippet.line_count} line(s)"
+        )
+        fields = [
+            ("Name", name),
+            ("Type", snippet.content_type or "-"),
+            ("Kind", snippet.kind + (f" ({', '.join(flags)})" if flags else "")),
+            ("Language", snippet.language or "-"),
+            ("Tags", ", ".join(snippet.tags) or "-"),
+            ("Size", size),
+            ("Created", format_timestamp(snippet.created_at, time_format)),
+            ("Updated", format_timestamp(snippet.updated_at, time_format)),
+        ]
+        if snippet.description:
+            fields.insert(1, ("Description", snippet.description))
+        if snippet.last_accessed_at:
+            fields.append(
+                ("Accessed", format_timestamp(snippet.last_accessed_at, time_format))
+            )
+        if snippet.expires_at:
+            fields.append(("Expires", format_timestamp(snippet.expires_at, time_format)))
+        width = max(10, *(len(label) + 1 for label, _ in fields))
+        for label, value in fields:
+            console.print(f"[bold]{label + ':':<{width}}[/bold] {escape(value)}")
+
+        if no_content:
+            return
+        console.print("[dim]" + "─" * 40 + "[/dim]")
+        if with_content:
+            _print_content(snippet.content.rstrip("\n"), snippet.language)
+        else:
+            from macolint.formatting import SECRET_MASK
+
+            console.print(f"[yellow]{SECRET_MASK}[/yellow] [dim](--reveal shows it here)[/dim]")
+
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@cli.command()
+@click.argument('name', required=False, shell_complete=_complete_snippet_names)
+@click.option(
+    '-e',
+    '--editor',
+    'use_editor',
+    is_flag=True,
+    help='Edit in $VISUAL/$EDITOR instead of the inline prompt.',
+)
+def edit(name, use_editor):
     """
     Edit the content of an existing snippet.
     
@@ -558,11 +2040,21 @@ def edit(name):
       2. Editor opens with existing content as default
       3. Edit content and save, or press Esc to cancel
     
+    \b
+    OPTIONS:
+      -e, --editor    Open the snippet in $VISUAL/$EDITOR (falls back to vi, or
+                      notepad on Windows). The content is saved when the editor
+                      exits; nothing is saved if it exits with an error or the
+                      content is unchanged.
+    
     \b
     EXAMPLES:
       # Edit by name
       snip edit deploy_staging
       snip edit git/commit/template
+
+      # Edit multi-line content in your editor
+      snip edit deploy_staging --editor
     
       # Interactive selection
       snip edit
@@ -586,32 +2078,134 @@ def edit(name):
                 return
         
         # Get existing snippet
+        name = _lookup_name(name)
         snippet = db.get_snippet(name)
         if snippet is None:
-            console.print(f"[red]Snippet '{name}' not found.[/red]")
-            sys.exit(1)
+            _fail(f"Snippet '{name}' not found.", "not_found")
+        passphrase = _unlock(snippet)
         
-        # Prompt for new content with existing content as default
-        new_content = prompt_snippet_content(existing_content=snippet.content)
-        if new_content is None:
-            console.print("[yellow]Changes discarded.[/yellow]")
-            return
+        if use_editor:
+            from macolint.interactive import edit_in_external_editor, EditorError
+
+            try:
+                new_content = edit_in_external_editor(snippet.content).rstrip("\n")
+            except EditorError as e:
+                console.print(f"[yellow]{e} Changes discarded.[/yellow]")
+                return
+            if new_content == snippet.content.rstrip("\n"):
+                console.print("[yellow]No changes made.[/yellow]")
+                return
+        else:
+            # Prompt for new content with existing content as default
+            new_content = prompt_snippet_content(existing_content=snippet.content)
+            if new_content is None:
+                console.print("[yellow]Changes discarded.[/yellow]")
+                return
         
         if not new_content.strip():
             console.print("[red]Error: Snippet content cannot be empty.[/red]")
             return
         
+        if passphrase is not None:
+            from macolint.crypto import protect
+
+            # Keep it protected under the same passphrase
+            new_content = protect(new_content, passphrase)
+
         # Update the snippet
         updated = db.update_snippet(name, new_content)
         if updated:
             console.print(f"[green]Snippet '{name}' updated successfully.[/green]")
         else:
-            console.print(f"[red]Failed to update snippet '{name}'.[/red]")
-            sys.exit(1)
+            _fail(f"Failed to update snippet '{name}'.")
             
     except Exception as e:
-        console.print(f"[red]Error: {e}[/red]")
-        sys.exit(1)
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@cli.command()
+@click.argument('name', shell_complete=_complete_snippet_names)
+@click.option(
+    '--clipboard',
+    'from_clipboard',
+    is_flag=True,
+    help='Append the system clipboard instead of reading stdin or prompting.',
+)
+@click.option(
+    '--create',
+    is_flag=True,
+    help="Create the snippet if it doesn't exist (otherwise that is an error).",
+)
+def append(name, from_clipboard, create):
+    """
+    Append content to the end of a snippet.
+
+    \b
+    The new content goes on a new line after the existing content, and is
+    saved as a new version (see 'snip history'); the creation date is kept.
+    Content comes from piped stdin, the system clipboard (--clipboard) or
+    an inline prompt. Protected snippets ask for their passphrase.
+
+    \b
+    EXAMPLES:
+      echo "git log --oneline --graph" | snip append notes/git_commands
+      snip append notes/links --clipboard
+      history | tail -1 | snip append notes/useful --create
+    """
+    try:
+        if from_clipboard:
+            _refuse_clipboard("snip append --clipboard")
+        name = validate_name(name)
+        snippet = db.get_snippet(name)
+        if snippet is None and not create:
+            _fail(
+                f"Snippet '{name}' not found. Use --create to create it.",
+                "not_found",
+            )
+
+        if from_clipboard:
+            from macolint.clipboard import read_clipboard
+
+            try:
+                addition = read_clipboard(_clipboard_backend())
+            except RuntimeError as e:
+                _fail(f"Error: Cannot read the clipboard: {e}")
+        elif not sys.stdin.isatty():
+            addition = sys.stdin.read()
+        else:
+            addition = prompt_snippet_content()
+            if addition is None:
+                console.print("[yellow]Cancelled.[/yellow]")
+                return
+        addition = addition.rstrip("\n")
+        if not addition.strip():
+            _fail("Error: Nothing to append.")
+
+        if snippet is None:
+            db.save_snippet(name, addition)
+            _report(f"[green]Snippet '{name}' created.[/green]", name=name, created=True)
+            return
+
+        passphrase = _unlock(snippet)
+        existing = snippet.content.rstrip("\n")
+        new_content = f"{existing}\n{addition}" if existing else addition
+        if passphrase is not None:
+            from macolint.crypto import protect
+
+            new_content = protect(new_content, passphrase)
+
+        if not db.update_snippet(name, new_content):
+            _fail(f"Failed to update snippet '{name}'.")
+        lines = len(addition.splitlines())
+        _report(
+            f"[green]Appended {lines} line(s) to '{name}'.[/green]",
+            name=name,
+            created=False,
+            appended_lines=lines,
+        )
+
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
 
 
 @cli.command()
@@ -652,7 +2246,6 @@ def update():
         console.print("[blue]Checking for updates on GitHub...[/blue]")
         
         try:
-            import json
             import ssl
             
             # Try using httpx first (better SSL handling)
@@ -735,8 +2328,7 @@ def update():
             # Find pip command
             pip_cmd = shutil.which("pip3") or shutil.which("pip")
             if not pip_cmd:
-                console.print("[red]Error: pip not found. Please install pip first.[/red]")
-                sys.exit(1)
+                _fail("Error: pip not found. Please install pip first.")
             
             # Update Macolint
             console.print("[blue]Updating Macolint...[/blue]")
@@ -780,12 +2372,11 @@ def update():
             sys.exit(1)
             
     except Exception as e:
-        console.print(f"[red]Error: {e}[/red]")
-        sys.exit(1)
+        _fail(f"Error: {e}", _error_kind(e))
 
 
 @cli.command()
-@click.argument("name", required=False)
+@click.argument("name", required=False, shell_complete=_complete_snippet_names)
 @click.option(
     "-m",
     "--module",
@@ -793,7 +2384,15 @@ def update():
     required=False,
     help="Delete an entire module (and its sub-modules/snippets) instead of a single snippet.",
 )
-def delete(name, module_path):
+@click.option(
+    "-y",
+    "--yes",
+    "assume_yes",
+    is_flag=True,
+    help="Delete without asking for confirmation.",
+)
+@click.pass_obj
+def delete(run, name, module_path, assume_yes):
     """
     Delete a snippet or an entire module tree.
     
@@ -822,6 +2421,7 @@ def delete(name, module_path):
     OPTIONS:
       -m, --module MODULE_PATH    Delete an entire module tree instead of a snippet.
                                    Cannot be used together with NAME argument.
+      -y, --yes                   Skip the confirmation prompt (for scripts).
     
     \b
     EXAMPLES:
@@ -832,22 +2432,40 @@ def delete(name, module_path):
       # Delete module (with confirmation prompt)
       snip delete -m old_module
       snip delete -m module1/submodule
+
+      # Delete without confirmation
+      snip delete old_snippet --yes
+
+      # See what a module delete would remove
+      snip --dry-run delete -m old_module
     
     \b
     SAFETY:
-      All deletions require confirmation before proceeding.
+      All deletions require confirmation before proceeding unless --yes is given.
+      Deleting a snippet or module that does not exist exits with an error.
     """
     try:
         # Module deletion takes precedence when --module is used
         if module_path is not None:
             if name:
+                _fail("Error: When using -m/--module, do not also pass a snippet NAME.", "usage")
+
+            if db.get_module_by_path(module_path) is None:
+                _fail(f"Module '{module_path}' not found.", "not_found")
+
+            if run.dry_run:
+                prefix = module_path.strip("/") + "/"
+                contained = [n for n in db.get_all_snippet_names() if n.startswith(prefix)]
+                for path in contained:
+                    console.print(f"  {path}")
                 console.print(
-                    "[red]Error: When using -m/--module, do not also pass a snippet NAME.[/red]"
+                    f"[cyan]Would delete module '{module_path}' and "
+                    f"{len(contained)} snippet(s) in it.[/cyan]"
                 )
-                sys.exit(1)
+                return
 
             # Confirm module deletion with cascade
-            confirm = click.confirm(
+            confirm = assume_yes or click.confirm(
                 f"Are you sure you want to delete module '{module_path}' and all its contents?"
             )
             if not confirm:
@@ -856,12 +2474,13 @@ def delete(name, module_path):
 
             deleted = db.delete_module_tree(module_path)
             if deleted:
-                console.print(
-                    f"[green]Module '{module_path}' and its contents deleted successfully.[/green]"
+                _report(
+                    f"[green]Module '{module_path}' and its contents deleted successfully.[/green]",
+                    module=module_path,
+                    deleted=True,
                 )
             else:
-                console.print(f"[red]Module '{module_path}' not found.[/red]")
-                sys.exit(1)
+                _fail(f"Module '{module_path}' not found.", "not_found")
             return
 
         # Snippet deletion (existing behaviour)
@@ -877,8 +2496,16 @@ def delete(name, module_path):
                 console.print("[yellow]Cancelled.[/yellow]")
                 return
 
+        name = _lookup_name(name)
+        if db.get_snippet(name) is None:
+            _fail(f"Snippet '{name}' not found.", "not_found")
+
+        if run.dry_run:
+            console.print(f"[cyan]Would delete snippet '{name}'.[/cyan]")
+            return
+
         # Confirm deletion
-        confirm = click.confirm(f"Are you sure you want to delete snippet '{name}'?")
+        confirm = assume_yes or click.confirm(f"Are you sure you want to delete snippet '{name}'?")
         if not confirm:
             console.print("[yellow]Deletion cancelled.[/yellow]")
             return
@@ -886,35 +2513,112 @@ def delete(name, module_path):
         # Delete the snippet
         deleted = db.delete_snippet(name)
         if deleted:
-            console.print(f"[green]Snippet '{name}' deleted successfully.[/green]")
+            _report(f"[green]Snippet '{name}' deleted successfully.[/green]", name=name, deleted=True)
         else:
-            console.print(f"[red]Snippet '{name}' not found.[/red]")
-            sys.exit(1)
+            _fail(f"Snippet '{name}' not found.", "not_found")
 
     except Exception as e:
-        console.print(f"[red]Error: {e}[/red]")
-        sys.exit(1)
+        _fail(f"Error: {e}", _error_kind(e))
 
 
 @cli.command()
-@click.argument('old_path', required=False)
-@click.argument('new_path', required=False)
 @click.option(
-    '-m',
-    '--module',
-    'is_module',
+    "--dry-run",
     is_flag=True,
-    help='Rename a module instead of a snippet.',
+    help="Only list the expired snippets, without deleting them.",
 )
-def rename(old_path, new_path, is_module):
+@click.pass_obj
+def prune(run, dry_run):
     """
-    Rename a snippet or module, or move it to a different location.
-    
+    Delete all expired snippets.
+
     \b
-    RENAME SNIPPETS:
-      Rename at root level:
-        snip rename old_name new_name
-    
+    Snippets saved with --expires-in are hidden from every command once they
+    expire; prune removes them (with their history, tags and attachments)
+    from the database for good.
+
+    \b
+    EXAMPLES:
+      snip prune
+      snip prune --dry-run
+    """
+    try:
+        dry_run = dry_run or run.dry_run
+        expired = db.prune_expired(dry_run=dry_run)
+        if not expired:
+            console.print("[yellow]No expired snippets.[/yellow]")
+            return
+        for path in expired:
+            console.print(f"  {path}")
+        if dry_run:
+            console.print(f"[yellow]{len(expired)} expired snippet(s) would be deleted.[/yellow]")
+        else:
+            console.print(f"[green]Deleted {len(expired)} expired snippet(s).[/green]")
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@cli.command()
+@click.argument("name", shell_complete=_complete_snippet_names)
+def pin(name):
+    """
+    Pin a snippet so it is listed first.
+
+    \b
+    Pinned snippets come first in 'snip list' (marked with ★) and at the
+    top of the interactive search in 'snip get' before you type.
+
+    \b
+    EXAMPLES:
+      snip pin deploy_staging
+      snip unpin deploy_staging
+    """
+    _set_pinned(name, True)
+
+
+@cli.command()
+@click.argument("name", shell_complete=_complete_snippet_names)
+def unpin(name):
+    """
+    Unpin a snippet pinned with 'snip pin'.
+
+    \b
+    EXAMPLES:
+      snip unpin deploy_staging
+    """
+    _set_pinned(name, False)
+
+
+def _set_pinned(name: str, pinned: bool):
+    """Shared implementation of pin and unpin."""
+    try:
+        if not db.set_snippet_pinned(name, pinned):
+            _fail(f"Snippet '{name}' not found.", "not_found")
+        action = "pinned" if pinned else "unpinned"
+        _report(f"[green]Snippet '{name}' {action}.[/green]", name=name, pinned=pinned)
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@cli.command()
+@click.argument('old_path', required=False, shell_complete=_complete_snippet_names)
+@click.argument('new_path', required=False)
+@click.option(
+    '-m',
+    '--module',
+    'is_module',
+    is_flag=True,
+    help='Rename a module instead of a snippet.',
+)
+def rename(old_path, new_path, is_module):
+    """
+    Rename a snippet or module, or move it to a different location.
+    
+    \b
+    RENAME SNIPPETS:
+      Rename at root level:
+        snip rename old_name new_name
+    
       Rename in module (stays in same module):
         snip rename module1/old_name new_name
         snip rename module1/old_name module1/new_name
@@ -934,6 +2638,9 @@ def rename(old_path, new_path, is_module):
     
       NOTE: Renaming a module automatically updates all child paths.
     
+      Renaming keeps the snippet's creation time; modules missing from the
+      new path are created.
+    
     \b
     INTERACTIVE MODE:
       Interactive rename (prompts for old and new paths):
@@ -1012,154 +2719,1817 @@ def rename(old_path, new_path, is_module):
             old_path = old_path[:-1]
         if new_path.endswith("/"):
             new_path = new_path[:-1]
+        new_path = validate_name(new_path)
 
         # Perform rename
         if is_module:
             success = db.rename_module(old_path, new_path)
             if success:
-                console.print(f"[green]Module '{old_path}' renamed to '{new_path}' successfully.[/green]")
+                _report(
+                    f"[green]Module '{old_path}' renamed to '{new_path}' successfully.[/green]",
+                    module=old_path,
+                    renamed_to=new_path,
+                )
             else:
-                console.print(f"[red]Failed to rename module '{old_path}'. Module not found or new path conflicts.[/red]")
-                sys.exit(1)
+                _fail(
+                    f"Failed to rename module '{old_path}'. Module not found or new path conflicts.",
+                    "not_found"
+                )
         else:
+            old_path = _lookup_name(old_path)
+            if db.get_snippet(old_path) is None:
+                _fail(f"Snippet '{old_path}' not found.", "not_found")
+            if db.get_snippet(new_path) is not None:
+                _fail(
+                    f"Snippet '{new_path}' already exists. Delete or rename it first.",
+                    "conflict"
+                )
+
             success = db.rename_snippet(old_path, new_path)
             if success:
-                console.print(f"[green]Snippet '{old_path}' renamed to '{new_path}' successfully.[/green]")
+                _report(
+                    f"[green]Snippet '{old_path}' renamed to '{new_path}' successfully.[/green]",
+                    name=old_path,
+                    renamed_to=new_path,
+                )
             else:
-                console.print(f"[red]Failed to rename snippet '{old_path}'. Snippet not found or new path conflicts.[/red]")
-                sys.exit(1)
+                _fail(
+                    f"Failed to rename snippet '{old_path}'. Snippet not found or new path conflicts.",
+                    "not_found"
+                )
+
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@cli.command()
+@click.argument('src', shell_complete=_complete_snippet_names)
+@click.argument('dst')
+def copy(src, dst):
+    """
+    Copy a snippet to a new name, leaving the original untouched.
+
+    \b
+    The copy gets the same content, type, kind, tags, expiry and attachments,
+    with fresh timestamps. Missing modules in DST are created. An existing DST is
+    never overwritten.
+
+    \b
+    EXAMPLES:
+      snip copy deploy_staging deploy_production
+      snip copy git/commit/template git/commit/hotfix
+    """
+    try:
+        if dst.endswith("/"):
+            dst = dst[:-1]
+        dst = validate_name(dst)
+        src = _lookup_name(src)
+        if db.get_snippet(src) is None:
+            _fail(f"Snippet '{src}' not found.", "not_found")
+        if db.get_snippet(dst) is not None:
+            _fail(f"Snippet '{dst}' already exists.", "conflict")
+
+        if not db.copy_snippet(src, dst):
+            _fail(f"Failed to copy '{src}' to '{dst}'.")
+        _report(
+            f"[green]Snippet '{src}' copied to '{dst}' successfully.[/green]",
+            name=src,
+            copied_to=dst,
+        )
+
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@cli.command()
+@click.option(
+    '-n',
+    '--limit',
+    type=click.IntRange(min=1),
+    default=10,
+    show_default=True,
+    help='Show at most this many snippets.',
+)
+@click.option(
+    '--relative',
+    is_flag=True,
+    help='Show dates as "3 hours ago".',
+)
+def recent(limit, relative):
+    """
+    List the snippets you retrieved most recently.
+
+    \b
+    Every 'snip get' records when the snippet was retrieved; this lists
+    them newest first, across all modules. Set "track_access": false in
+    ~/.macolint/settings.json to stop recording access times. With the
+    global --json flag the list is printed as JSON.
+
+    \b
+    EXAMPLES:
+      snip recent
+      snip recent -n 3 --relative
+      snip --json recent
+    """
+    try:
+        infos = db.list_recent(limit)
+        if _json_mode():
+            from macolint.formatting import snippet_info_to_dict
+            print(json.dumps([snippet_info_to_dict(info) for info in infos], indent=2))
+            return
+        if not infos:
+            console.print("[yellow]No snippets retrieved yet.[/yellow]")
+            return
+
+        from rich.table import Table
+        from macolint.formatting import format_timestamp
+
+        time_format = _time_format(relative)
+        table = Table(title="Recently retrieved")
+        table.add_column("Name", style="cyan")
+        table.add_column("Accessed")
+        for info in infos:
+            table.add_row(info.path, format_timestamp(info.last_accessed_at, time_format))
+        console.print(table)
+
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@cli.command(name='run')
+@click.argument('name', shell_complete=_complete_snippet_names)
+@click.option(
+    '-y',
+    '--yes',
+    'assume_yes',
+    is_flag=True,
+    help='Run without showing the command and asking first.',
+)
+@click.option(
+    '--fill',
+    'fill_values',
+    multiple=True,
+    metavar='NAME=VALUE',
+    callback=_parse_fill,
+    help='Value for a {{NAME}} placeholder. Can be repeated.',
+)
+def run_snippet(name, assume_yes, fill_values):
+    """
+    Run a shell snippet with $SHELL -c.
+
+    \b
+    The command is shown first and only runs once you confirm; --yes skips
+    the question (required when stdin is not a terminal). Output streams to
+    the terminal and snip exits with the command's exit status.
+
+    \b
+    Only shell snippets can be run: language sh, bash, zsh or fish (see
+    'snip save --lang'), detected content type shell, or the tag
+    'runnable' ('snip save NAME --force --tags runnable' adds it).
+    {{placeholders}} are filled in first, as with 'snip get --fill'.
+
+    \b
+    EXAMPLES:
+      snip run docker/cleanup
+      snip run deploy --fill env=staging --yes
+    """
+    from macolint.runner import RUNNABLE_TAG, is_runnable, run_in_shell
+
+    try:
+        snippet = db.get_snippet(name)
+        if snippet is None:
+            _fail(f"Snippet '{name}' not found.", "not_found")
+        if not is_runnable(snippet):
+            _fail(
+                f"Error: '{name}' is not a shell snippet (language: "
+                f"{snippet.language or 'unknown'}). Tag it '{RUNNABLE_TAG}' to run it anyway.",
+                "usage"
+            )
+        if not assume_yes and not sys.stdin.isatty():
+            _fail("Error: Pass --yes to run a snippet when stdin is not a terminal.", "usage")
+
+        _unlock(snippet)
+        command = _fill_template(name, snippet.content, fill_values)
+
+        if not assume_yes:
+            if snippet.kind == "secret":
+                err_console.print(f"[bold]{name}[/bold] is a secret; its command is not shown.")
+            else:
+                err_console.print(f"[bold]About to run {name}:[/bold]")
+                err_console.print(command, markup=False, highlight=False)
+            if not click.confirm("Run it?", default=False, err=True):
+                console.print("[yellow]Cancelled.[/yellow]")
+                return
+
+        db.record_snippet_use(name, track_access=_track_access())
+        status = run_in_shell(command)
+    except KeyboardInterrupt:
+        sys.exit(130)
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+    sys.exit(status)
+
+
+@cli.command()
+@click.option(
+    '--port',
+    type=click.IntRange(1, 65535),
+    default=8765,
+    show_default=True,
+    help='Port to listen on (always on 127.0.0.1).',
+)
+def serve(port):
+    """
+    Serve snippets to editors and browser extensions over a local HTTP API.
+
+    \b
+    Listens on 127.0.0.1 only, never on a network interface, and prints a
+    token that every request must send as 'Authorization: Bearer TOKEN'. A
+    new token is made each time; stop the server with Ctrl+C. Snippets are
+    decrypted by snip and sent in plaintext, so keep the server bound to
+    localhost: do not forward the port or put a proxy in front of it.
+
+    \b
+    ENDPOINTS (JSON in and out):
+      GET /snippets                 {"snippets": [name, ...]}; ?module=git for one module
+      GET /snippets/NAME            {"name", "content", "kind", "language", "tags",
+                                     "description", "updated_at"}; a secret needs
+                                     ?reveal=1, and protected snippets are refused
+      PUT /snippets/NAME            Save {"content", "tags", "kind", "language",
+                                     "description", "overwrite"}; 201 if created,
+                                     409 if it exists and overwrite is not true
+
+    \b
+    Errors are {"error", "kind"} with a 4xx/5xx status. Requests whose Host
+    is not localhost are refused, so web pages cannot reach the API.
+
+    \b
+    EXAMPLES:
+      snip serve
+      snip serve --port 9000
+      curl -H "Authorization: Bearer $TOKEN" http://127.0.0.1:8765/snippets/git/undo
+    """
+    from macolint.api import Config
+    from macolint.server import HOST, new_token, serve as serve_api
+
+    try:
+        config = Config.for_vault()
+        token = new_token()
+        err_console.print(f"Serving snippets on http://{HOST}:{port} (Ctrl+C to stop)")
+        err_console.print(f"Token: {token}", markup=False, highlight=False)
+        serve_api(config, port, token)
+    except KeyboardInterrupt:
+        err_console.print("[yellow]Stopped.[/yellow]")
+    except OSError as e:
+        _fail(f"Error: Cannot listen on {HOST}:{port} ({e}).")
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+def _browse_preview(name: str) -> str:
+    """Full content of a snippet for the browse preview pane (never a secret's)."""
+    from macolint.formatting import content_mask
+
+    try:
+        snippet = db.get_snippet(name)
+    except Exception as e:
+        return f"(could not load: {e})"
+    if snippet is None:
+        return "(not found)"
+    return content_mask(snippet.content, snippet.kind) or snippet.content
+
+
+@cli.command()
+@click.pass_context
+def browse(ctx):
+    """
+    Browse snippets in a full-screen list with a live preview.
+
+    \b
+    KEYS:
+      Up/Down, PgUp/PgDn   Move through the list
+      /                    Type a filter (Enter or Esc goes back to the list)
+      Enter                Copy the highlighted snippet to the clipboard and exit
+      e                    Edit the highlighted snippet in $VISUAL/$EDITOR
+      d                    Delete the highlighted snippet (asks first)
+      q, Esc, Ctrl+C       Quit
+
+    \b
+    Only the highlighted snippet is decrypted, for its preview; secrets and
+    protected snippets are never previewed. After an edit or delete the
+    browser reopens with the same filter.
+
+    \b
+    EXAMPLES:
+      snip browse
+    """
+    from macolint.browse import browse_snippets
+
+    if not sys.stdin.isatty() or not sys.stdout.isatty():
+        _fail("Error: snip browse requires a terminal.", "usage")
+
+    filter_text, selected = "", None
+    while True:
+        snippet_names = db.get_all_snippet_names()
+        if not snippet_names:
+            console.print("[yellow]No snippets found.[/yellow]")
+            return
+
+        result = browse_snippets(snippet_names, _browse_preview, filter_text, selected)
+        if result is None:
+            return
+        action, selected, filter_text = result
+
+        if action == "copy":
+            # Under --no-clipboard the snippet is printed instead
+            ctx.invoke(get, name=selected, to_clipboard=not _no_clipboard())
+            return
+        if action == "edit":
+            ctx.invoke(edit, name=selected, use_editor=True)
+        elif action == "delete":
+            ctx.invoke(delete, name=selected)
+
+
+@cli.group('clip-history')
+@click.pass_context
+def clip_history(ctx):
+    """Keep a rolling history of clipboard contents as snippets."""
+    command = f"clip-history {ctx.invoked_subcommand}"
+    if ctx.obj.dry_run and command not in DRY_RUN_COMMANDS:
+        _refuse_dry_run(command)
+
+
+@clip_history.command('capture')
+@click.option(
+    '--watch',
+    'interval',
+    type=click.FloatRange(min=0.2),
+    required=False,
+    help='Keep running, checking the clipboard every INTERVAL seconds.',
+)
+def clip_capture(interval):
+    """
+    Save the current clipboard contents to the clipboard history.
+
+    \b
+    Each distinct value is stored, encrypted, as a snippet named after the
+    time it was captured (clip/20240501-143000). After each capture the
+    history is pruned: only the newest clip_history_size entries are kept
+    (default 20), and none older than clip_history_max_age_secs if set
+    (both in ~/.macolint/settings.json); a value already in the history
+    moves to the top. Nothing is captured unless you run this: bind it to
+    a hotkey, or leave it running with --watch (Ctrl+C stops it).
+
+    \b
+    EXAMPLES:
+      snip clip-history capture
+      snip clip-history capture --watch 2
+    """
+    import time
+    from macolint.clipboard import read_clipboard
+    from macolint.cliphistory import record_clipboard
+
+    _refuse_clipboard("snip clip-history capture")
+    size, max_age = _clip_history_limits()
+    max_bytes = _max_snippet_bytes()
+    backend = _clipboard_backend()
+    last = None
+    try:
+        while True:
+            try:
+                content = read_clipboard(backend)
+            except RuntimeError as e:
+                _fail(f"Error: Cannot read the clipboard: {e}")
+            if content != last and content.strip():
+                last = content
+                content_size = len(content.encode("utf-8"))
+                if max_bytes and content_size > max_bytes:
+                    err_console.print(
+                        f"[yellow]Skipped: clipboard holds {content_size} bytes, over "
+                        f"max_snippet_bytes ({max_bytes}).[/yellow]"
+                    )
+                else:
+                    name = record_clipboard(db, content, size, max_age)
+                    if name is not None:
+                        _report(f"✓ Captured clipboard as '{name}'", name=name)
+                    elif interval is None:
+                        _report("Clipboard unchanged since the last capture.", name=None)
+            elif interval is None:
+                _report("Nothing to capture: the clipboard is empty.", name=None)
+            if interval is None:
+                return
+            time.sleep(interval)
+    except KeyboardInterrupt:
+        return
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@clip_history.command('list')
+@click.option(
+    '--relative',
+    is_flag=True,
+    help='Show dates as "3 hours ago".',
+)
+def clip_list(relative):
+    """
+    List the clipboard history, newest first.
+
+    \b
+    Shows each entry with the time it was captured and a one-line preview.
+    With the global --json flag the list is printed as JSON (metadata only).
+
+    \b
+    EXAMPLES:
+      snip clip-history list
+      snip --json clip-history list
+    """
+    from macolint.cliphistory import CLIP_MODULE
+
+    try:
+        module = db.get_module_by_path(CLIP_MODULE)
+        infos = [] if module is None else db.list_snippet_infos_in_module(module, sort="name", reverse=True)
+        if _json_mode():
+            from macolint.formatting import snippet_info_to_dict
+            print(json.dumps([snippet_info_to_dict(info) for info in infos], indent=2))
+            return
+        if not infos:
+            console.print("[yellow]The clipboard history is empty. Capture with: snip clip-history capture[/yellow]")
+            return
+
+        from rich.table import Table
+        from macolint.formatting import format_timestamp
+
+        time_format = _time_format(relative)
+        table = Table(title="Clipboard history")
+        table.add_column("Name", style="cyan")
+        table.add_column("Captured")
+        table.add_column("Preview")
+        for info in infos:
+            table.add_row(
+                info.path,
+                format_timestamp(info.created_at, time_format),
+                _snippet_preview(info.path),
+            )
+        console.print(table)
+
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@clip_history.command('prune')
+@click.option(
+    '--keep',
+    type=click.IntRange(min=0),
+    required=False,
+    help='Keep only the newest N entries (default: clip_history_size).',
+)
+@click.option(
+    '--older-than',
+    'older_than',
+    required=False,
+    help="Also delete entries older than this, e.g. '7d' (default: clip_history_max_age_secs).",
+)
+@click.option(
+    '--dry-run',
+    is_flag=True,
+    help='Only list the entries that would be deleted.',
+)
+@click.pass_obj
+def clip_prune(run, keep, older_than, dry_run):
+    """
+    Delete old clipboard history entries now.
+
+    \b
+    Applies the same retention as each capture: the newest
+    clip_history_size entries are kept, and none older than
+    clip_history_max_age_secs (settings.json). --keep and --older-than
+    override those for this run. Only entries directly under clip/ are
+    deleted; pinned entries are always kept.
+
+    \b
+    EXAMPLES:
+      snip clip-history prune
+      snip clip-history prune --keep 5 --older-than 1d
+      snip clip-history prune --keep 0 --dry-run
+    """
+    from macolint.cliphistory import prune_history
 
+    try:
+        dry_run = dry_run or run.dry_run
+        size, max_age = _clip_history_limits()
+        if keep is not None:
+            size = keep
+        if older_than is not None:
+            try:
+                max_age = parse_duration(older_than)
+            except ValueError as e:
+                _fail(f"Error: {e}", "usage")
+
+        deleted = prune_history(db, size, max_age, dry_run=dry_run)
+        if not _json_mode():
+            for name in deleted:
+                console.print(f"  {'Would delete' if dry_run else 'Deleted'}: {name}")
+        verb = "Would delete" if dry_run else "✓ Deleted"
+        _report(
+            f"[green]{verb} {len(deleted)} clipboard history entr"
+            f"{'y' if len(deleted) == 1 else 'ies'}[/green]",
+            deleted=deleted, dry_run=dry_run,
+        )
+
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+@clip_history.command('get')
+@click.pass_context
+def clip_get(ctx):
+    """
+    Pick a clipboard history entry with fuzzy search and copy it back.
+
+    \b
+    Type part of an entry's name or pick from the suggestions (previews are
+    shown beside them); the entry is copied to the system clipboard, as
+    'snip get NAME --clipboard' would. 'snip get clip/NAME' works too.
+
+    \b
+    EXAMPLES:
+      snip clip-history get
+    """
+    from macolint.cliphistory import history_entries
+
+    _refuse_clipboard("snip clip-history get")
+    try:
+        entries = history_entries(db)
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+    if not entries:
+        console.print("[yellow]The clipboard history is empty. Capture with: snip clip-history capture[/yellow]")
+        return
+
+    try:
+        name = prompt_snippet_name_simple(entries, preview=_snippet_preview)
+    except NoSelectionError:
+        return
+    if not name:
+        console.print("[yellow]Cancelled.[/yellow]")
+        return
+    ctx.invoke(get, name=name, to_clipboard=True)
+
+
+@cli.command()
+@click.argument('name', shell_complete=_complete_snippet_names)
+@click.option(
+    '--relative',
+    is_flag=True,
+    help='Show dates as "3 hours ago".',
+)
+def history(name, relative):
+    """
+    Show the saved versions of a snippet.
+
+    \b
+    Every save, edit and restore adds a version; the newest one is the
+    current content. Content is not shown; use 'snip restore' to roll back.
+    Dates follow "time_format" in ~/.macolint/settings.json unless
+    --relative is given.
+
+    \b
+    EXAMPLES:
+      snip history deploy_staging
+      snip history deploy_staging --relative
+      snip restore deploy_staging --version 2
+    """
+    try:
+        versions = db.list_versions(name)
+        if versions is None:
+            _fail(f"Snippet '{name}' not found.", "not_found")
+
+        from rich.table import Table
+        from macolint.formatting import format_timestamp
+
+        time_format = _time_format(relative)
+        table = Table(title=f"History of {name}")
+        table.add_column("Version", justify="right", style="cyan")
+        table.add_column("Saved")
+        table.add_column("Size", justify="right")
+        for version in versions:
+            table.add_row(
+                str(version.number),
+                format_timestamp(version.created_at, time_format),
+                f"{version.size} chars" + (" (current)" if version.is_current else ""),
+            )
+        console.print(table)
+
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@cli.command()
+@click.argument('name', shell_complete=_complete_snippet_names)
+@click.option(
+    '--version',
+    'version_number',
+    type=int,
+    required=True,
+    help="Version number to restore (see 'snip history').",
+)
+def restore(name, version_number):
+    """
+    Roll a snippet back to an earlier version.
+
+    \b
+    The restored content is saved as a new version, so the content it
+    replaces stays in the history.
+
+    \b
+    EXAMPLES:
+      snip history deploy_staging
+      snip restore deploy_staging --version 2
+    """
+    try:
+        if not db.restore_version(name, version_number):
+            _fail(f"Snippet '{name}' not found.", "not_found")
+        console.print(f"[green]✓ Restored '{name}' to version {version_number}[/green]")
+
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+# Snippets per page for `snip list --page` when --limit is not given
+LIST_PAGE_SIZE = 50
+
+
+@cli.command()
+@click.argument('keyword', required=False)
+@click.option(
+    '-m',
+    '--module',
+    'module_path',
+    required=False,
+    help='List contents of a specific module instead of root level.',
+)
+@click.option(
+    '--format',
+    'format_string',
+    required=False,
+    help="Output format: table (default), json, plain, or a format string "
+         "such as '{name} ({updated})'.",
+)
+@click.option(
+    '--type',
+    'content_type',
+    type=click.Choice(CONTENT_TYPES, case_sensitive=False),
+    required=False,
+    help='Only show snippets of this content type.',
+)
+@click.option(
+    '--tag',
+    'tag_filters',
+    multiple=True,
+    help='Only show snippets with this tag. Repeat to require several tags.',
+)
+@click.option(
+    '--team',
+    'team',
+    metavar='TEAM',
+    required=False,
+    help='Only show snippets shared with this team (name or id).',
+)
+@click.option(
+    '--sort',
+    'sort',
+    type=click.Choice(['name', 'created', 'updated'], case_sensitive=False),
+    required=False,
+    help='Sort snippets by name (A-Z) or by creation/update date (newest first).',
+)
+@click.option(
+    '--reverse',
+    is_flag=True,
+    help='Reverse the sort order.',
+)
+@click.option(
+    '--limit',
+    'limit',
+    type=click.IntRange(min=1),
+    required=False,
+    help='Show at most this many snippets.',
+)
+@click.option(
+    '--page',
+    'page',
+    type=click.IntRange(min=1),
+    required=False,
+    help=f'Show this page of snippets ({LIST_PAGE_SIZE} per page, or --limit per page).',
+)
+@click.option(
+    '--count',
+    'count_only',
+    is_flag=True,
+    help='Print only the number of matching snippets.',
+)
+@click.option(
+    '-q',
+    '--quiet',
+    is_flag=True,
+    help='Print only snippet names, one per line (same as --format plain).',
+)
+@click.option(
+    '--min-lines',
+    'min_lines',
+    type=click.IntRange(min=0),
+    required=False,
+    help='Only show snippets with at least this many lines.',
+)
+@click.option(
+    '--relative',
+    is_flag=True,
+    help='Show dates as "3 hours ago" (default: time_format in settings.json).',
+)
+@click.option(
+    '--tree',
+    is_flag=True,
+    help='Show all nested modules and snippets as an indented tree.',
+)
+@click.option(
+    '--since',
+    'since_text',
+    metavar='DATE',
+    required=False,
+    help='Only show snippets updated (or created, with --by) on or after DATE.',
+)
+@click.option(
+    '--until',
+    'until_text',
+    metavar='DATE',
+    required=False,
+    help='Only show snippets updated (or created, with --by) up to DATE.',
+)
+@click.option(
+    '--by',
+    'date_field',
+    type=click.Choice(['updated', 'created'], case_sensitive=False),
+    default='updated',
+    show_default=True,
+    help='Which date --since/--until filter on.',
+)
+def list(keyword, module_path, format_string, content_type, tag_filters, team, sort, reverse,
+         limit, page, count_only, quiet, min_lines, relative, tree, since_text, until_text, date_field):
+    """
+    List snippets and modules at a specific level.
+    
+    \b
+    LIST ROOT LEVEL:
+      List all top-level items:
+        snip list
+      
+      Filter by keyword:
+        snip list git
+        snip list deploy
+    
+    \b
+    LIST MODULE CONTENTS:
+      List contents of a specific module:
+        snip list -m module1
+        snip list -m module1/module2
+      
+      List module with keyword filter:
+        snip list -m module1 deploy
+    
+    \b
+    OUTPUT FORMAT:
+      - Modules are shown with trailing "/" (in yellow)
+      - Snippets are shown as full paths (in cyan)
+      - Only direct children are shown (not nested descendants; use --tree)
+      - Sorted alphabetically (with --sort/--reverse/--limit: modules first,
+        then snippets in the requested order)
+      - Pinned snippets (snip pin) come first, marked with ★
+      - Size and line count of each snippet ("-" if not known yet)
+      - When each snippet was last updated, in local time
+      - Each snippet's description (snip save --desc), if any has one
+    
+    \b
+    OPTIONS:
+      -m, --module MODULE_PATH    List contents of a specific module instead of root level.
+      --format FORMAT             table (default), json (array of snippet metadata,
+                                  never content), plain (one name per line), or a
+                                  format string printed once per snippet.
+                                  Tokens: {name}, {id}, {created}, {updated}, {tags}, {type},
+                                  {kind}, {lang}, {bytes}, {lines}, {desc}.
+                                  Unknown tokens are printed literally. Modules are
+                                  omitted for every format except table.
+      --type TYPE                 Only show snippets of this content type (modules are omitted).
+      --tag TAG                   Only show snippets tagged TAG (modules are omitted).
+                                  Repeat to require several tags.
+      --team TEAM                 Only show snippets shared with TEAM, by name or id
+                                  (snip share; modules are omitted). Works offline.
+      --min-lines N               Only show snippets of at least N lines (modules are
+                                  omitted). Protected snippets, and snippets saved by an
+                                  older version and not retrieved since, never match.
+      --since DATE, --until DATE  Only show snippets last updated in this range (modules
+                                  are omitted). DATE is YYYY-MM-DD or 'YYYY-MM-DD HH:MM'
+                                  in local time; both ends are inclusive, and a bare
+                                  --until date includes that whole day.
+      --by updated|created        Filter --since/--until on the creation date instead.
+      --tree                      Show everything below the level (all nested modules
+                                  and snippets) as an indented tree. A keyword keeps
+                                  matching snippets and the modules leading to them.
+                                  Cannot be combined with the other output or filter
+                                  options.
+      --relative                  Show dates relative to now ("3 hours ago") in the
+                                  table and in {created}/{updated}. Set "time_format"
+                                  (local, iso or relative) in ~/.macolint/settings.json
+                                  to change the default.
+      --sort ORDER                name (A-Z), created or updated (newest first).
+      --reverse                   Reverse the sort order.
+      --limit N                   Show at most N snippets.
+      --page N                    Show the Nth page of snippets: 50 per page, or
+                                  --limit per page. Only the page is read from the
+                                  database, so this stays fast with thousands of
+                                  snippets. Modules are shown on page 1 only.
+      --count                     Print only the number of matching snippets.
+      -q, --quiet                 Print only snippet names, one per line, with no
+                                  table or title (same as --format plain).
+    
+    \b
+    EXAMPLES:
+      # List root level
+      snip list
+      snip list git
+      
+      # List module contents
+      snip list -m module1
+      snip list -m module1/module2
+      snip list -m module1 deploy
+      
+      # Custom output
+      snip list --format '{name} ({updated})'
+      snip list -m git --format json | jq '.[].name'
+      for s in $(snip list --format plain); do echo "$s"; done
+      
+      # Only JSON snippets
+      snip list --type json
+
+      # Only snippets tagged rust
+      snip list --tag rust
+
+      # Snippets shared with the dev team
+      snip list --team dev
+
+      # Multi-line snippets only
+      snip list --min-lines 2
+
+      # The whole hierarchy, or one branch of it
+      snip list --tree
+      snip list --tree -m aws
+
+      # Recently changed snippets, with relative dates
+      snip list --sort updated --limit 10 --relative
+
+      # The 20 most recently updated snippets
+      snip list --sort updated --limit 20
+
+      # Page through a large store, 100 snippets at a time
+      snip list --page 2 --limit 100
+
+      # Oldest snippets first
+      snip list --sort created --reverse
+
+      # Snippets changed in January, or created since a date
+      snip list --since 2024-01-01 --until 2024-01-31
+      snip list --since 2024-03-01 --by created
+
+      # Scripting
+      snip list --tag rust --count
+      for s in $(snip list -q --tag deploy); do snip get "$s" --print; done
+    """
+    try:
+        if sum(bool(option) for option in (count_only, quiet, format_string)) > 1:
+            _fail("Error: Use only one of --count, --quiet and --format.", "usage")
+        if tree and (count_only or quiet or format_string or content_type or tag_filters
+                     or team or sort or reverse or limit or page or min_lines is not None
+                     or since_text or until_text):
+            _fail(
+                "Error: --tree cannot be combined with --format, --count, --quiet, --type, "
+                "--tag, --team, --sort, --reverse, --limit, --page, --min-lines, --since "
+                "or --until.",
+                "usage"
+            )
+        if page and count_only:
+            _fail("Error: --page cannot be used with --count.", "usage")
+        try:
+            since = parse_date_bound(since_text) if since_text else None
+            until = parse_date_bound(until_text, end=True) if until_text else None
+        except ValueError as e:
+            _fail(f"Error: {e}", "usage")
+        if since and until and since >= until:
+            _fail("Error: --since must be before --until.", "usage")
+        date_field = date_field.lower()
+        if quiet:
+            format_string = "plain"
+        elif format_string is None and not (count_only or tree) and _json_mode():
+            format_string = "json"
+        time_format = _time_format(relative)
+
+        if content_type:
+            content_type = content_type.lower()
+        if sort:
+            sort = sort.lower()
+        ordered = bool(sort or reverse or limit or page)
+        offset = 0
+        if page:
+            limit = limit or LIST_PAGE_SIZE
+            offset = (page - 1) * limit
+        # A keyword filter runs after the query, so the limit is applied then.
+        # One row past the page tells whether there is a next one.
+        query_limit = None if keyword else (limit + 1 if page else limit)
+        query_offset = 0 if keyword else offset
+
+        # Determine which module to list (None = root)
+        target_module = None
+        if module_path:
+            target_module = db.get_module_by_path(module_path)
+            if target_module is None:
+                _fail(f"Module '{module_path}' not found.", "not_found")
+
+        if tree:
+            _print_tree(target_module, keyword)
+            return
+        
+        # Machine-readable or custom format: snippets only, no table
+        if count_only or (format_string is not None and format_string != "table"):
+            from macolint.formatting import format_snippet_line, snippet_info_to_dict
+            infos = db.list_snippet_infos_in_module(
+                target_module, content_type=content_type, tags=tag_filters,
+                sort=sort, reverse=reverse, limit=query_limit, min_lines=min_lines,
+                since=since, until=until, date_field=date_field, team=team,
+                offset=query_offset,
+            )
+            if keyword:
+                keyword_lower = keyword.lower()
+                infos = [i for i in infos if keyword_lower in i.path.lower()][offset:]
+            infos = infos[:limit]
+            if count_only:
+                print(len(infos))
+            elif format_string == "json":
+                print(json.dumps([snippet_info_to_dict(info) for info in infos], indent=2))
+            elif format_string == "plain":
+                for info in infos:
+                    print(info.path)
+            else:
+                for info in infos:
+                    print(format_snippet_line(format_string, info, time_format))
+            return
+        
+        # Get direct children of the target module
+        # (type and tag filters only apply to snippets, so modules are hidden)
+        filtered = bool(
+            content_type or tag_filters or team or min_lines is not None or since or until
+        )
+        # Modules belong to the first page only
+        child_modules = [] if filtered or (page or 1) > 1 else db.get_module_children(target_module)
+        child_infos = db.list_snippet_infos_in_module(
+            target_module, content_type=content_type, tags=tag_filters,
+            sort=sort, reverse=reverse, limit=query_limit, min_lines=min_lines,
+            since=since, until=until, date_field=date_field, team=team,
+            offset=query_offset,
+        )
+        child_snippets = [info.path for info in child_infos]
+        languages = {info.path: info.language for info in child_infos}
+        from macolint.formatting import format_timestamp
+        details = {
+            info.path: (
+                "-" if info.byte_len is None else _format_size(info.byte_len),
+                "-" if info.line_count is None else str(info.line_count),
+                format_timestamp(info.updated_at, time_format),
+            )
+            for info in child_infos
+        }
+        pinned = {info.path for info in child_infos if info.pinned}
+        descriptions = {info.path: info.description or "" for info in child_infos}
+        
+        # Build module paths
+        if target_module is None:
+            # At root: just module names
+            module_paths = [m.name for m in child_modules]
+        else:
+            # Inside a module: show relative names (just the module name, not full path)
+            module_paths = [m.name for m in child_modules]
+        
+        # Build snippet paths (these are already full paths from list_snippet_infos_in_module)
+        snippet_paths = child_snippets
+        
+        # Apply keyword filter if provided
+        if keyword:
+            keyword_lower = keyword.lower()
+            module_paths = [m for m in module_paths if keyword_lower in m.lower()]
+            snippet_paths = [s for s in snippet_paths if keyword_lower in s.lower()][offset:]
+        more_pages = bool(page) and len(snippet_paths) > limit
+        snippet_paths = snippet_paths[:limit]
+        
+        # Show modules with a trailing "/" to distinguish them
+        # Add (*) indicator to shared snippets and a leading ★ to pinned ones
+        display_entries = []
+        pinned_entries = []
+        for m in module_paths:
+            display_entries.append((f"{m}/", "", "", "", "", ""))
+        for snippet_path in snippet_paths:
+            is_shared = db.is_snippet_shared(snippet_path)
+            display_name = f"{snippet_path}*" if is_shared else snippet_path
+            entry = (display_name, languages.get(snippet_path) or "", *details[snippet_path],
+                     descriptions[snippet_path])
+            if snippet_path in pinned:
+                pinned_entries.append((f"★ {display_name}", *entry[1:]))
+            else:
+                display_entries.append(entry)
+        
+        # Stable sort so modules and snippets are mixed alphabetically,
+        # unless an explicit order was requested (modules first, then snippets).
+        # Pinned snippets always come first, in query order.
+        if ordered:
+            display_entries = sorted(display_entries[:len(module_paths)]) + \
+                display_entries[len(module_paths):]
+        else:
+            display_entries = sorted(display_entries)
+        display_entries = pinned_entries + display_entries
+
+        # Build title to show which module we're listing
+        if target_module is None:
+            title = "Snippets" + (f" (filtered: {keyword})" if keyword else "")
+        else:
+            module_full_path = db.get_module_full_path(target_module)
+            title = f"Snippets in {module_full_path}" + (f" (filtered: {keyword})" if keyword else "")
+        if content_type:
+            title += f" [type: {content_type}]"
+        if tag_filters:
+            title += f" [tag: {', '.join(db.normalize_tags(tag_filters))}]"
+        if team:
+            title += f" [team: {team}]"
+        if min_lines is not None:
+            title += f" [min lines: {min_lines}]"
+        if since_text or until_text:
+            title += f" [{date_field}: {since_text or '…'} to {until_text or 'now'}]"
+        if page:
+            title += f" [page {page}]"
+        
+        # Use a custom display function or modify the existing one
+        if not display_entries:
+            if (page or 1) > 1:
+                console.print(f"[yellow]No snippets on page {page}.[/yellow]")
+            elif keyword:
+                console.print(f"[yellow]No items found matching '{keyword}' in '{module_full_path if target_module else '/'}'.[/yellow]")
+            else:
+                location = db.get_module_full_path(target_module) if target_module else "/"
+                console.print(f"[yellow]No snippets or modules in '{location}'.[/yellow]")
+        else:
+            from rich.table import Table
+            from rich.text import Text
+            table = Table(title=title)
+            table.add_column("Name", style="cyan")
+            table.add_column("Language", style="dim")
+            table.add_column("Size", style="dim", justify="right")
+            table.add_column("Lines", style="dim", justify="right")
+            table.add_column("Updated", style="dim")
+            # Only when some snippet has one (snip save --desc)
+            with_descriptions = any(entry[5] for entry in display_entries)
+            if with_descriptions:
+                table.add_column("Description")
+            for entry, language, size, lines, updated, description in display_entries:
+                cells = [language, size, lines, updated]
+                if with_descriptions:
+                    cells.append(Text(description))
+                # Modules (ending with /) in yellow, snippets in cyan
+                if entry.endswith("/"):
+                    table.add_row(Text(entry, style="yellow"), *cells)
+                else:
+                    table.add_row(entry, *cells)
+            console.print(table)
+            if more_pages:
+                console.print(f"[dim]More snippets on page {page + 1} (--page {page + 1}).[/dim]")
+        
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+def _print_tree(target_module, keyword: Optional[str]):
+    """`snip list --tree`: every module and snippet below target_module as a tree."""
+    from macolint.formatting import tree_lines
+    from rich.markup import escape
+
+    root = db.get_module_full_path(target_module) if target_module else ""
+    prefix = f"{root}/" if root else ""
+    module_paths = [m[len(prefix):] for m in db.list_modules() if m.startswith(prefix)]
+    snippet_paths = [s[len(prefix):] for s in db.list_snippets() if s.startswith(prefix)]
+    if keyword:
+        keyword_lower = keyword.lower()
+        # Modules leading to a matching snippet are added by tree_lines
+        module_paths = [m for m in module_paths if keyword_lower in (prefix + m).lower()]
+        snippet_paths = [s for s in snippet_paths if keyword_lower in (prefix + s).lower()]
+
+    if not module_paths and not snippet_paths:
+        if keyword:
+            console.print(f"[yellow]No items found matching '{keyword}' in '{root or '/'}'.[/yellow]")
+        else:
+            console.print(f"[yellow]No snippets or modules in '{root or '/'}'.[/yellow]")
+        return
+
+    console.print(f"[bold]{escape(prefix or '/')}[/bold]")
+    for indent, label in tree_lines(module_paths, snippet_paths):
+        style = "yellow" if label.endswith("/") else "cyan"
+        console.print(f"{indent}[{style}]{escape(label)}[/{style}]")
+
+
+@cli.command()
+@click.argument('query')
+@click.option(
+    '-C',
+    '--context',
+    'context',
+    type=click.IntRange(min=0),
+    default=1,
+    show_default=True,
+    help='Lines of context to show around each match.',
+)
+@click.option(
+    '--name-only',
+    is_flag=True,
+    help='Print only the names of matching snippets, one per line.',
+)
+@click.option(
+    '--reveal',
+    is_flag=True,
+    help='Also show matching lines of secret snippets.',
+)
+def search(query, context, name_only, reveal):
+    """
+    Search the content of all snippets.
+
+    \b
+    Snippets are decrypted in memory only; plaintext is never written to disk.
+    Matching is a case-insensitive substring match on each line.
+
+    \b
+    OPTIONS:
+      -C, --context N    Lines of context around each match (default: 1).
+      --name-only        Print only matching snippet names (for scripting).
+      --reveal           Show matching lines of secret snippets too. Without it,
+                         secrets are listed by name only.
+                         Protected snippets are never searched.
+
+    \b
+    EXAMPLES:
+      snip search kubectl
+      snip search "docker run" -C 0
+      snip search postgres --name-only | xargs -n1 snip get
+    """
+    try:
+        from macolint.search import find_hunks
+        from rich.markup import escape
+
+        from macolint.crypto import is_protected
+
+        found = 0
+        for path, content, kind in db.get_all_snippet_contents():
+            if is_protected(content):
+                # Only the ciphertext is available without the passphrase
+                continue
+            hunks = find_hunks(content, query, context=context)
+            if not hunks:
+                continue
+            found += 1
+
+            if name_only:
+                print(path)
+                continue
+
+            if found > 1:
+                console.print()
+            console.print(f"[cyan]{escape(path)}[/cyan]")
+            if kind == "secret" and not reveal:
+                console.print("  [dim](secret: use --reveal to show matching lines)[/dim]")
+                continue
+            for hunk_idx, hunk in enumerate(hunks):
+                if hunk_idx > 0:
+                    console.print("  [dim]--[/dim]")
+                for line_number, line, is_match in hunk:
+                    if is_match:
+                        console.print(f"  [green]{line_number}:[/green] {escape(line)}")
+                    else:
+                        console.print(f"  [dim]{line_number}-[/dim] [dim]{escape(line)}[/dim]")
+
+        if not found and not name_only:
+            console.print(f"[yellow]No snippets contain '{escape(query)}'.[/yellow]")
+
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@cli.command()
+@click.argument('pattern')
+@click.option(
+    '-i',
+    '--ignore-case',
+    is_flag=True,
+    help='Match case-insensitively.',
+)
+@click.option(
+    '-l',
+    '--files-with-matches',
+    'names_only',
+    is_flag=True,
+    help='Print only the names of matching snippets, one per line.',
+)
+@click.option(
+    '--reveal',
+    is_flag=True,
+    help='Also print matching lines of secret snippets.',
+)
+def grep(pattern, ignore_case, names_only, reveal):
+    """
+    Search snippet content with a regular expression.
+
+    \b
+    Prints NAME:LINE: TEXT for every matching line, like grep. Patterns use
+    Python regular expression syntax and are matched against each line
+    (^ and $ anchor to the line). Snippets are decrypted one at a time, in
+    memory only. Exits with 1 when nothing matches.
+
+    \b
+    OPTIONS:
+      -i, --ignore-case            Case-insensitive matching.
+      -l, --files-with-matches     Print only matching snippet names.
+      --reveal                     Print matching lines of secret snippets too.
+                                   Without it, a matching secret is only named
+                                   (on stderr). Protected snippets are never searched.
+
+    \b
+    EXAMPLES:
+      snip grep '^TODO'
+      snip grep -i 'docker (run|exec)'
+      snip grep -l 'postgres://' | xargs -n1 snip show
+    """
+    from rich.markup import escape
+    from macolint.crypto import is_protected
+    from macolint.search import compile_pattern, find_regex_lines
+
+    try:
+        regex = compile_pattern(pattern, ignore_case)
+    except ValueError as e:
+        _fail(f"Error: {e}", "usage")
+
+    try:
+        found = 0
+        for path, content, kind in db.iter_snippet_contents():
+            if is_protected(content):
+                # Only the ciphertext is available without the passphrase
+                continue
+            lines = find_regex_lines(content, regex)
+            if not lines:
+                continue
+            found += 1
+
+            if names_only:
+                print(path)
+            elif kind == "secret" and not reveal:
+                err_console.print(f"[dim]{escape(path)}: secret matches (use --reveal to show them)[/dim]")
+            else:
+                for line_number, line in lines:
+                    print(f"{path}:{line_number}: {line}")
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+    if not found:
+        if not names_only:
+            err_console.print(f"[yellow]No snippets match '{escape(pattern)}'.[/yellow]")
+        sys.exit(1)
+
+
+@cli.command()
+@click.argument('file', type=click.Path(dir_okay=False))
+@click.option('--force', is_flag=True, help='Overwrite FILE if it already exists.')
+def export(file, force):
+    """
+    Export all snippets to an encrypted, portable archive.
+
+    \b
+    The archive is a single JSON file holding every snippet's name, tags,
+    kind, timestamps and attachments. Content stays encrypted exactly as
+    stored; the master key is included, wrapped with a passphrase you
+    choose (PBKDF2-SHA256, random salt). Import it on another machine with
+    'snip import FILE'.
+
+    \b
+    OPTIONS:
+      --force    Overwrite FILE if it already exists.
+
+    \b
+    EXAMPLES:
+      snip export ~/backups/snippets.snip
+    """
+    try:
+        import getpass
+        from macolint.archive import build_archive
+        from macolint.config import get_master_key
+
+        if os.path.exists(file) and not force:
+            _fail(f"Error: '{file}' already exists. Use --force to overwrite.", "conflict")
+
+        passphrase = getpass.getpass("Archive passphrase: ")
+        if not passphrase:
+            _fail("Error: Passphrase cannot be empty.")
+        if getpass.getpass("Confirm passphrase: ") != passphrase:
+            _fail("Error: Passphrases do not match.")
+
+        records = db.get_export_records()
+        archive = build_archive(records, get_master_key(), passphrase)
+
+        fd = os.open(file, os.O_WRONLY | os.O_CREAT | os.O_TRUNC, 0o600)
+        with os.fdopen(fd, "w") as f:
+            json.dump(archive, f, indent=2)
+
+        console.print(f"[green]✓ Exported {len(records)} snippets to '{file}'.[/green]")
+        console.print("[yellow]You will need the passphrase to import this archive.[/yellow]")
+
+    except KeyboardInterrupt:
+        console.print("\n[yellow]Cancelled.[/yellow]")
+        sys.exit(1)
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@cli.command(name='import')
+@click.argument('file', type=click.Path(exists=True, dir_okay=False))
+@click.option(
+    '--strategy',
+    type=click.Choice(['skip', 'overwrite', 'rename']),
+    default='skip',
+    show_default=True,
+    help='What to do when an imported name already exists.',
+)
+@click.pass_obj
+def import_archive(run, file, strategy):
+    """
+    Import snippets from an archive made by 'snip export'.
+
+    \b
+    The archive's key is unwrapped with its passphrase; each snippet is
+    decrypted in memory and re-encrypted with your local key. Timestamps,
+    tags, kind and attachments are preserved.
+
+    \b
+    OPTIONS:
+      --strategy skip|overwrite|rename
+                 On a name collision: keep the local snippet (skip, default),
+                 replace it (overwrite), or import as 'name-1', 'name-2', ... (rename).
+
+    \b
+    EXAMPLES:
+      snip import ~/backups/snippets.snip
+      snip import snippets.snip --strategy rename
+      snip --dry-run import snippets.snip --strategy overwrite
+    """
+    try:
+        import getpass
+        from macolint.archive import open_archive, read_archive_snippets
+
+        with open(file, "r") as f:
+            try:
+                archive = json.load(f)
+            except ValueError:
+                _fail(f"Error: '{file}' is not a Macolint archive.")
+
+        passphrase = getpass.getpass("Archive passphrase: ")
+        records = read_archive_snippets(archive, open_archive(archive, passphrase))
+        results = db.import_snippets(records, strategy=strategy, dry_run=run.dry_run)
+
+        counts = {"imported": 0, "overwritten": 0, "renamed": 0, "skipped": 0, "failed": 0}
+        for record, (outcome, final_name, error) in zip(records, results):
+            counts[outcome] += 1
+            if outcome == "renamed":
+                action = "would be imported" if run.dry_run else "imported"
+                console.print(
+                    f"[yellow]'{record['name']}' exists; {action} as '{final_name}'.[/yellow]"
+                )
+            elif outcome == "overwritten" and run.dry_run:
+                console.print(f"[yellow]Would overwrite '{final_name}'.[/yellow]")
+            elif outcome == "failed":
+                console.print(f"[red]Error importing '{record['name']}': {error}[/red]")
+
+        summary = ", ".join(f"{outcome} {count}" for outcome, count in counts.items()
+                            if count or outcome == "imported")
+        if run.dry_run:
+            console.print(f"[cyan]Dry run, nothing was imported: {summary}[/cyan]")
+        else:
+            console.print(f"[green]✓ Import finished: {summary}[/green]")
+        if counts["failed"]:
+            sys.exit(1)
+
+    except KeyboardInterrupt:
+        console.print("\n[yellow]Cancelled.[/yellow]")
+        sys.exit(1)
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@cli.command(name='import-dir')
+@click.argument('directory', type=click.Path(exists=True, file_okay=False))
+@click.option(
+    '-r',
+    '--recursive',
+    is_flag=True,
+    help='Also import files in subdirectories (as modules).',
+)
+@click.option(
+    '--prefix',
+    required=False,
+    help="Module to import into, e.g. 'sql/'.",
+)
+@click.option(
+    '-f',
+    '--force',
+    is_flag=True,
+    help='Overwrite snippets that already exist.',
+)
+@click.pass_obj
+def import_dir(run, directory, recursive, prefix, force):
+    """
+    Save every file in a directory as a snippet.
+
+    \b
+    Each file becomes a snippet named after its file name without the
+    extension, and its language is taken from the extension (.sql, .sh,
+    .py, ...). With --recursive, subdirectories become modules:
+    'db/users.sql' is saved as 'db/users'. Hidden files, binary files and
+    files that are not UTF-8 text are skipped with a warning.
+
+    \b
+    OPTIONS:
+      -r, --recursive    Descend into subdirectories.
+      --prefix MODULE    Save the snippets under MODULE (created if needed).
+      -f, --force        Overwrite existing snippets; without it they are skipped.
+
+    \b
+    EXAMPLES:
+      snip import-dir ~/queries --prefix sql/
+      snip import-dir ~/scripts --recursive
+    """
+    try:
+        from macolint.bulk import read_directory
+
+        records, skipped = read_directory(directory, recursive=recursive, prefix=prefix)
+
+        imported = 0
+        for record in records:
+            exists = db.get_snippet(record.name) is not None
+            if not force and exists:
+                skipped.append((record.path, f"snippet '{record.name}' already exists"))
+                continue
+            if run.dry_run:
+                action = "overwrite" if exists else "create"
+                console.print(f"[cyan]Would {action} '{record.name}' from {record.path}[/cyan]")
+            else:
+                db.save_snippet(record.name, record.content, language=record.language)
+            imported += 1
+
+        for path, reason in skipped:
+            console.print(f"[yellow]Skipped {path}: {reason}[/yellow]")
+        verb = "Would import" if run.dry_run else "✓ Imported"
+        console.print(
+            f"[green]{verb} {imported} file(s)[/green]"
+            + (f"[yellow], skipped {len(skipped)}[/yellow]" if skipped else "")
+        )
+
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@cli.command(name='save-batch')
+@click.argument('document', type=click.File('r', encoding='utf-8'), default='-', required=False)
+@click.option(
+    '--format',
+    'document_format',
+    type=click.Choice(['auto', 'json', 'yaml']),
+    default='auto',
+    show_default=True,
+    help='Format of the document.',
+)
+@click.option(
+    '--allow-large',
+    is_flag=True,
+    help='Save content larger than max_snippet_bytes (settings.json).',
+)
+@click.pass_obj
+def save_batch(run, document, document_format, allow_large):
+    """
+    Save many snippets at once from a JSON or YAML document.
+
+    \b
+    The document is a list of entries, each with a name and content and
+    optionally tags, a language, a description, a type and a kind. Every entry is checked
+    before anything is saved, and the snippets are written in a single
+    transaction: if any entry fails, none are saved. Existing snippets
+    with the same name are updated.
+
+    \b
+    DOCUMENT FORMAT (JSON; YAML takes the same fields):
+      [
+        {"name": "db/users", "content": "SELECT * FROM users;",
+         "tags": ["sql"], "language": "sql", "description": "Every user row"},
+        {"name": "k8s/pods", "content": "kubectl get pods -A"}
+      ]
+
+    \b
+    OPTIONS:
+      DOCUMENT           File to read (default: stdin).
+      --format FORMAT    json, yaml, or auto (default): JSON if the document
+                         starts with '[' or '{', YAML otherwise.
+      --allow-large      Save entries over the max_snippet_bytes limit.
+
+    \b
+    EXAMPLES:
+      snip save-batch < snippets.json
+      snip save-batch provision.yaml
+      snip --dry-run save-batch snippets.json
+    """
+    try:
+        from rich.markup import escape
+        from macolint.bulk import parse_document
+
+        if document.name == '<stdin>' and sys.stdin.isatty():
+            _fail("Error: Pipe a document to snip save-batch, or pass its file name.", "usage")
+        records, errors = parse_document(document.read(), document_format)
+        max_bytes = 0 if allow_large else _max_snippet_bytes()
+        if max_bytes:
+            for record in records:
+                size = len(record.content.encode("utf-8"))
+                if size > max_bytes:
+                    errors.append((
+                        record.line_number,
+                        f"{record.name}: content is {_format_size(size)}, "
+                        f"over the {_format_size(max_bytes)} limit",
+                    ))
+
+        results = []
+        if not errors:
+            results = db.save_snippets_bulk([
+                (record.name, record.content, record.content_type, record.kind,
+                 record.tags, record.language, record.description)
+                for record in records
+            ], dry_run=run.dry_run, atomic=True)
+            for record, (_, error) in zip(records, results):
+                if error is not None:
+                    errors.append((record.line_number, f"{record.name}: {error}"))
+
+        if errors:
+            for number, message in sorted(errors):
+                where = f"Entry {number}: " if number else ""
+                err_console.print(f"[red]{where}{escape(message)}[/red]")
+            _fail(f"Error: Nothing saved; {len(errors)} problem(s) in the document.")
+
+        entries = []
+        for record, (created, _) in zip(records, results):
+            action = "created" if created else "updated"
+            entries.append({"name": record.name, "action": action})
+            if not _json_mode():
+                verb = f"Would be {action}" if run.dry_run else action.capitalize()
+                console.print(f"  {verb}: {escape(record.name)}")
+        created = sum(1 for entry in entries if entry["action"] == "created")
+        verb = "Would save" if run.dry_run else "✓ Saved"
+        _report(
+            f"[green]{verb} {len(entries)} snippets ({created} created, "
+            f"{len(entries) - created} updated)[/green]",
+            dry_run=run.dry_run, snippets=entries,
+        )
+
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+def _format_size(num_bytes: int) -> str:
+    """Format a byte count for human-readable output."""
+    if num_bytes < 1024:
+        return f"{num_bytes} B"
+    size = num_bytes / 1024
+    for unit in ("KB", "MB"):
+        if size < 1024:
+            return f"{size:.1f} {unit}"
+        size /= 1024
+    return f"{size:.1f} GB"
+
+
+@cli.command()
+@click.option(
+    '--format',
+    'output_format',
+    type=click.Choice(['table', 'json']),
+    default='table',
+    show_default=True,
+    help='Output format.',
+)
+@click.option(
+    '--json',
+    'as_json',
+    is_flag=True,
+    help='Same as --format json.',
+)
+def stats(output_format, as_json):
+    """
+    Show a summary of your snippet store.
+
+    \b
+    WHAT IT SHOWS:
+      - Number of snippets and modules
+      - Total encrypted size on disk, and the plaintext size and line count
+      - Oldest and newest snippet, most recent update
+      - Snippet counts per module, tag and language
+
+    \b
+    OPTIONS:
+      --format table|json    Output format; json gives one object (for scripts and
+                             dashboards), like `snip list --format json`.
+      --json                 Same as --format json.
+
+    \b
+    EXAMPLES:
+      snip stats
+      snip stats --format json
+    """
+    try:
+        store_stats = db.get_stats()
+
+        if as_json or output_format == 'json':
+            print(json.dumps(store_stats.to_dict(), indent=2))
+            return
+
+        from rich.table import Table
+        from macolint.formatting import format_timestamp
+
+        time_format = _time_format()
+
+        def fmt(value):
+            return format_timestamp(value, time_format) if value else "-"
+
+        table = Table(title="Snippet Stats", show_header=False)
+        table.add_column("Metric", style="cyan")
+        table.add_column("Value")
+        table.add_row("Snippets", str(store_stats.snippet_count))
+        table.add_row("Modules", str(store_stats.module_count))
+        table.add_row("Total size", _format_size(store_stats.total_size))
+        content_summary = (
+            f"{_format_size(store_stats.content_bytes)} in {store_stats.content_lines} lines"
+        )
+        if store_stats.unsized_count:
+            content_summary += f" ({store_stats.unsized_count} snippets not counted)"
+        table.add_row("Content", content_summary)
+        table.add_row("Oldest", fmt(store_stats.oldest_created_at))
+        table.add_row("Newest", fmt(store_stats.newest_created_at))
+        table.add_row("Last updated", fmt(store_stats.last_updated_at))
+        console.print(table)
+
+        if store_stats.by_module:
+            module_table = Table(title="Snippets per Module")
+            module_table.add_column("Module", style="yellow")
+            module_table.add_column("Snippets", justify="right")
+            for module_label, module_snippets in store_stats.by_module.items():
+                module_table.add_row(module_label, str(module_snippets))
+            console.print(module_table)
+
+        if store_stats.by_tag:
+            tag_table = Table(title="Snippets per Tag")
+            tag_table.add_column("Tag", style="cyan")
+            tag_table.add_column("Snippets", justify="right")
+            for tag, tag_snippets in store_stats.by_tag.items():
+                tag_table.add_row(tag, str(tag_snippets))
+            console.print(tag_table)
+
+        if store_stats.by_language:
+            language_table = Table(title="Snippets per Language")
+            language_table.add_column("Language", style="cyan")
+            language_table.add_column("Snippets", justify="right")
+            for language, language_snippets in store_stats.by_language.items():
+                language_table.add_row(language or "(unknown)", str(language_snippets))
+            console.print(language_table)
+
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@cli.command()
+def compact():
+    """
+    Reclaim unused space in the local snippet database.
+
+    \b
+    After many deletes and re-saves the database file keeps free pages
+    around. This runs SQLite's VACUUM (after checkpointing the WAL, if any)
+    and reports the file size before and after.
+
+    \b
+    EXAMPLES:
+      snip compact
+    """
+    try:
+        before, after = db.vacuum()
+        saved = max(before - after, 0)
+        console.print(
+            f"[green]✓ Database compacted: {_format_size(before)} → {_format_size(after)} "
+            f"({_format_size(saved)} reclaimed)[/green]"
+        )
     except Exception as e:
-        console.print(f"[red]Error: {e}[/red]")
-        sys.exit(1)
+        _fail(f"Error: {e}", _error_kind(e))
 
 
-@cli.command()
-@click.argument('keyword', required=False)
+@cli.command(name='restore-backup')
+@click.argument('backup', required=False)
 @click.option(
-    '-m',
-    '--module',
-    'module_path',
-    required=False,
-    help='List contents of a specific module instead of root level.',
+    '-y',
+    '--yes',
+    'assume_yes',
+    is_flag=True,
+    help='Restore without asking for confirmation.',
 )
-def list(keyword, module_path):
+def restore_from_backup(backup, assume_yes):
     """
-    List snippets and modules at a specific level.
-    
-    \b
-    LIST ROOT LEVEL:
-      List all top-level items:
-        snip list
-      
-      Filter by keyword:
-        snip list git
-        snip list deploy
-    
-    \b
-    LIST MODULE CONTENTS:
-      List contents of a specific module:
-        snip list -m module1
-        snip list -m module1/module2
-      
-      List module with keyword filter:
-        snip list -m module1 deploy
-    
+    List the automatic database backups, or restore one.
+
     \b
-    OUTPUT FORMAT:
-      - Modules are shown with trailing "/" (in yellow)
-      - Snippets are shown as full paths (in cyan)
-      - Only direct children are shown (not nested descendants)
-      - Sorted alphabetically
-    
+    Before a command that changes snippets, snip copies the database to
+    backups/ beside it if the newest backup is older than
+    backup_interval_secs (default one day), keeping the newest backup_keep
+    copies (default 7). Both are set in ~/.macolint/settings.json; an
+    interval of 0 turns backups off.
+
     \b
-    OPTIONS:
-      -m, --module MODULE_PATH    List contents of a specific module instead of root level.
-    
+    Without BACKUP the backups are listed. With it (a listed name or a
+    path) the current database is backed up once more and then replaced,
+    after checking that the backup opens with the current master key.
+
     \b
     EXAMPLES:
-      # List root level
-      snip list
-      snip list git
-      
-      # List module contents
-      snip list -m module1
-      snip list -m module1/module2
-      snip list -m module1 deploy
+      snip restore-backup
+      snip restore-backup snippets-20240501-143000.db
     """
+    from macolint.backup import backup_dir, list_backups, make_backup, restore_backup
+
     try:
-        # Determine which module to list (None = root)
-        target_module = None
-        if module_path:
-            target_module = db.get_module_by_path(module_path)
-            if target_module is None:
-                console.print(f"[red]Module '{module_path}' not found.[/red]")
-                sys.exit(1)
-        
-        # Get direct children of the target module
-        child_modules = db.get_module_children(target_module)
-        child_snippets = db.list_snippets_in_module(target_module)
-        
-        # Build module paths
-        if target_module is None:
-            # At root: just module names
-            module_paths = [m.name for m in child_modules]
-        else:
-            # Inside a module: show relative names (just the module name, not full path)
-            module_paths = [m.name for m in child_modules]
-        
-        # Build snippet paths (these are already full paths from list_snippets_in_module)
-        snippet_paths = child_snippets
-        
-        # Apply keyword filter if provided
-        if keyword:
-            keyword_lower = keyword.lower()
-            module_paths = [m for m in module_paths if keyword_lower in m.lower()]
-            snippet_paths = [s for s in snippet_paths if keyword_lower in s.lower()]
-        
-        # Show modules with a trailing "/" to distinguish them
-        # Add (*) indicator to shared snippets
-        display_entries = []
-        for m in module_paths:
-            display_entries.append(f"{m}/")
-        for snippet_path in snippet_paths:
-            is_shared = db.is_snippet_shared(snippet_path)
-            display_name = f"{snippet_path}*" if is_shared else snippet_path
-            display_entries.append(display_name)
-        
-        # Stable sort so modules and snippets are mixed alphabetically
-        display_entries = sorted(display_entries)
+        backups = list_backups(db.db_path)
+        if not backup:
+            if _json_mode():
+                print(json.dumps([
+                    {"name": path.name, "path": str(path), "bytes": path.stat().st_size,
+                     "created_at": datetime.fromtimestamp(path.stat().st_mtime).isoformat()}
+                    for path in backups
+                ], indent=2))
+                return
+            if not backups:
+                console.print(f"[yellow]No backups in {backup_dir(db.db_path)}.[/yellow]")
+                return
 
-        # Build title to show which module we're listing
-        if target_module is None:
-            title = "Snippets" + (f" (filtered: {keyword})" if keyword else "")
-        else:
-            module_full_path = db.get_module_full_path(target_module)
-            title = f"Snippets in {module_full_path}" + (f" (filtered: {keyword})" if keyword else "")
-        
-        # Use a custom display function or modify the existing one
-        if not display_entries:
-            if keyword:
-                console.print(f"[yellow]No items found matching '{keyword}' in '{module_full_path if target_module else '/'}'.[/yellow]")
-            else:
-                location = db.get_module_full_path(target_module) if target_module else "/"
-                console.print(f"[yellow]No snippets or modules in '{location}'.[/yellow]")
-        else:
             from rich.table import Table
-            from rich.text import Text
-            table = Table(title=title)
+            from macolint.formatting import format_timestamp
+
+            time_format = _time_format()
+            table = Table(title=f"Backups in {backup_dir(db.db_path)}")
             table.add_column("Name", style="cyan")
-            for entry in display_entries:
-                # Modules (ending with /) in yellow, snippets in cyan
-                if entry.endswith("/"):
-                    table.add_row(Text(entry, style="yellow"))
-                else:
-                    table.add_row(entry)
+            table.add_column("Created")
+            table.add_column("Size", justify="right")
+            for path in backups:
+                table.add_row(
+                    path.name,
+                    format_timestamp(datetime.fromtimestamp(path.stat().st_mtime), time_format),
+                    _format_size(path.stat().st_size),
+                )
             console.print(table)
-        
+            return
+
+        source = Path(backup).expanduser()
+        if not source.is_file():
+            source = backup_dir(db.db_path) / backup
+        if not source.is_file():
+            _fail(f"Backup '{backup}' not found. Run 'snip restore-backup' to list them.", "not_found")
+
+        if not assume_yes and not click.confirm(
+            f"Replace the current database with {source.name}? (It is backed up first.)"
+        ):
+            console.print("[yellow]Restore cancelled.[/yellow]")
+            return
+
+        # Keep every existing backup, including the one being restored
+        _, keep = _backup_settings()
+        saved = make_backup(db, max(keep, len(backups) + 1))
+
+        def verify(path: Path):
+            try:
+                Database(db_path=path)
+            except (ValueError, sqlite3.DatabaseError) as e:
+                raise ValueError(f"{source.name} cannot be opened with the current key and settings: {e}")
+
+        restore_backup(db.db_path, source, verify)
+        _report(
+            f"[green]✓ Restored {source.name}; the previous database was saved as {saved.name}.[/green]",
+            restored=str(source), backup=str(saved),
+        )
     except Exception as e:
-        console.print(f"[red]Error: {e}[/red]")
-        sys.exit(1)
+        _fail(f"Error: {e}", _error_kind(e))
 
 
 def _auto_detect_shell() -> str:
@@ -1366,12 +4736,11 @@ def setup(shell, fix_path, force):
         console.print("[green]The snippet will automatically appear in your command line![/green]")
         
     except Exception as e:
-        console.print(f"[red]Error: {e}[/red]")
-        sys.exit(1)
+        _fail(f"Error: {e}", _error_kind(e))
 
 
 # Wrapper version - increment this when the wrapper code changes
-WRAPPER_VERSION = "2.5"
+WRAPPER_VERSION = "2.6"
 
 def _get_wrapper_code(shell: str) -> str:
     """Get the wrapper code for the specified shell."""
@@ -1386,8 +4755,9 @@ snip() {{
   
   # If this is 'snip get' (with or without name), use the wrapper behavior
   if [ "${{1}}" = "get" ]; then
-    # If second arg looks like an option (starts with -), fall back to normal call
-    if [ -n "${{2}}" ] && [[ "${{2}}" == -* ]]; then
+    # If second arg looks like an option (starts with -) or extra options follow
+    # the name (e.g. --clipboard), fall back to normal call
+    if [ $# -gt 2 ] || {{ [ -n "${{2}}" ] && [[ "${{2}}" == -* ]]; }}; then
       "$snip_cmd" "${{@}}"
     elif [ -n "${{2}}" ]; then
       # Has name: get snippet directly
@@ -1427,8 +4797,9 @@ snip() {{
   
   # If this is 'snip get' (with or without name), use the wrapper behavior
   if [ "${{1}}" = "get" ]; then
-    # If second arg looks like an option (starts with -), fall back to normal call
-    if [ -n "${{2}}" ] && [[ "${{2}}" == -* ]]; then
+    # If second arg looks like an option (starts with -) or extra options follow
+    # the name (e.g. --clipboard), fall back to normal call
+    if [ $# -gt 2 ] || {{ [ -n "${{2}}" ] && [[ "${{2}}" == -* ]]; }}; then
       "$snip_cmd" "${{@}}"
     elif [ -n "${{2}}" ]; then
       # Has name: get snippet directly
@@ -1462,8 +4833,11 @@ snip() {{
 function snip
     # If this is 'snip get' (with or without name), use the wrapper behavior
     if [ "${{argv[1]}}" = "get" ]
+        # If extra options follow the name (e.g. --clipboard), fall back to normal call
+        if [ (count $argv) -gt 2 ]
+            command snip $argv
         # If second arg looks like an option (starts with -), fall back to normal call
-        if [ (count $argv) -ge 2 -a (string match -q -- '-' (string sub -s 1 -l 1 $argv[2])) ]
+        else if [ (count $argv) -ge 2 -a (string match -q -- '-' (string sub -s 1 -l 1 $argv[2])) ]
             command snip $argv
         else if [ -n "${{argv[2]}}" ]
             # Has name: get snippet directly
@@ -1743,8 +5117,7 @@ def login():
         if not success:
             sys.exit(1)
     except Exception as e:
-        console.print(f"[red]Error: {e}[/red]")
-        sys.exit(1)
+        _fail(f"Error: {e}", _error_kind(e))
 
 
 @auth.command()
@@ -1773,8 +5146,7 @@ def signup():
         if not success:
             sys.exit(1)
     except Exception as e:
-        console.print(f"[red]Error: {e}[/red]")
-        sys.exit(1)
+        _fail(f"Error: {e}", _error_kind(e))
 
 
 @auth.command()
@@ -1794,160 +5166,487 @@ def logout():
         from macolint.auth import logout as auth_logout
         auth_logout()
     except Exception as e:
-        console.print(f"[red]Error: {e}[/red]")
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@cli.group(invoke_without_command=True)
+@click.option('--team', 'team_name', required=False, help='Sync shared snippets with team space')
+@click.option(
+    '--strategy',
+    type=click.Choice(SYNC_STRATEGIES),
+    default='newer',
+    show_default=True,
+    help='How to settle snippets changed both locally and on the server.',
+)
+@click.option(
+    '--status',
+    'show_status',
+    is_flag=True,
+    help='Only summarize what is pending and synced (same as snip sync status).',
+)
+@click.pass_context
+def sync(ctx, team_name, strategy, show_status):
+    """
+    Cloud sync commands.
+
+    \b
+    With no subcommand, pulls and then pushes: new and changed snippets on
+    the server are saved locally, then local changes are uploaded. Content
+    is encrypted with your passphrase before it leaves this machine, so the
+    server never sees plaintext. The server is the Supabase project set by
+    SUPABASE_URL and SUPABASE_ANON_KEY (environment or ~/.macolint/.env).
+    With --team, the snippets shared with that team (snip share) are synced
+    instead, encrypted with the team passphrase its members share.
+
+    \b
+    Each snippet is pending (new or changed since its last sync), synced,
+    or in conflict (changed on both sides); see 'snip sync status'. A
+    conflict is settled by --strategy: newer (default) keeps the side
+    updated last, local or remote always keeps that side. When the server
+    has no update time for a snippet, newer leaves it in conflict and push
+    skips it until it is resolved ('snip conflicts', 'snip resolve').
+
+    \b
+    Works offline-first: saving, editing and deleting only change the local
+    database, and mark what the next sync has to send. If the server cannot
+    be reached, whatever was not sent stays pending, and a later sync
+    retries it.
+
+    \b
+    EXAMPLES:
+      snip sync                      # pull, then push
+      snip sync --status             # how many snippets are pending
+      snip sync --strategy local     # local edits win every conflict
+      snip sync status               # what the next push would send
+      snip sync --team dev           # the same for the 'dev' team space
+    """
+    if ctx.invoked_subcommand is not None:
+        return
+    if show_status:
+        ctx.invoke(sync_status, team_name=team_name, show_all=False)
+        return
+    try:
+        from macolint.sync import sync_pull, sync_push
+
+        team_id, passphrase = _sync_session(team_name)
+        pulled, pull_errors = sync_pull(
+            passphrase, team_id=team_id, strategy=strategy, team_name=team_name
+        )
+        pushed, push_errors = sync_push(passphrase, team_id=team_id)
+        summary = f"Pulled {pulled} and pushed {pushed} snippets"
+        if pull_errors or push_errors:
+            console.print(f"\n[yellow]{summary}, with {pull_errors + push_errors} errors.[/yellow]")
+        else:
+            console.print(f"\n[green]✓ {summary}.[/green]")
+        _exit_on_sync_conflicts(team_id)
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+def _sync_session(team_name: Optional[str]) -> Tuple[Optional[str], str]:
+    """
+    Check the login, look up the team (if any) and ask for the sync
+    passphrase, or the team passphrase for a team. Returns (team_id, passphrase).
+    """
+    from macolint.auth import is_authenticated
+    from macolint.teams import get_team_by_name
+    import getpass
+
+    if not is_authenticated():
+        console.print("[red]Error: Not logged in.[/red]")
+        console.print("[yellow]Run 'snip auth login' first.[/yellow]")
         sys.exit(1)
 
+    team_id = None
+    if team_name:
+        team = get_team_by_name(team_name)
+        if team is None:
+            _fail(f"Error: Team '{team_name}' not found or you are not a member.", "not_found")
+        team_id = team.id
+
+    if team_name:
+        passphrase = getpass.getpass(f"Enter team passphrase for '{team_name}': ")
+    else:
+        passphrase = getpass.getpass("Enter sync passphrase: ")
+    if not passphrase:
+        _fail("Error: Passphrase cannot be empty.")
+    return team_id, passphrase
 
-@cli.group()
-def sync():
-    """Cloud sync commands."""
-    pass
+
+def _exit_on_sync_conflicts(team_id: Optional[str]) -> None:
+    """Exit with the conflict status if any snippet in this space is still in conflict."""
+    states = db.get_sync_states(team=team_id) if team_id else db.get_sync_states(shared=False)
+    if any(status == "conflict" for status, _, _ in states.values()):
+        sys.exit(ERROR_EXIT_CODES["conflict"])
 
 
 @sync.command()
 @click.option('--team', 'team_name', required=False, help='Push shared snippets to team space')
-def push(team_name):
+@click.option(
+    '--all',
+    'push_all',
+    is_flag=True,
+    help='Push every snippet, not only those changed since the last sync.',
+)
+def push(team_name, push_all):
     """
     Push local snippets to the cloud (encrypted).
     
     \b
     This command will:
-    1. Encrypt all local snippets using your passphrase
-    2. Upload them to Supabase
+    1. Encrypt the snippets changed since they were last synced, using your passphrase
+    2. Upload them to Supabase and mark them synced
     3. Show a summary of what was synced
     
     \b
     You must be logged in (run 'snip auth login' first).
-    You'll be prompted for your passphrase.
+    You'll be prompted for your passphrase (with --team, the team passphrase).
+    Snippets in conflict with the server are skipped; settle them with
+    'snip resolve NAME --take local|remote|merge'.
     
     \b
     OPTIONS:
-      --team TEAM_NAME    Push shared snippets to team space instead of personal space
+      --team TEAM_NAME    Push the snippets shared with the team (snip share) to team
+                          space instead of personal space, encrypted under the team key
+      --all               Push every snippet again, e.g. after changing the passphrase
     
     \b
     EXAMPLES:
       snip sync push              # Push personal snippets
       snip sync push --team dev   # Push shared snippets to 'dev' team
+      snip sync push --all        # Re-upload everything
     """
     try:
-        from macolint.auth import is_authenticated
         from macolint.sync import sync_push
-        from macolint.teams import get_team_by_name
-        import getpass
-        
-        if not is_authenticated():
-            console.print("[red]Error: Not logged in.[/red]")
-            console.print("[yellow]Run 'snip auth login' first.[/yellow]")
-            sys.exit(1)
-        
-        team_id = None
-        if team_name:
-            team = get_team_by_name(team_name)
-            if team is None:
-                console.print(f"[red]Error: Team '{team_name}' not found or you are not a member.[/red]")
-                sys.exit(1)
-            team_id = team.id
-        
-        # Prompt for passphrase
-        passphrase = getpass.getpass("Enter passphrase to encrypt snippets: ")
-        if not passphrase:
-            console.print("[red]Error: Passphrase cannot be empty.[/red]")
-            sys.exit(1)
+
+        team_id, passphrase = _sync_session(team_name)
         
         # Push snippets
-        pushed, errors = sync_push(passphrase, team_id=team_id)
+        pushed, errors = sync_push(passphrase, team_id=team_id, push_all=push_all)
         
         if errors > 0:
             console.print(f"\n[yellow]Pushed {pushed} snippets with {errors} errors.[/yellow]")
-        else:
+        elif pushed:
             if team_name:
                 console.print(f"\n[green]✓ Successfully pushed {pushed} shared snippets to team '{team_name}'.[/green]")
             else:
                 console.print(f"\n[green]✓ Successfully pushed {pushed} snippets to cloud.[/green]")
+        _exit_on_sync_conflicts(team_id)
+            
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@sync.command()
+@click.option('--team', 'team_name', required=False, help='Pull shared snippets from team space')
+@click.option(
+    '--strategy',
+    type=click.Choice(SYNC_STRATEGIES),
+    default='newer',
+    show_default=True,
+    help='How to settle snippets changed both locally and on the server.',
+)
+@click.option(
+    '--legacy',
+    is_flag=True,
+    help='With --team, also ask for your sync passphrase, for snippets shared before team keys.',
+)
+def pull(team_name, strategy, legacy):
+    """
+    Pull snippets from the cloud and decrypt locally.
+    
+    \b
+    This command will:
+    1. Download all your encrypted snippets from Supabase
+    2. Decrypt them using your passphrase
+    3. Save new snippets, and update local ones not changed since their last sync
+    
+    \b
+    You must be logged in (run 'snip auth login' first).
+    You'll be prompted for your passphrase (with --team, the team passphrase).
+    
+    \b
+    OPTIONS:
+      --team TEAM_NAME    Pull shared snippets from team space instead of personal space
+      --strategy NAME     For snippets changed both locally and on the server:
+                          newer (default) keeps the side updated last, local or
+                          remote always keeps that side. Kept local content is
+                          sent by the next push.
+      --legacy            With --team, also ask for your sync passphrase: team
+                          snippets pushed by older versions were encrypted with
+                          the sharer's own one. They are marked pending, so the
+                          next push --team re-encrypts them under the team key.
+    
+    \b
+    EXAMPLES:
+      snip sync pull                     # Pull personal snippets
+      snip sync pull --team dev          # Pull shared snippets from 'dev' team
+      snip sync pull --team dev --legacy # Also those shared before team keys
+      snip sync pull --strategy remote   # The server wins every conflict
+    """
+    try:
+        from macolint.sync import sync_pull
+
+        if legacy and not team_name:
+            _fail("Error: --legacy needs --team.", "usage")
+        team_id, passphrase = _sync_session(team_name)
+        legacy_passphrase = None
+        if legacy:
+            import getpass
+
+            legacy_passphrase = getpass.getpass("Enter sync passphrase: ")
+            if not legacy_passphrase:
+                _fail("Error: Passphrase cannot be empty.")
         
-        if pushed == 0 and errors == 0:
+        # Pull snippets
+        pulled, errors = sync_pull(
+            passphrase, team_id=team_id, strategy=strategy, team_name=team_name,
+            legacy_passphrase=legacy_passphrase,
+        )
+        
+        if errors > 0:
+            console.print(f"\n[yellow]Pulled {pulled} snippets with {errors} errors.[/yellow]")
+            if errors > 0:
+                console.print("[yellow]Some snippets failed to decrypt. Check your passphrase.[/yellow]")
+        elif pulled:
             if team_name:
-                console.print("[yellow]No shared snippets to sync for this team.[/yellow]")
+                console.print(f"\n[green]✓ Successfully pulled {pulled} shared snippets from team '{team_name}'.[/green]")
             else:
-                console.print("[yellow]No snippets to sync.[/yellow]")
+                console.print(f"\n[green]✓ Successfully pulled {pulled} snippets from cloud.[/green]")
+        else:
+            console.print("[green]Already up to date.[/green]")
+        _exit_on_sync_conflicts(team_id)
             
     except Exception as e:
-        console.print(f"[red]Error: {e}[/red]")
-        sys.exit(1)
+        _fail(f"Error: {e}", _error_kind(e))
 
 
-@sync.command()
-@click.option('--team', 'team_name', required=False, help='Pull shared snippets from team space')
-def pull(team_name):
+@sync.command(name='status')
+@click.option('--team', 'team_name', required=False, help="Show a team's snippets instead of personal ones")
+@click.option('--all', 'show_all', is_flag=True, help='List synced snippets too.')
+def sync_status(team_name, show_all):
+    """
+    Show which snippets the next push would send.
+
+    \b
+    Lists snippets that are pending (never synced, or changed since their
+    last sync) or in conflict with the server, with when each was last
+    synced, then the snippets deleted here that the next push deletes on
+    the server. Works offline: nothing is sent or decrypted. With the global
+    --json flag prints {"snippets": [{"name", "status", "synced_at"}, ...],
+    "deletions": [name, ...]}.
+
+    \b
+    EXAMPLES:
+      snip sync status
+      snip sync status --all
+      snip --json sync status | jq -r '.snippets[].name'
+    """
+    try:
+        from rich.markup import escape
+        from macolint.formatting import format_timestamp
+
+        if team_name:
+            states = db.get_sync_states(team=team_name)
+            deletions = []
+        else:
+            states = db.get_sync_states(shared=False)
+            deletions = [path for _, path in db.get_sync_deletions()]
+        rows = sorted(
+            (path, status, synced_at) for path, (status, synced_at, _) in states.items()
+            if show_all or status != "synced"
+        )
+        if _json_mode():
+            _report("", snippets=[
+                {"name": path, "status": status,
+                 "synced_at": synced_at.isoformat(timespec="seconds") if synced_at else None}
+                for path, status, synced_at in rows
+            ], deletions=deletions)
+            return
+        counts = {status: 0 for status in SYNC_STATUSES}
+        for status, _, _ in states.values():
+            counts[status] += 1
+        summary = (
+            f"{counts['pending']} pending, {counts['synced']} synced, "
+            f"{counts['conflict']} in conflict"
+        )
+        if deletions:
+            summary += f", {len(deletions)} deleted here"
+        console.print(f"[bold]{summary}[/bold]")
+        styles = {"pending": "yellow", "synced": "green", "conflict": "red"}
+        time_format = _time_format(False)
+        for path, status, synced_at in rows:
+            last = format_timestamp(synced_at, time_format) if synced_at else "never synced"
+            style = styles[status]
+            console.print(f"  [{style}]{status:<8}[/{style}] {escape(path)}  [dim]{last}[/dim]")
+        for path in deletions:
+            console.print(f"  [magenta]{'deleted':<8}[/magenta] {escape(path)}")
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@cli.command()
+@click.option('--diff', 'show_diff', is_flag=True, help='Show how each local version differs from the server.')
+def conflicts(show_diff):
+    """
+    List snippets in conflict with the cloud.
+
+    \b
+    A snippet is in conflict when it changed both here and on the server
+    since it was last synced, and 'snip sync pull' could not settle it
+    (see --strategy). The server's version is kept, encrypted, until the
+    conflict is resolved with 'snip resolve'. Push skips these snippets.
+    With the global --json flag prints {"snippets": [{"name", "updated_at",
+    "synced_at"}, ...]}.
+
+    \b
+    OPTIONS:
+      --diff    Show a diff from the server's version to the local one.
+                Secrets and protected snippets are listed without one.
+
+    \b
+    EXAMPLES:
+      snip conflicts
+      snip conflicts --diff
+      snip resolve deploy_staging --take merge
+    """
+    try:
+        import difflib
+        from rich.markup import escape
+        from macolint.formatting import format_timestamp
+
+        states = db.get_sync_states()
+        rows = sorted(
+            (path, synced_at, updated_at)
+            for path, (status, synced_at, updated_at) in states.items() if status == "conflict"
+        )
+        if _json_mode():
+            _report("", snippets=[
+                {"name": path,
+                 "updated_at": updated_at.isoformat(timespec="seconds"),
+                 "synced_at": synced_at.isoformat(timespec="seconds") if synced_at else None}
+                for path, synced_at, updated_at in rows
+            ])
+            return
+        if not rows:
+            console.print("[green]No sync conflicts.[/green]")
+            return
+
+        time_format = _time_format(False)
+        for path, synced_at, updated_at in rows:
+            last = format_timestamp(synced_at, time_format) if synced_at else "never"
+            console.print(
+                f"[red]{escape(path)}[/red]  [dim]changed {format_timestamp(updated_at, time_format)}, "
+                f"last synced {last}[/dim]"
+            )
+            if not show_diff:
+                continue
+            snippet = db.get_snippet(path)
+            remote = db.get_sync_conflict(path)
+            if snippet.kind == "secret" or snippet.protected or remote is None:
+                console.print("  [dim](no diff for secrets and protected snippets)[/dim]")
+                continue
+            diff = difflib.unified_diff(
+                remote.splitlines(), snippet.content.splitlines(),
+                fromfile="remote", tofile="local", lineterm="",
+            )
+            for line in diff:
+                style = "green" if line.startswith("+") else "red" if line.startswith("-") else "dim"
+                console.print(f"  [{style}]{escape(line)}[/{style}]")
+        console.print(
+            f"\n[yellow]{len(rows)} conflict(s). Resolve each with "
+            f"'snip resolve NAME --take local|remote|merge'.[/yellow]"
+        )
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+# Markers around the two versions offered by snip resolve --take merge
+MERGE_MARKERS = ("<<<<<<< local", "=======", ">>>>>>> remote")
+
+
+@cli.command()
+@click.argument('name', shell_complete=_complete_snippet_names)
+@click.option(
+    '--take',
+    type=click.Choice(['local', 'remote', 'merge']),
+    required=True,
+    help='Keep the local version, the server version, or merge them in $EDITOR.',
+)
+def resolve(name, take):
     """
-    Pull snippets from the cloud and decrypt locally.
-    
-    \b
-    This command will:
-    1. Download all your encrypted snippets from Supabase
-    2. Decrypt them using your passphrase
-    3. Save them to your local database
-    
+    Resolve a snippet's sync conflict (see 'snip conflicts').
+
     \b
-    You must be logged in (run 'snip auth login' first).
-    You'll be prompted for your passphrase.
-    
+    --take remote   Replace the local content with the server's version.
+                    The snippet is synced again.
+    --take local    Keep the local content. It is pending, and the next
+                    'snip sync push' uploads it over the server's version.
+    --take merge    Open both versions in $VISUAL/$EDITOR, between
+                    '<<<<<<< local', '=======' and '>>>>>>> remote' lines
+                    as in git; save the reconciled content with the marker
+                    lines removed. The result is encrypted and saved as a
+                    new version (see 'snip history'), and is pending like
+                    --take local. Leaving a marker line, or quitting the
+                    editor with an error, changes nothing.
+
     \b
-    OPTIONS:
-      --team TEAM_NAME    Pull shared snippets from team space instead of personal space
-    
+    Protected snippets ask for their passphrase to merge, and the result
+    stays protected under it.
+
     \b
     EXAMPLES:
-      snip sync pull              # Pull personal snippets
-      snip sync pull --team dev   # Pull shared snippets from 'dev' team
+      snip resolve deploy_staging --take remote
+      snip resolve notes/todo --take merge && snip sync push
     """
     try:
-        from macolint.auth import is_authenticated
-        from macolint.sync import sync_pull
-        from macolint.teams import get_team_by_name
-        import getpass
-        
-        if not is_authenticated():
-            console.print("[red]Error: Not logged in.[/red]")
-            console.print("[yellow]Run 'snip auth login' first.[/yellow]")
-            sys.exit(1)
-        
-        team_id = None
-        if team_name:
-            team = get_team_by_name(team_name)
-            if team is None:
-                console.print(f"[red]Error: Team '{team_name}' not found or you are not a member.[/red]")
-                sys.exit(1)
-            team_id = team.id
-        
-        # Prompt for passphrase
-        passphrase = getpass.getpass("Enter passphrase to decrypt snippets: ")
-        if not passphrase:
-            console.print("[red]Error: Passphrase cannot be empty.[/red]")
-            sys.exit(1)
-        
-        # Pull snippets
-        pulled, errors = sync_pull(passphrase, team_id=team_id)
-        
-        if errors > 0:
-            console.print(f"\n[yellow]Pulled {pulled} snippets with {errors} errors.[/yellow]")
-            if errors > 0:
-                console.print("[yellow]Some snippets failed to decrypt. Check your passphrase.[/yellow]")
-        else:
-            if team_name:
-                console.print(f"\n[green]✓ Successfully pulled {pulled} shared snippets from team '{team_name}'.[/green]")
-            else:
-                console.print(f"\n[green]✓ Successfully pulled {pulled} snippets from cloud.[/green]")
-        
-        if pulled == 0 and errors == 0:
-            if team_name:
-                console.print("[yellow]No snippets found on server for this team.[/yellow]")
-            else:
-                console.print("[yellow]No snippets found on server.[/yellow]")
-            
+        snippet = db.get_snippet(name)
+        if snippet is None:
+            _fail(f"Snippet '{name}' not found.", "not_found")
+        remote = db.get_sync_conflict(name)
+        if remote is None:
+            _fail(f"Error: Snippet '{name}' is not in conflict with the server.")
+
+        if take == "remote":
+            db.update_snippet(name, remote)
+            db.mark_synced(name, remote)
+            _report(f"[green]Snippet '{name}' now has the server's version.[/green]",
+                    name=name, take=take)
+            return
+        if take == "local":
+            db.set_sync_conflict(name, False, remote_content=remote)
+            _report(f"[green]Kept the local version of '{name}'. Run 'snip sync push' to upload it.[/green]",
+                    name=name, take=take)
+            return
+
+        from macolint.crypto import is_protected, protect, unprotect
+        from macolint.interactive import edit_in_external_editor, EditorError
+
+        passphrase = _unlock(snippet)
+        remote_text = unprotect(remote, passphrase) if passphrase and is_protected(remote) else remote
+        draft = "\n".join((
+            MERGE_MARKERS[0], snippet.content.rstrip("\n"),
+            MERGE_MARKERS[1], remote_text.rstrip("\n"),
+            MERGE_MARKERS[2],
+        )) + "\n"
+        try:
+            merged = edit_in_external_editor(draft).rstrip("\n")
+        except EditorError as e:
+            console.print(f"[yellow]{e} The conflict is unchanged.[/yellow]")
+            return
+        if any(line in MERGE_MARKERS for line in merged.splitlines()):
+            _fail(f"Error: The merged content still has conflict markers; '{name}' is unchanged.")
+        if not merged.strip():
+            _fail(f"Error: The merged content is empty; '{name}' is unchanged.")
+
+        if passphrase is not None:
+            merged = protect(merged, passphrase)
+        db.update_snippet(name, merged)
+        db.set_sync_conflict(name, False, remote_content=remote)
+        _report(f"[green]Merged '{name}'. Run 'snip sync push' to upload it.[/green]",
+                name=name, take=take)
     except Exception as e:
-        console.print(f"[red]Error: {e}[/red]")
-        sys.exit(1)
+        _fail(f"Error: {e}", _error_kind(e))
 
 
 @cli.group()
@@ -1982,11 +5681,9 @@ def create(name):
             console.print(f"[green]✓ Team '{name}' created successfully.[/green]")
             console.print(f"[green]Team ID: {team.id}[/green]")
         else:
-            console.print(f"[red]Failed to create team '{name}'.[/red]")
-            sys.exit(1)
+            _fail(f"Failed to create team '{name}'.")
     except Exception as e:
-        console.print(f"[red]Error: {e}[/red]")
-        sys.exit(1)
+        _fail(f"Error: {e}", _error_kind(e))
 
 
 @team.command()
@@ -2020,8 +5717,7 @@ def list():
         
         console.print(table)
     except Exception as e:
-        console.print(f"[red]Error: {e}[/red]")
-        sys.exit(1)
+        _fail(f"Error: {e}", _error_kind(e))
 
 
 @team.command()
@@ -2043,8 +5739,7 @@ def members(team_name):
         
         team = get_team_by_name(team_name)
         if team is None:
-            console.print(f"[red]Error: Team '{team_name}' not found or you are not a member.[/red]")
-            sys.exit(1)
+            _fail(f"Error: Team '{team_name}' not found or you are not a member.", "not_found")
         
         members_list = list_team_members(team.id)
         
@@ -2066,8 +5761,7 @@ def members(team_name):
         
         console.print(table)
     except Exception as e:
-        console.print(f"[red]Error: {e}[/red]")
-        sys.exit(1)
+        _fail(f"Error: {e}", _error_kind(e))
 
 
 @team.command()
@@ -2090,59 +5784,59 @@ def add(team_name, user_email):
         
         team = get_team_by_name(team_name)
         if team is None:
-            console.print(f"[red]Error: Team '{team_name}' not found or you are not a member.[/red]")
-            sys.exit(1)
+            _fail(f"Error: Team '{team_name}' not found or you are not a member.", "not_found")
         
         success = add_team_member(team.id, user_email)
         if success:
             console.print(f"[green]✓ User '{user_email}' added to team '{team_name}'.[/green]")
         else:
-            console.print(f"[red]Failed to add user to team.[/red]")
-            sys.exit(1)
+            _fail(f"Failed to add user to team.")
     except Exception as e:
-        console.print(f"[red]Error: {e}[/red]")
-        sys.exit(1)
+        _fail(f"Error: {e}", _error_kind(e))
 
 
 @cli.command()
 @click.argument('snippet_path')
-@click.argument('team_name')
-def share(snippet_path, team_name):
+@click.argument('team_name', required=False)
+@click.option('--team', 'team_option', metavar='TEAM_NAME', help='The team to share with.')
+def share(snippet_path, team_name, team_option):
     """
     Share a snippet with a team.
     
     \b
     This command will:
-    1. Mark the snippet as shared in your local database
+    1. Scope the snippet to the team in your local database
     2. Prepare it for syncing to the team space
     
     \b
     After sharing, run 'snip sync push --team TEAM_NAME' to upload to team space.
+    The snippet is then encrypted under the team key, derived from the team
+    passphrase that members agree on, instead of your personal passphrase, so
+    every member can pull and decrypt it. It is no longer pushed to your
+    personal space. 'snip list --team TEAM_NAME' lists a team's snippets.
     
     \b
     You must be logged in and be a member of the team.
     
     \b
     EXAMPLE:
-      snip share my_snippet dev-team
+      snip share my_snippet --team dev-team
       snip share module1/snippet dev-team
     """
     try:
-        from macolint.sharing import share_snippet
-        import getpass
+        if team_name and team_option and team_name != team_option:
+            _fail("Error: Give the team once, as TEAM_NAME or --team.", "usage")
+        team_name = team_name or team_option
+        if not team_name:
+            _fail("Error: Missing the team: snip share NAME --team TEAM_NAME.", "usage")
         
-        passphrase = getpass.getpass("Enter passphrase to encrypt snippet: ")
-        if not passphrase:
-            console.print("[red]Error: Passphrase cannot be empty.[/red]")
-            sys.exit(1)
+        from macolint.sharing import share_snippet
         
-        success = share_snippet(snippet_path, team_name, passphrase)
+        success = share_snippet(snippet_path, team_name)
         if not success:
-            console.print(f"[red]Failed to share snippet.[/red]")
-            sys.exit(1)
+            _fail(f"Failed to share snippet.")
     except Exception as e:
-        console.print(f"[red]Error: {e}[/red]")
-        sys.exit(1)
+        _fail(f"Error: {e}", _error_kind(e))
 
 
 @cli.command()
@@ -2170,11 +5864,9 @@ def unshare(snippet_path, team_name):
         
         success = unshare_snippet(snippet_path, team_name)
         if not success:
-            console.print(f"[red]Failed to unshare snippet.[/red]")
-            sys.exit(1)
+            _fail(f"Failed to unshare snippet.")
     except Exception as e:
-        console.print(f"[red]Error: {e}[/red]")
-        sys.exit(1)
+        _fail(f"Error: {e}", _error_kind(e))
 
 
 @cli.command()
@@ -2205,13 +5897,11 @@ def set_passphrase():
         
         passphrase1 = getpass.getpass("Enter passphrase: ")
         if not passphrase1:
-            console.print("[red]Error: Passphrase cannot be empty.[/red]")
-            sys.exit(1)
+            _fail("Error: Passphrase cannot be empty.")
         
         passphrase2 = getpass.getpass("Confirm passphrase: ")
         if passphrase1 != passphrase2:
-            console.print("[red]Error: Passphrases do not match.[/red]")
-            sys.exit(1)
+            _fail("Error: Passphrases do not match.")
         
         console.print("\n[green]✓ Passphrase set successfully![/green]")
         console.print("[yellow]Remember: You'll need this passphrase to sync snippets across devices.[/yellow]")
@@ -2221,12 +5911,268 @@ def set_passphrase():
         console.print("\n[yellow]Cancelled.[/yellow]")
         sys.exit(1)
     except Exception as e:
-        console.print(f"[red]Error: {e}[/red]")
-        sys.exit(1)
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@cli.command()
+@click.option(
+    '--dry-run',
+    is_flag=True,
+    help='Report how many items would be re-encrypted without changing anything.',
+)
+@click.option(
+    '-y',
+    '--yes',
+    'assume_yes',
+    is_flag=True,
+    help='Rotate without asking for confirmation.',
+)
+@click.pass_obj
+def change_key(run, dry_run, assume_yes):
+    """
+    Rotate the local master key and re-encrypt all snippets.
+
+    \b
+    Generates a fresh master key and re-encrypts every snippet and attachment
+    with it in a single transaction, so a failure leaves everything readable
+    with the old key. The new key replaces the stored one only after the
+    database has been rewritten.
+
+    \b
+    Cloud sync is unaffected: synced snippets are encrypted with your
+    passphrase, not the master key.
+
+    \b
+    Not available while MACOLINT_MASTER_KEY is set, since the new key
+    could not replace one that lives in the environment.
+
+    \b
+    OPTIONS:
+      --dry-run    Report how many snippets and attachments would be re-encrypted.
+      -y, --yes    Skip the confirmation prompt.
+
+    \b
+    EXAMPLES:
+      snip change-key --dry-run
+      snip change-key
+    """
+    try:
+        from macolint.config import (
+            MASTER_KEY_ENV,
+            generate_master_key,
+            get_env_master_key,
+            get_pending_key_file,
+            save_master_key,
+        )
+
+        if get_env_master_key() is not None:
+            _fail(
+                f"Error: The master key comes from {MASTER_KEY_ENV}; unset it to "
+                f"rotate the stored key, or set it to a new key and re-import an export."
+            )
+
+        dry_run = dry_run or run.dry_run
+        snippet_count, attachment_count = db.count_encrypted_rows()
+        summary = f"{snippet_count} snippets and {attachment_count} attachments"
+        pending_key_file = get_pending_key_file()
+
+        if dry_run:
+            console.print(f"[cyan]Would re-encrypt {summary} with a new master key.[/cyan]")
+            return
+
+        if pending_key_file.exists():
+            console.print(
+                f"[red]Error: A previous key rotation did not finish; its new key is in "
+                f"{pending_key_file}.[/red]"
+            )
+            console.print(
+                "[yellow]If 'snip get' fails to decrypt, store that key as the master key "
+                "before rotating again; otherwise delete the file.[/yellow]"
+            )
+            sys.exit(1)
+
+        if not assume_yes and not click.confirm(f"Re-encrypt {summary} with a new master key?"):
+            console.print("[yellow]Cancelled.[/yellow]")
+            return
+
+        new_key = generate_master_key()
+
+        # Keep the new key on disk until it is stored, so a failure after the
+        # database rewrite can never leave snippets without a usable key
+        fd = os.open(pending_key_file, os.O_WRONLY | os.O_CREAT | os.O_EXCL, 0o600)
+        with os.fdopen(fd, "wb") as f:
+            f.write(new_key)
+
+        try:
+            rotated = db.reencrypt_all(new_key)
+        except Exception:
+            pending_key_file.unlink()
+            raise
+
+        try:
+            save_master_key(new_key)
+        except Exception as e:
+            console.print(f"[red]Error: Snippets were re-encrypted but the new key could not be stored: {e}[/red]")
+            console.print(f"[yellow]The new key is in {pending_key_file}; keep it safe.[/yellow]")
+            sys.exit(1)
+        pending_key_file.unlink()
+
+        console.print(
+            f"[green]✓ Master key rotated; re-encrypted {rotated[0]} snippets and "
+            f"{rotated[1]} attachments.[/green]"
+        )
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@cli.command()
+@click.argument('name', required=False, shell_complete=_complete_snippet_names)
+def which(name):
+    """
+    Show which data directory, vault and database commands use.
+
+    \b
+    Prints the data directory (and whether it comes from --data-dir,
+    MACOLINT_DATA_DIR or the default), the active vault, the database and
+    settings files and where the master key is kept. With NAME, also says
+    whether that snippet (or module) exists there; nothing is decrypted.
+    Exits with status 3 if NAME is not found.
 
+    \b
+    EXAMPLES:
+      snip which
+      snip which db/users
+      snip --vault work which db/users
+      snip --json which db/users
+    """
+    try:
+        from rich.markup import escape
+
+        result = _store_locations()
+        found = None
+        if name:
+            if db.snippet_exists(name):
+                found = "snippet"
+            elif db.get_module_by_path(name.strip("/")) is not None:
+                found = "module"
+            result.update(name=name, exists=found is not None, type=found)
+
+        if _json_mode():
+            print(json.dumps(result, indent=2))
+        else:
+            fields = _store_location_fields(result)
+            if name:
+                fields.append(("Name", f"{name}: " + (f"{found} exists" if found else "not found")))
+            for label, value in fields:
+                console.print(f"[bold]{label + ':':<12}[/bold] {escape(value)}")
+
+        if name and found is None:
+            sys.exit(ERROR_EXIT_CODES["not_found"])
+
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+def _store_locations() -> dict:
+    """Where the active store lives, as reported by `snip which` and `snip info`."""
+    from macolint import config
+
+    return {
+        "data_dir": str(config.CONFIG_DIR),
+        "data_dir_source": config.data_dir_source(),
+        "vault": config.get_active_vault(),
+        "database": str(db.db_path),
+        "settings": str(config.SETTINGS_FILE),
+        "master_key": config.master_key_location(),
+    }
+
+
+def _store_location_fields(locations: dict) -> list:
+    """(label, value) lines for the result of _store_locations."""
+    from macolint import config
+
+    sources = {
+        "flag": "--data-dir", "environment": config.DATA_DIR_ENV, "default": "default",
+    }
+    settings_file = config.SETTINGS_FILE
+    return [
+        ("Data dir", f"{locations['data_dir']} ({sources[locations['data_dir_source']]})"),
+        ("Vault", locations["vault"]),
+        ("Database", locations["database"]),
+        ("Settings", str(settings_file) + ("" if settings_file.exists() else " (not created)")),
+        ("Master key", locations["master_key"] or "not created yet"),
+    ]
+
+
+@cli.command()
+def info():
+    """
+    Print versions, storage formats and paths, for bug reports.
+
+    \b
+    Shows the Macolint and Python versions, the platform, the database
+    schema version, how content is encrypted and how passphrase keys are
+    derived, the number of snippets and modules, and the paths `snip which`
+    shows. The output is plain text, ready to paste into an issue; it never
+    contains snippet names, content or keys.
+
+    \b
+    EXAMPLES:
+      snip info
+      snip --json info
+    """
+    try:
+        import platform
+        from macolint.config import load_settings
+        from macolint.crypto import KDF_ALGORITHM, KDF_ITERATIONS
+
+        settings = load_settings()
+        stats = db.get_stats()
+        result = {
+            "version": __version__,
+            "python": platform.python_version(),
+            "platform": platform.platform(),
+            "schema_version": db.get_schema_version(),
+            "encrypt_names": bool(settings["encrypt_names"]),
+            "encrypt_database": bool(settings["encrypt_database"]),
+            "kdf": {"algorithm": KDF_ALGORITHM, "iterations": KDF_ITERATIONS},
+            "snippets": stats.snippet_count,
+            "modules": stats.module_count,
+            **_store_locations(),
+        }
+
+        if _json_mode():
+            print(json.dumps(result, indent=2))
+            return
+        encryption = "Fernet (AES-128-CBC + HMAC-SHA256) under the master key"
+        if result["encrypt_names"]:
+            encryption += ", names encrypted"
+        if result["encrypt_database"]:
+            encryption += ", database file encrypted (SQLCipher)"
+        fields = [
+            ("Macolint", result["version"]),
+            ("Python", result["python"]),
+            ("Platform", result["platform"]),
+            ("Schema", str(result["schema_version"])),
+            ("Encryption", encryption),
+            ("KDF", f"{KDF_ALGORITHM}, {KDF_ITERATIONS} iterations (passphrases)"),
+            ("Snippets", f"{result['snippets']} in {result['modules']} modules"),
+            *_store_location_fields(result),
+        ]
+        # click.echo, not console: no markup or wrapping in pasted output
+        for label, value in fields:
+            click.echo(f"{label + ':':<12} {value}")
+
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
 
 @cli.command()
-def doctor():
+@click.option(
+    '--quarantine',
+    is_flag=True,
+    help='Move snippets that cannot be decrypted out of the way.',
+)
+def doctor(quarantine):
     """
     Diagnose and report issues with Macolint installation.
     
@@ -2234,8 +6180,17 @@ def doctor():
     WHAT IT CHECKS:
       ✓ snip command in PATH
       ✓ Shell wrapper installation status
-      ✓ Database accessibility
+      ✓ Where the master key is stored (OS keyring, file or environment)
+      ✓ Database accessibility and SQLite integrity check
       ✓ Snippet count
+      ✓ Every snippet, version and attachment decrypts with your key
+    
+    \b
+    OPTIONS:
+      --quarantine    Move snippets whose content cannot be decrypted into the
+                      quarantined_snippets table (ciphertext kept) so they stop
+                      breaking list/get. Their history, tags and attachments
+                      are removed.
     
     \b
     WHAT IT PROVIDES:
@@ -2325,13 +6280,66 @@ def doctor():
         console.print("[yellow]⚠ Could not detect shell type[/yellow]")
     
     console.print("")
+
+    # Check where the master key is kept
+    from macolint.config import get_keyring_fallback_file, master_key_location
+
+    try:
+        location = master_key_location()
+    except ValueError as e:
+        location = None
+        console.print(f"[red]✗ {e}[/red]")
+    if location == "keyring":
+        console.print("[green]✓ Master key stored in the OS keyring[/green]")
+    elif location == "environment":
+        console.print("[green]✓ Master key taken from MACOLINT_MASTER_KEY[/green]")
+    elif location == "file":
+        console.print(f"[yellow]⚠ Master key stored in a file: {get_keyring_fallback_file()}[/yellow]")
+        console.print("[yellow]  No OS keyring was available when it was created; the file is readable only by you.[/yellow]")
+
+    console.print("")
     
     # Check database
     try:
-        from macolint.database import Database
+        from macolint.config import get_db_path, get_master_key, load_settings
+        from macolint.database import Database, check_integrity, database_key, is_plain_sqlite
+
+        db_path = get_db_path()
+        if os.path.exists(db_path):
+            key = None
+            if load_settings()["encrypt_database"] and is_plain_sqlite(db_path) is False:
+                key = database_key(get_master_key())
+            problems = check_integrity(db_path, key)
+            if problems:
+                console.print("[red]✗ Database integrity check failed:[/red]")
+                for problem in problems[:10]:
+                    console.print(f"[red]  {problem}[/red]")
+            else:
+                console.print("[green]✓ Database integrity check passed[/green]")
+
         db = Database()
         count = len(db.get_all_snippet_names())
         console.print(f"[green]✓ Database accessible ({count} snippets)[/green]")
+
+        unreadable = db.find_undecryptable()
+        if not unreadable:
+            console.print("[green]✓ All snippets decrypt with the master key[/green]")
+        else:
+            console.print(f"[red]✗ {len(unreadable)} item(s) cannot be decrypted:[/red]")
+            for _, path, problem in unreadable:
+                console.print(f"[red]  {path}: {problem}[/red]")
+            broken_ids = sorted({snippet_id for snippet_id, _, problem in unreadable
+                                 if problem == "content"})
+            if broken_ids and quarantine:
+                moved = db.quarantine_snippets(broken_ids)
+                console.print(
+                    f"[green]✓ Moved {moved} snippet(s) to the quarantined_snippets table[/green]"
+                )
+            elif broken_ids:
+                console.print(
+                    "[yellow]  Run 'snip doctor --quarantine' to move the unreadable "
+                    "snippets aside.[/yellow]"
+                )
     except Exception as e:
         console.print(f"[red]✗ Database error: {e}[/red]")
     
@@ -2364,6 +6372,168 @@ def doctor():
             console.print("")
 
 
+@cli.group()
+def vault():
+    """Manage vaults (separate snippet databases, selected with --vault)."""
+    pass
+
+
+@vault.command('list')
+def vault_list():
+    """
+    List existing vaults.
+
+    \b
+    Each vault has its own database and master key. 'default' is the
+    original ~/.macolint store; others live in ~/.macolint/vaults/NAME/ and
+    are created the first time they are used:
+      snip --vault work save deploy
+      SNIP_VAULT=work snip get deploy
+    """
+    from macolint.config import get_active_vault, list_vaults
+
+    active = get_active_vault()
+    for name in list_vaults():
+        if name == active:
+            console.print(f"[green]* {name}[/green]")
+        else:
+            console.print(f"  {name}")
+
+
+@cli.group('config')
+def config_group():
+    """
+    View and change settings (~/.macolint/settings.json).
+
+    \b
+    Nested settings are named section.key, e.g. fuzzy_weights.match.
+    The master key is not a setting and can't be read or changed here.
+    """
+    pass
+
+
+@config_group.command('get')
+@click.argument('key')
+def config_get(key):
+    """
+    Print a setting's current value (its default if it was never set).
+
+    \b
+    EXAMPLES:
+      snip config get clipboard_clear_secs
+      snip config get fuzzy_weights.recency
+    """
+    try:
+        from macolint.config import get_setting
+
+        value = get_setting(key)
+        if _json_mode():
+            _report("", key=key, value=value)
+        else:
+            click.echo(value if isinstance(value, str) else json.dumps(value))
+    except ValueError as e:
+        _fail(f"Error: {e}", "usage")
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@config_group.command('set')
+@click.argument('key')
+@click.argument('value')
+def config_set(key, value):
+    """
+    Change a setting, checking the value first.
+
+    \b
+    Booleans take true/false (or yes/no, on/off, 1/0); numbers can't be
+    negative; clipboard_backend, time_format and name_lookup take one of
+    their listed values. The file is rewritten atomically and entries it
+    holds for other settings are kept.
+
+    \b
+    EXAMPLES:
+      snip config set clipboard_clear_secs 10
+      snip config set time_format relative
+      snip config set fuzzy_weights.usage 0.5
+    """
+    try:
+        from macolint.config import parse_setting, set_setting
+        from rich.markup import escape
+
+        parsed = parse_setting(key, value)
+        set_setting(key, parsed)
+        shown = parsed if isinstance(parsed, str) else json.dumps(parsed)
+        _report(f"[green]✓ {key} = {escape(str(shown))}[/green]", key=key, value=parsed)
+    except ValueError as e:
+        _fail(f"Error: {e}", "usage")
+    except Exception as e:
+        _fail(f"Error: {e}", _error_kind(e))
+
+
+@config_group.command('path')
+def config_path():
+    """Print the path of the settings file (it may not exist yet)."""
+    from macolint import config
+
+    if _json_mode():
+        _report("", path=str(config.SETTINGS_FILE), exists=config.SETTINGS_FILE.exists())
+    else:
+        click.echo(str(config.SETTINGS_FILE))
+
+
+@cli.command()
+@click.argument('shell', type=click.Choice(['bash', 'zsh', 'fish', 'powershell']))
+def completions(shell):
+    """
+    Print a tab-completion script for your shell.
+
+    \b
+    Completes subcommands, options and snippet names (for get, edit,
+    delete and rename).
+
+    \b
+    INSTALL:
+      bash:        snip completions bash > ~/.local/share/bash-completion/completions/snip
+      zsh:         snip completions zsh > ~/.zfunc/_snip
+                   (with 'fpath+=~/.zfunc' before 'compinit' in ~/.zshrc)
+      fish:        snip completions fish > ~/.config/fish/completions/snip.fish
+      PowerShell:  snip completions powershell >> $PROFILE
+    Restart your shell afterwards.
+    """
+    from macolint.completion import completion_script
+
+    print(completion_script(cli, shell))
+
+
+@cli.command(name='__preview', hidden=True)
+@click.argument('name')
+def preview(name):
+    """
+    Print a snippet's content for a picker's preview pane, e.g.
+    snip __complete_names | fzf --preview 'snip __preview {}'.
+    Secrets are never printed.
+    """
+    try:
+        snippet = db.get_snippet(name)
+    except Exception:
+        sys.exit(1)
+    if snippet is None:
+        sys.exit(1)
+    from macolint.formatting import content_mask
+
+    print(content_mask(snippet.content, snippet.kind) or snippet.content.rstrip())
+
+
+@cli.command(name='__complete_names', hidden=True)
+def complete_names():
+    """Print all snippet names, one per line (used by completion scripts)."""
+    try:
+        for name in db.get_all_snippet_names():
+            print(name)
+    except Exception:
+        sys.exit(1)
+
+
 def main():
     """Main entry point."""
     cli()
@@ -0,0 +1,240 @@
+"""Clipboard output sinks for snippet content."""
+
+import base64
+import hashlib
+import os
+import shutil
+import subprocess
+import sys
+import time
+from typing import List, Optional
+
+
+# Seconds before a copied secret is wiped from the clipboard
+DEFAULT_CLEAR_SECONDS = 30
+# Values of the clipboard_backend setting and --clipboard-backend flag:
+# 'auto' picks the platform's tool, 'x11' and 'wayland' force that tool
+# family on Linux/BSD, 'osc52' writes to the terminal (works over SSH)
+CLIPBOARD_BACKENDS = ("auto", "x11", "wayland", "osc52")
+
+
+class ClipboardUnavailable(RuntimeError):
+    """No system clipboard can be used in this session (headless or missing tools)."""
+
+
+def session_type() -> Optional[str]:
+    """
+    Graphical session on Linux/BSD: 'wayland', 'x11', or None when there is
+    no display (SSH, containers, the console). XWayland sessions report
+    'wayland' even though DISPLAY is also set.
+    """
+    if os.environ.get("WAYLAND_DISPLAY"):
+        return "wayland"
+    if os.environ.get("DISPLAY"):
+        return "x11"
+    return None
+
+
+def _system_clipboard_command(backend: str = "auto") -> Optional[List[str]]:
+    """Find a command-line tool that can write to the system clipboard."""
+    if backend == "wayland":
+        candidates = [["wl-copy"]]
+    elif backend == "x11":
+        candidates = [["xclip", "-selection", "clipboard"], ["xsel", "--clipboard", "--input"]]
+    elif sys.platform == "darwin":
+        candidates = [["pbcopy"]]
+    elif sys.platform.startswith("win"):
+        candidates = [["clip"]]
+    else:
+        candidates = []
+        if session_type() == "wayland":
+            candidates.append(["wl-copy"])
+        if os.environ.get("DISPLAY"):
+            candidates.extend([
+                ["xclip", "-selection", "clipboard"],
+                ["xsel", "--clipboard", "--input"],
+            ])
+
+    for command in candidates:
+        if shutil.which(command[0]):
+            return command
+    return None
+
+
+def _system_paste_command(backend: str = "auto") -> Optional[List[str]]:
+    """Find a command-line tool that can read the system clipboard."""
+    if backend == "wayland":
+        candidates = [["wl-paste", "--no-newline"]]
+    elif backend == "x11":
+        candidates = [["xclip", "-selection", "clipboard", "-o"], ["xsel", "--clipboard", "--output"]]
+    elif sys.platform == "darwin":
+        candidates = [["pbpaste"]]
+    elif sys.platform.startswith("win"):
+        candidates = [["powershell", "-NoProfile", "-Command", "Get-Clipboard -Raw"]]
+    else:
+        candidates = []
+        if session_type() == "wayland":
+            candidates.append(["wl-paste", "--no-newline"])
+        if os.environ.get("DISPLAY"):
+            candidates.extend([
+                ["xclip", "-selection", "clipboard", "-o"],
+                ["xsel", "--clipboard", "--output"],
+            ])
+
+    for command in candidates:
+        if shutil.which(command[0]):
+            return command
+    return None
+
+
+def read_clipboard(backend: str = "auto") -> str:
+    """
+    Read the clipboard of the given backend (see CLIPBOARD_BACKENDS).
+
+    Raises:
+        ClipboardUnavailable: If there is no display or no clipboard tool to
+            use, or the backend (osc52) cannot be read from
+        RuntimeError: If the clipboard tool fails
+    """
+    if backend == "osc52":
+        raise ClipboardUnavailable(
+            "The osc52 clipboard backend can only copy; pick another one with "
+            "--clipboard-backend to read the clipboard."
+        )
+    command = _system_paste_command(backend)
+    if command is None:
+        raise ClipboardUnavailable(_unavailable_reason(backend))
+    result = subprocess.run(command, capture_output=True)
+    if result.returncode != 0:
+        stderr = result.stderr.decode("utf-8", errors="replace").strip()
+        raise RuntimeError(f"{command[0]} failed: {stderr or 'unknown error'}")
+    return result.stdout.decode("utf-8", errors="replace")
+
+
+def read_system_clipboard(backend: str = "auto") -> Optional[str]:
+    """Read the system clipboard, or return None if it cannot be read."""
+    try:
+        return read_clipboard(backend)
+    except RuntimeError:
+        return None
+
+
+def _unavailable_reason(backend: str = "auto") -> str:
+    """Explain why no system clipboard tool was found."""
+    if backend == "wayland":
+        return "No Wayland clipboard tool found (install wl-clipboard)."
+    if backend == "x11":
+        return "No X11 clipboard tool found (install xclip or xsel)."
+    if sys.platform == "darwin" or sys.platform.startswith("win"):
+        return "No clipboard tool found."
+    session = session_type()
+    if session == "wayland":
+        return "No clipboard tool found for Wayland (install wl-clipboard)."
+    if session == "x11":
+        return "No clipboard tool found for X11 (install xclip or xsel)."
+    return "No display available for the system clipboard (headless session)."
+
+
+def copy_to_clipboard(text: str, backend: str = "auto") -> None:
+    """
+    Copy text with the given backend (see CLIPBOARD_BACKENDS): the system
+    clipboard tool, or the terminal via OSC 52.
+
+    Raises:
+        ClipboardUnavailable: If there is no display or no clipboard tool to use
+        RuntimeError: If the clipboard tool fails, or there is no terminal for OSC 52
+    """
+    if backend == "osc52":
+        copy_via_osc52(text)
+    else:
+        copy_to_system_clipboard(text, backend)
+
+
+def copy_to_system_clipboard(text: str, backend: str = "auto") -> None:
+    """
+    Copy text to the system clipboard using the platform's clipboard tool
+    (or the x11/wayland tools when that backend is given).
+
+    Raises:
+        ClipboardUnavailable: If there is no display or no clipboard tool to use
+        RuntimeError: If the clipboard tool fails
+    """
+    command = _system_clipboard_command(backend)
+    if command is None:
+        raise ClipboardUnavailable(_unavailable_reason(backend))
+    result = subprocess.run(command, input=text.encode("utf-8"), capture_output=True)
+    if result.returncode != 0:
+        stderr = result.stderr.decode("utf-8", errors="replace").strip()
+        raise RuntimeError(f"{command[0]} failed: {stderr or 'unknown error'}")
+
+
+def copy_via_osc52(text: str) -> None:
+    """
+    Copy text to the terminal's clipboard using the OSC 52 escape sequence.
+    Works over SSH when the local terminal supports OSC 52.
+
+    Raises:
+        RuntimeError: If no terminal is available to write the sequence to
+    """
+    payload = base64.b64encode(text.encode("utf-8")).decode("ascii")
+    sequence = f"\033]52;c;{payload}\a"
+    if os.environ.get("TMUX"):
+        # tmux requires passthrough wrapping for OSC sequences
+        sequence = f"\033Ptmux;\033{sequence}\033\\"
+
+    try:
+        with open("/dev/tty", "w") as tty:
+            tty.write(sequence)
+            tty.flush()
+        return
+    except OSError:
+        pass
+
+    if sys.stderr.isatty():
+        sys.stderr.write(sequence)
+        sys.stderr.flush()
+        return
+    raise RuntimeError("No terminal available for OSC 52 output.")
+
+
+def _digest(text: str) -> str:
+    return hashlib.sha256(text.encode("utf-8")).hexdigest()
+
+
+def schedule_clipboard_clear(text: str, seconds: int, backend: str = "auto") -> None:
+    """
+    Clear the system clipboard (of a system backend, not osc52) after a
+    delay, in a detached background process.
+    The clipboard is only cleared if it still holds `text`, so anything the
+    user copies in the meantime is left alone. Only a hash of the text is
+    handed to the background process.
+    """
+    if os.name == "posix":
+        detach = {"start_new_session": True}
+    else:
+        detach = {"creationflags": 0x00000008}  # DETACHED_PROCESS
+
+    process = subprocess.Popen(
+        [sys.executable, "-m", "macolint.clipboard", "--clear-after", str(seconds), backend],
+        stdin=subprocess.PIPE,
+        stdout=subprocess.DEVNULL,
+        stderr=subprocess.DEVNULL,
+        close_fds=True,
+        **detach,
+    )
+    process.stdin.write((_digest(text) + "\n").encode("ascii"))
+    process.stdin.close()
+
+
+def _clear_after(seconds: int, digest: str, backend: str) -> None:
+    """Background worker for schedule_clipboard_clear."""
+    time.sleep(seconds)
+    current = read_system_clipboard(backend)
+    if current is not None and _digest(current) == digest:
+        copy_to_system_clipboard("", backend)
+
+
+if __name__ == "__main__":
+    if len(sys.argv) in (3, 4) and sys.argv[1] == "--clear-after":
+        backend = sys.argv[3] if len(sys.argv) == 4 else "auto"
+        _clear_after(int(sys.argv[2]), sys.stdin.readline().strip(), backend)
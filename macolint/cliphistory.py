@@ -0,0 +1,68 @@
+"""Rolling history of clipboard contents, kept as snippets under clip/ (snip clip-history)."""
+
+from datetime import datetime, timedelta
+from typing import List, Optional
+
+from macolint.database import Database
+
+CLIP_MODULE = "clip"
+# Entry names sort oldest to newest: clip/20240501-143000, clip/20240501-143000-2, ...
+ENTRY_TIME_FORMAT = "%Y%m%d-%H%M%S"
+
+
+def history_entries(db: Database) -> List[str]:
+    """Full paths of the clipboard history entries, newest first."""
+    prefix = CLIP_MODULE + "/"
+    names = [name for name in db.get_all_snippet_names() if name.startswith(prefix)]
+    return sorted(
+        (name for name in names if "/" not in name[len(prefix):]),
+        key=lambda name: name[len(prefix):],
+        reverse=True,
+    )
+
+
+def entry_name(existing: List[str], now: Optional[datetime] = None) -> str:
+    """A new entry name for the current time, not clashing with `existing`."""
+    base = f"{CLIP_MODULE}/{(now or datetime.now()).strftime(ENTRY_TIME_FORMAT)}"
+    name, count = base, 1
+    while name in existing:
+        count += 1
+        name = f"{base}-{count}"
+    return name
+
+
+def prune_history(
+    db: Database, size: Optional[int], max_age: Optional[timedelta] = None, dry_run: bool = False
+) -> List[str]:
+    """
+    Apply the retention policy: keep the newest `size` entries, and none
+    older than max_age (either may be None for no limit). Only clip/ entries
+    are ever deleted, and pinned ones are kept. Returns the deleted names.
+    """
+    return db.prune_namespace(CLIP_MODULE, keep_n=size, max_age=max_age, dry_run=dry_run)
+
+
+def record_clipboard(
+    db: Database, content: str, size: int, max_age: Optional[timedelta] = None
+) -> Optional[str]:
+    """
+    Add `content` to the history, then prune it (see prune_history).
+    A value already in the history is moved to the top rather than stored
+    twice. Returns the new entry's name, or None if it was already the
+    newest entry. Content is encrypted like any other snippet.
+    """
+    entries = history_entries(db)
+    if entries:
+        newest = db.get_snippet(entries[0])
+        if newest is not None and newest.content == content:
+            return None
+
+    for name in db.find_identical(content):
+        if name in entries:
+            db.delete_snippet(name)
+            entries.remove(name)
+
+    name = entry_name(entries)
+    db.save_snippet(name, content)
+    prune_history(db, size, max_age)
+    return name
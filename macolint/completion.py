@@ -0,0 +1,52 @@
+"""Shell completion scripts for the snip command."""
+
+import click
+from click.shell_completion import get_completion_class
+
+PROG_NAME = "snip"
+COMPLETE_VAR = "_SNIP_COMPLETE"
+SHELLS = ("bash", "zsh", "fish", "powershell")
+
+# Commands whose first argument is a snippet name
+NAME_COMMANDS = ("get", "edit", "delete", "rename", "pin", "unpin", "run", "which", "resolve")
+
+# Click has no PowerShell support, so this script completes subcommands
+# statically and snippet names through the hidden `snip __complete_names`.
+POWERSHELL_SOURCE = """\
+Register-ArgumentCompleter -Native -CommandName %(prog)s -ScriptBlock {
+    param($wordToComplete, $commandAst, $cursorPosition)
+    $words = @($commandAst.CommandElements | ForEach-Object { $_.ToString() })
+    if ($words.Count -le 1 -or ($words.Count -eq 2 -and $wordToComplete)) {
+        $candidates = @(%(commands)s)
+    } elseif ($words[1] -in @(%(name_commands)s)) {
+        $candidates = @(%(prog)s __complete_names)
+    } else {
+        return
+    }
+    $candidates | Where-Object { $_ -like "$wordToComplete*" } | ForEach-Object {
+        [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+    }
+}
+"""
+
+
+def _quoted(values) -> str:
+    return ", ".join(f"'{value}'" for value in values)
+
+
+def completion_script(cli: click.Group, shell: str) -> str:
+    """Return the completion script for `shell` (one of SHELLS)."""
+    if shell == "powershell":
+        ctx = click.Context(cli, info_name=PROG_NAME)
+        commands = [
+            name for name in cli.list_commands(ctx)
+            if not cli.get_command(ctx, name).hidden
+        ]
+        return POWERSHELL_SOURCE % {
+            "prog": PROG_NAME,
+            "commands": _quoted(commands),
+            "name_commands": _quoted(NAME_COMMANDS),
+        }
+
+    completion_class = get_completion_class(shell)
+    return completion_class(cli, {}, PROG_NAME, COMPLETE_VAR).source()
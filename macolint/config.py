@@ -1,8 +1,12 @@
 """Configuration and key management for Macolint."""
 
 import os
+import re
+import sys
+import json
 import keyring
 from pathlib import Path
+from typing import List, Optional
 from cryptography.fernet import Fernet
 from cryptography.hazmat.primitives import hashes
 from cryptography.hazmat.primitives.kdf.pbkdf2 import PBKDF2HMAC
@@ -12,14 +16,165 @@ import base64
 
 SERVICE_NAME = "macolint"
 KEY_NAME = "master_key"
-CONFIG_DIR = Path.home() / ".macolint"
-DB_PATH = CONFIG_DIR / "snippets.db"
-KEYRING_FALLBACK_FILE = CONFIG_DIR / "key.enc"
+# MACOLINT_DATA_DIR moves all local data (e.g. to an external drive or a
+# temporary directory for tests); it is validated in ensure_config_dir.
+# The global --data-dir flag overrides it (see set_data_dir).
+DATA_DIR_ENV = "MACOLINT_DATA_DIR"
+# A master key supplied by the environment (e.g. a CI secret); never stored
+MASTER_KEY_ENV = "MACOLINT_MASTER_KEY"
+CONFIG_DIR = Path(os.environ.get(DATA_DIR_ENV) or Path.home() / ".macolint").expanduser()
+SETTINGS_FILE = CONFIG_DIR / "settings.json"
+# Where CONFIG_DIR came from: 'default', 'environment' or 'flag' (see data_dir_source)
+_data_dir_source = "environment" if os.environ.get(DATA_DIR_ENV) else "default"
+
+# Vaults are separate databases with their own master key. The default vault
+# keeps the original layout directly in CONFIG_DIR; others live in VAULTS_DIR.
+VAULTS_DIR = CONFIG_DIR / "vaults"
+DEFAULT_VAULT = "default"
+VAULT_NAME_PATTERN = re.compile(r"^[A-Za-z0-9_-]+$")
+_active_vault = DEFAULT_VAULT
+
+# User-tunable settings; values in settings.json override these per section
+DEFAULT_SETTINGS = {
+    # Weights for ranking interactive search results (see macolint.ranking)
+    "fuzzy_weights": {"match": 1.0, "recency": 0.3, "usage": 0.3},
+    # How far (0-1) the best match must lead the next before a typed name
+    # picks it without asking; a closer race lists the matches instead
+    "fuzzy_auto_select_gap": 0.15,
+    # Seconds before a copied secret is cleared from the clipboard (0 = never)
+    "clipboard_clear_secs": 30,
+    # Clipboard used by --clipboard: auto, x11, wayland or osc52 (the
+    # terminal, for SSH sessions); the global --clipboard-backend overrides it
+    "clipboard_backend": "auto",
+    # How dates are shown by list, history and stats: local, iso or relative
+    "time_format": "local",
+    # Store snippet and module names as keyed hashes plus ciphertext
+    # (takes effect, converting the database, the next time snip runs)
+    "encrypt_names": False,
+    # Record when each snippet was last retrieved (shown by snip recent)
+    "track_access": True,
+    # Encrypt the whole database file with SQLCipher (needs the sqlcipher3
+    # package); an existing database must be exported and re-imported
+    "encrypt_database": False,
+    # Largest content snip save accepts without --allow-large (0 = no limit)
+    "max_snippet_bytes": 1024 * 1024,
+    # Clipboard history retention, applied on each capture: keep the newest
+    # clip_history_size entries, and none older than clip_history_max_age_secs
+    # (0 = no age limit)
+    "clip_history_size": 20,
+    "clip_history_max_age_secs": 0,
+    # Copy the database to backups/ before a write if the newest copy is
+    # older than this many seconds (0 = never), keeping backup_keep copies
+    "backup_interval_secs": 86400,
+    "backup_keep": 7,
+    # How a typed snippet name is looked up by get, show, edit, delete,
+    # rename and copy: "exact", or "insensitive" to ignore case and accents
+    # when there is no exact match (several matches are offered to pick from)
+    "name_lookup": "exact",
+}
 
 
 def ensure_config_dir():
-    """Ensure the configuration directory exists."""
+    """
+    Ensure the configuration directory (and the active vault's) exists.
+
+    Raises:
+        ValueError: If MACOLINT_DATA_DIR is not absolute or cannot be created
+    """
+    if os.environ.get(DATA_DIR_ENV):
+        if not CONFIG_DIR.is_absolute():
+            raise ValueError(f"{DATA_DIR_ENV} must be an absolute path (got '{CONFIG_DIR}').")
+        try:
+            CONFIG_DIR.mkdir(parents=True, exist_ok=True, mode=0o700)
+        except OSError as e:
+            raise ValueError(f"Cannot create {DATA_DIR_ENV} directory '{CONFIG_DIR}': {e}")
     CONFIG_DIR.mkdir(exist_ok=True, mode=0o700)
+    if _active_vault != DEFAULT_VAULT:
+        VAULTS_DIR.mkdir(exist_ok=True, mode=0o700)
+        get_vault_dir().mkdir(exist_ok=True, mode=0o700)
+
+
+def set_data_dir(path: str):
+    """
+    Keep all local data in `path` for the rest of this process, as
+    MACOLINT_DATA_DIR does (for the global --data-dir flag, which wins over
+    the variable). A relative path is taken from the current directory.
+    """
+    global CONFIG_DIR, SETTINGS_FILE, VAULTS_DIR, _data_dir_source
+    CONFIG_DIR = Path(path).expanduser().resolve()
+    _data_dir_source = "flag"
+    SETTINGS_FILE = CONFIG_DIR / "settings.json"
+    VAULTS_DIR = CONFIG_DIR / "vaults"
+    # Seen by ensure_config_dir, the keyring entry name and child processes
+    os.environ[DATA_DIR_ENV] = str(CONFIG_DIR)
+
+
+def data_dir_source() -> str:
+    """
+    How the data directory was chosen: 'flag' (--data-dir), 'environment'
+    (MACOLINT_DATA_DIR) or 'default' (~/.macolint).
+    """
+    return _data_dir_source
+
+
+def set_active_vault(name: str):
+    """
+    Select the vault used by get_master_key, get_db_path, etc.
+
+    Raises:
+        ValueError: If the name is not a valid vault name
+    """
+    global _active_vault
+    if not VAULT_NAME_PATTERN.match(name):
+        raise ValueError(
+            f"Invalid vault name '{name}'. Use letters, digits, '-' and '_' only."
+        )
+    _active_vault = name
+
+
+def get_active_vault() -> str:
+    """Get the name of the active vault."""
+    return _active_vault
+
+
+def get_vault_dir(vault: str = None) -> Path:
+    """Get the directory holding a vault's database and key (default: active vault)."""
+    vault = vault or _active_vault
+    if vault == DEFAULT_VAULT:
+        return CONFIG_DIR
+    return VAULTS_DIR / vault
+
+
+def list_vaults() -> List[str]:
+    """List existing vaults; the default vault is always included."""
+    vaults = [DEFAULT_VAULT]
+    if VAULTS_DIR.is_dir():
+        vaults.extend(sorted(
+            entry.name for entry in VAULTS_DIR.iterdir()
+            if entry.is_dir() and VAULT_NAME_PATTERN.match(entry.name)
+        ))
+    return vaults
+
+
+def _key_name() -> str:
+    """Keyring entry for the active vault's master key."""
+    name = KEY_NAME
+    if os.environ.get(DATA_DIR_ENV):
+        # Never share (or rotate) the regular store's key from another data dir
+        name = f"{name}@{CONFIG_DIR}"
+    if _active_vault != DEFAULT_VAULT:
+        name = f"{name}:{_active_vault}"
+    return name
+
+
+def get_keyring_fallback_file() -> Path:
+    """File holding the active vault's master key when no keyring is available."""
+    return get_vault_dir() / "key.enc"
+
+
+def get_pending_key_file() -> Path:
+    """New master key staged during `snip change-key` until it has been stored."""
+    return get_vault_dir() / "key.new"
 
 
 def generate_master_key() -> bytes:
@@ -27,36 +182,121 @@ def generate_master_key() -> bytes:
     return Fernet.generate_key()
 
 
+def get_env_master_key() -> Optional[bytes]:
+    """
+    The master key from MACOLINT_MASTER_KEY, or None if it is not set.
+
+    Raises:
+        ValueError: If the variable is set but is not a valid key
+    """
+    value = os.environ.get(MASTER_KEY_ENV, "").strip()
+    if not value:
+        return None
+    try:
+        Fernet(value.encode())
+    except ValueError:
+        raise ValueError(
+            f"{MASTER_KEY_ENV} is not a valid master key "
+            f"(expected 32 url-safe base64-encoded bytes)."
+        )
+    return value.encode()
+
+
 def get_master_key() -> bytes:
     """
-    Get the master encryption key from secure storage.
-    Creates a new key if one doesn't exist.
+    Get the master encryption key: MACOLINT_MASTER_KEY when set (nothing is
+    written to disk then), otherwise the key in secure storage.
+    Creates a new key if neither exists.
     """
     ensure_config_dir()
-    
+
+    env_key = get_env_master_key()
+    if env_key is not None:
+        stored_key = _load_stored_key()
+        if stored_key is not None and stored_key != env_key:
+            print(
+                f"Warning: {MASTER_KEY_ENV} differs from the master key stored for this "
+                f"vault; snippets saved with the stored key cannot be decrypted.",
+                file=sys.stderr,
+            )
+        return env_key
+
+    stored_key = _load_stored_key()
+    if stored_key is not None:
+        return stored_key
+
+    # Generate new key if none exists
+    new_key = generate_master_key()
+    save_master_key(new_key)
+    return new_key
+
+
+def _load_stored_key() -> Optional[bytes]:
+    """The master key from the keyring or the fallback file, if there is one."""
     # Try to get key from keyring first
     try:
-        stored_key = keyring.get_password(SERVICE_NAME, KEY_NAME)
+        stored_key = keyring.get_password(SERVICE_NAME, _key_name())
         if stored_key:
             return stored_key.encode()
     except Exception:
         pass
     
     # If not in keyring, try fallback file
-    if KEYRING_FALLBACK_FILE.exists():
+    fallback_file = get_keyring_fallback_file()
+    if fallback_file.exists():
         try:
-            with open(KEYRING_FALLBACK_FILE, "rb") as f:
+            with open(fallback_file, "rb") as f:
                 encrypted_key = f.read()
             # For MVP, we'll use a simple approach: store base64 encoded key
             # In production, this should be encrypted with a user passphrase
             return base64.b64decode(encrypted_key)
         except Exception:
             pass
-    
-    # Generate new key if none exists
-    new_key = generate_master_key()
-    save_master_key(new_key)
-    return new_key
+    return None
+
+
+def master_key_location() -> Optional[str]:
+    """
+    Where get_master_key finds the active vault's key: 'environment'
+    (MACOLINT_MASTER_KEY), 'keyring', 'file' (the fallback file), or None
+    if no key has been stored yet.
+    """
+    if get_env_master_key() is not None:
+        return "environment"
+    try:
+        if keyring.get_password(SERVICE_NAME, _key_name()):
+            return "keyring"
+    except Exception:
+        pass
+    if get_keyring_fallback_file().exists():
+        return "file"
+    return None
+
+
+def write_private_file(path: Path, data: bytes) -> None:
+    """
+    Write data to path atomically with 0600 permissions.
+
+    The data goes to a temporary file in the same directory, is flushed to
+    disk and then renamed over path, so a crash leaves either the old file
+    or the new one, never a truncated one.
+    """
+    import tempfile
+
+    # mkstemp creates the file with 0600 permissions
+    fd, tmp_path = tempfile.mkstemp(dir=path.parent, prefix=f".{path.name}.", suffix=".tmp")
+    try:
+        with os.fdopen(fd, "wb") as f:
+            f.write(data)
+            f.flush()
+            os.fsync(f.fileno())
+        os.replace(tmp_path, path)
+    except BaseException:
+        try:
+            os.unlink(tmp_path)
+        except OSError:
+            pass
+        raise
 
 
 def save_master_key(key: bytes):
@@ -65,18 +305,21 @@ def save_master_key(key: bytes):
     
     # Try to save to keyring first
     try:
-        keyring.set_password(SERVICE_NAME, KEY_NAME, key.decode())
+        keyring.set_password(SERVICE_NAME, _key_name(), key.decode())
         return
-    except Exception:
-        pass
+    except Exception as e:
+        keyring_error = e
     
     # Fallback to encrypted file
+    print(
+        f"Warning: No OS keyring is available ({str(keyring_error) or type(keyring_error).__name__}); "
+        f"storing the master key in {get_keyring_fallback_file()} (readable only by you).",
+        file=sys.stderr,
+    )
     try:
         # For MVP, store as base64 encoded
         # In production, encrypt with user passphrase
-        with open(KEYRING_FALLBACK_FILE, "wb") as f:
-            f.write(base64.b64encode(key))
-        KEYRING_FALLBACK_FILE.chmod(0o600)
+        write_private_file(get_keyring_fallback_file(), base64.b64encode(key))
     except Exception as e:
         raise RuntimeError(f"Failed to save master key: {e}")
 
@@ -88,7 +331,172 @@ def get_fernet() -> Fernet:
 
 
 def get_db_path() -> Path:
-    """Get the database path."""
+    """Get the active vault's database path."""
     ensure_config_dir()
-    return DB_PATH
+    return get_vault_dir() / "snippets.db"
+
+
+def load_settings() -> dict:
+    """
+    Load user settings from settings.json, filling in defaults.
+    A missing or unreadable file yields the defaults.
+    """
+    settings = {
+        key: dict(value) if isinstance(value, dict) else value
+        for key, value in DEFAULT_SETTINGS.items()
+    }
+    if not SETTINGS_FILE.exists():
+        return settings
+
+    try:
+        with open(SETTINGS_FILE, "r") as f:
+            stored = json.load(f)
+    except (OSError, ValueError):
+        return settings
+
+    if isinstance(stored, dict):
+        for key, value in stored.items():
+            if isinstance(settings.get(key), dict) and isinstance(value, dict):
+                settings[key].update(value)
+            else:
+                settings[key] = value
+    return settings
+
+
+# Values name_lookup accepts
+NAME_LOOKUPS = ("exact", "insensitive")
+# Settings that are fractions between 0 and 1
+FRACTION_SETTINGS = {"fuzzy_auto_select_gap"}
+
 
+def _setting_choices(key: str) -> Optional[tuple]:
+    """The values a text setting accepts, or None if any text will do."""
+    if key == "clipboard_backend":
+        from macolint.clipboard import CLIPBOARD_BACKENDS
+        return CLIPBOARD_BACKENDS
+    if key == "time_format":
+        from macolint.formatting import TIME_FORMATS
+        return TIME_FORMATS
+    if key == "name_lookup":
+        return NAME_LOOKUPS
+    return None
+
+
+def setting_keys() -> List[str]:
+    """Every setting key, with nested ones spelled section.key."""
+    keys = []
+    for key, value in DEFAULT_SETTINGS.items():
+        if isinstance(value, dict):
+            keys.extend(f"{key}.{subkey}" for subkey in value)
+        else:
+            keys.append(key)
+    return keys
+
+
+def _default_setting(key: str):
+    """
+    The default value of a setting key (section.key for nested ones).
+
+    Raises:
+        ValueError: If key is not a setting
+    """
+    if key.split(".")[0] == KEY_NAME:
+        raise ValueError(
+            f"'{KEY_NAME}' is not a setting; the master key can't be read or "
+            f"changed with snip config (see snip change-key)."
+        )
+    section, _, subkey = key.partition(".")
+    default = DEFAULT_SETTINGS.get(section)
+    if isinstance(default, dict) and subkey:
+        default = default.get(subkey)
+    elif subkey:
+        default = None
+    if default is None or isinstance(default, dict):
+        raise ValueError(f"Unknown setting '{key}'. Settings: {', '.join(setting_keys())}.")
+    return default
+
+
+def get_setting(key: str):
+    """
+    The current value of a setting (settings.json, else its default). A
+    section such as fuzzy_weights gives all of its keys.
+
+    Raises:
+        ValueError: If key is not a setting
+    """
+    if isinstance(DEFAULT_SETTINGS.get(key), dict):
+        return load_settings()[key]
+    _default_setting(key)
+    section, _, subkey = key.partition(".")
+    value = load_settings()[section]
+    return value[subkey] if subkey else value
+
+
+def parse_setting(key: str, text: str):
+    """
+    Convert text typed for a setting to the setting's type, checking it.
+
+    Raises:
+        ValueError: If key is not a setting or text is not a valid value for it
+    """
+    default = _default_setting(key)
+    text = text.strip()
+    if isinstance(default, bool):
+        lowered = text.lower()
+        if lowered in ("true", "yes", "on", "1"):
+            return True
+        if lowered in ("false", "no", "off", "0"):
+            return False
+        raise ValueError(f"'{key}' must be true or false.")
+    if isinstance(default, int):
+        try:
+            value = int(text)
+        except ValueError:
+            raise ValueError(f"'{key}' must be a whole number.")
+        if value < 0:
+            raise ValueError(f"'{key}' cannot be negative.")
+        return value
+    if isinstance(default, float):
+        try:
+            value = float(text)
+        except ValueError:
+            raise ValueError(f"'{key}' must be a number.")
+        if not value >= 0 or (key in FRACTION_SETTINGS and value > 1):
+            bounds = "between 0 and 1" if key in FRACTION_SETTINGS else "0 or more"
+            raise ValueError(f"'{key}' must be {bounds}.")
+        return value
+    choices = _setting_choices(key)
+    if choices and text.lower() not in choices:
+        raise ValueError(f"'{key}' must be one of: {', '.join(choices)}.")
+    return text.lower() if choices else text
+
+
+def set_setting(key: str, value) -> None:
+    """
+    Store a setting (already checked by parse_setting) in settings.json.
+
+    Other entries in the file, including ones this version doesn't know,
+    are kept. The file is replaced atomically, so an interrupted write
+    never leaves it half written.
+
+    Raises:
+        ValueError: If settings.json exists but is not a JSON object
+    """
+    ensure_config_dir()
+    stored = {}
+    if SETTINGS_FILE.exists():
+        try:
+            with open(SETTINGS_FILE, "r") as f:
+                stored = json.load(f)
+        except (OSError, ValueError) as e:
+            raise ValueError(f"Can't read {SETTINGS_FILE} ({e}); fix or remove it first.")
+        if not isinstance(stored, dict):
+            raise ValueError(f"{SETTINGS_FILE} is not a JSON object; fix or remove it first.")
+    section, _, subkey = key.partition(".")
+    if subkey:
+        if not isinstance(stored.get(section), dict):
+            stored[section] = {}
+        stored[section][subkey] = value
+    else:
+        stored[key] = value
+    write_private_file(SETTINGS_FILE, (json.dumps(stored, indent=2) + "\n").encode("utf-8"))
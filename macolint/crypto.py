@@ -1,6 +1,7 @@
 """Cryptography utilities for end-to-end encryption."""
 
 import os
+import zlib
 import base64
 from typing import Tuple
 from cryptography.hazmat.primitives.ciphers.aead import AESGCM
@@ -8,7 +9,16 @@ from cryptography.hazmat.primitives import hashes
 from cryptography.hazmat.primitives.kdf.pbkdf2 import PBKDF2HMAC
 
 
-def derive_key(passphrase: str, salt: bytes, iterations: int = 200_000) -> bytes:
+class DecryptionError(ValueError):
+    """Stored data could not be decrypted: wrong key or passphrase, or corrupted."""
+
+
+# Key derivation for passphrases (sync, team and protected snippet keys)
+KDF_ALGORITHM = "pbkdf2-sha256"
+KDF_ITERATIONS = 200_000
+
+
+def derive_key(passphrase: str, salt: bytes, iterations: int = KDF_ITERATIONS) -> bytes:
     """
     Derive an encryption key from a passphrase using PBKDF2.
     
@@ -29,6 +39,24 @@ def derive_key(passphrase: str, salt: bytes, iterations: int = 200_000) -> bytes
     return kdf.derive(passphrase.encode())
 
 
+def derive_team_key(team_passphrase: str, team_id: str, salt: bytes) -> bytes:
+    """
+    Derive a team's encryption key from the passphrase its members share.
+
+    The team ID is mixed into the salt, so the same passphrase gives each
+    team a different key, and none of them matches a personal key.
+
+    Args:
+        team_passphrase: Passphrase shared by the team's members
+        team_id: Supabase team ID
+        salt: Random salt stored with each snippet
+
+    Returns:
+        32-byte encryption key suitable for AES-256
+    """
+    return derive_key(team_passphrase, salt + team_id.encode())
+
+
 def encrypt(plaintext: bytes, key: bytes) -> Tuple[bytes, bytes]:
     """
     Encrypt plaintext using AES-GCM.
@@ -100,3 +128,105 @@ def ub64(s: str) -> bytes:
     """
     return base64.b64decode(s.encode())
 
+
+
+# Content encrypted under a snippet passphrase (on top of the master key)
+PROTECTED_PREFIX = "macolint-protected:v1:"
+
+
+def protect(plaintext: str, passphrase: str) -> str:
+    """
+    Encrypt snippet content under a passphrase, for `snip save --protected`.
+
+    The result still gets encrypted with the master key when stored, so both
+    are needed to read it. It records its own salt and nonce.
+    """
+    salt = gen_salt()
+    ciphertext, nonce = encrypt(plaintext.encode("utf-8"), derive_key(passphrase, salt))
+    return PROTECTED_PREFIX + ":".join((b64(salt), b64(nonce), b64(ciphertext)))
+
+
+def is_protected(content: str) -> bool:
+    """True if content was produced by protect()."""
+    return content.startswith(PROTECTED_PREFIX)
+
+
+def unprotect(content: str, passphrase: str) -> str:
+    """
+    Decrypt content produced by protect().
+
+    Raises:
+        DecryptionError: If the passphrase is wrong or the content is corrupted
+    """
+    from cryptography.exceptions import InvalidTag
+
+    try:
+        salt, nonce, ciphertext = (
+            ub64(part) for part in content[len(PROTECTED_PREFIX):].split(":")
+        )
+        plaintext = decrypt(ciphertext, nonce, derive_key(passphrase, salt))
+    except (InvalidTag, ValueError):
+        raise DecryptionError("Wrong passphrase or corrupted snippet.")
+    return plaintext.decode("utf-8")
+
+
+# Stored content is the UTF-8 text itself, or COMPRESSED_FLAG + a format byte
+# + the compressed text. 0xFF never occurs in UTF-8, so content stored before
+# compression existed is still read as plain text.
+COMPRESSED_FLAG = b"\xff"
+COMPRESSION_ZLIB = b"\x01"
+# Content shorter than this is stored as is; compressing it rarely pays off
+COMPRESS_MIN_BYTES = 256
+
+
+def pack_content(content: str) -> bytes:
+    """
+    Encode snippet content for encryption, compressed with zlib when that
+    makes it smaller. Read back with unpack_content.
+    """
+    data = content.encode("utf-8")
+    if len(data) < COMPRESS_MIN_BYTES:
+        return data
+    packed = COMPRESSED_FLAG + COMPRESSION_ZLIB + zlib.compress(data, 9)
+    return packed if len(packed) < len(data) else data
+
+
+def unpack_content(data: bytes) -> str:
+    """
+    Decode content produced by pack_content (or stored before compression).
+
+    Raises:
+        DecryptionError: If the data is compressed in an unknown format or corrupted
+    """
+    if not data.startswith(COMPRESSED_FLAG):
+        return data.decode("utf-8")
+    if data[1:2] != COMPRESSION_ZLIB:
+        raise DecryptionError(
+            "Snippet content uses an unknown compression format; update Macolint to read it."
+        )
+    try:
+        return zlib.decompress(data[2:]).decode("utf-8")
+    except (zlib.error, UnicodeDecodeError):
+        raise DecryptionError("Snippet content is corrupted (it does not decompress).")
+
+
+# Fernet tokens are URL-safe base64 text; the database stores their raw bytes
+# instead, a quarter smaller. A raw token starts with the version byte 0x80,
+# its text form with "gAAAAA", so both can be told apart when reading.
+FERNET_VERSION = b"\x80"
+
+
+def token_to_blob(token: bytes) -> bytes:
+    """The raw bytes of a Fernet token, for storing in a BLOB column."""
+    return base64.urlsafe_b64decode(token)
+
+
+def blob_to_token(blob: bytes) -> bytes:
+    """
+    The Fernet token for a value stored by token_to_blob. Tokens stored as
+    text (before raw storage existed) are returned unchanged.
+    """
+    blob = bytes(blob)
+    if blob.startswith(FERNET_VERSION):
+        return base64.urlsafe_b64encode(blob)
+    return blob
@@ -1,19 +1,169 @@
 """Database operations and encryption for Macolint, including hierarchical modules."""
 
+import hashlib
+import hmac
+import os
 import sqlite3
-from datetime import datetime
-from typing import Optional, List, Tuple
-
-from macolint.config import get_fernet, get_db_path
-from macolint.models import Snippet, Module
+from datetime import datetime, timedelta
+from typing import Iterator, Optional, List, Tuple, Dict
+
+from cryptography.fernet import Fernet, InvalidToken
+
+from macolint.config import get_db_path, get_master_key, load_settings
+from macolint.crypto import (
+    DecryptionError,
+    blob_to_token,
+    is_protected,
+    pack_content,
+    token_to_blob,
+    unpack_content,
+)
+from macolint.detect import detect_content_type, detect_language
+from macolint.migrations import SchemaVersionError, get_schema_version, migrate
+from macolint.models import (
+    Snippet, SnippetInfo, Module, SnippetStats, SnippetVersion, fold_name, parse_timestamp,
+    validate_name,
+)
+
+# Condition matching snippets that have not expired. expires_at is stored
+# like the other timestamps (local time, ISO format); NULL never expires.
+LIVE_SQL = (
+    "(expires_at IS NULL OR expires_at > strftime('%Y-%m-%dT%H:%M:%S', 'now', 'localtime'))"
+)
+
+# How long to wait for another process's lock before "database is locked"
+BUSY_TIMEOUT_SECONDS = 5
+
+# Sort orders for listings: name -> (column, descending by default)
+SORT_COLUMNS = {
+    "name": ("name", False),
+    "created": ("created_at", True),
+    "updated": ("updated_at", True),
+}
+
+# Per-snippet cloud sync state (snip sync). Only "synced" and "conflict" are
+# stored: a snippet never synced, or changed after its synced_at, is pending.
+SYNC_STATUSES = ("pending", "synced", "conflict")
+SYNC_STATUS_SQL = (
+    "CASE WHEN sync_status = 'conflict' THEN 'conflict' "
+    "WHEN sync_status = 'synced' AND synced_at >= updated_at THEN 'synced' "
+    "ELSE 'pending' END"
+)
+# How a pull settles a snippet changed both locally and on the server since
+# the last sync: by the later updated_at ("newer"), or always one side
+SYNC_STRATEGIES = ("newer", "local", "remote")
+# A team-scoped snippet (snip share --team) is selected by team id or name
+TEAM_MATCH_SQL = "(team_id = ? OR team_name = ?)"
+
+
+# Known plaintext kept encrypted in meta to tell a wrong master key at startup
+KEY_CHECK_PLAINTEXT = b"macolint-key-check"
+KEY_MISMATCH_MESSAGE = (
+    "The master key does not match this database: it was written with another "
+    "key. Check MACOLINT_MASTER_KEY and --vault, or restore the key it was "
+    "created with ('snip doctor' shows where the key comes from)."
+)
+
+
+class KeyMismatchError(DecryptionError):
+    """The master key is not the one the database was written with."""
+
+
+# Every unencrypted SQLite file starts with these bytes; SQLCipher files do not
+SQLITE_HEADER = b"SQLite format 3\x00"
+
+
+def sqlcipher_module():
+    """
+    The SQLCipher driver used when encrypt_database is on (same API as sqlite3).
+
+    Raises:
+        ValueError: If the sqlcipher3 package is not installed
+    """
+    try:
+        from sqlcipher3 import dbapi2
+    except ImportError:
+        raise ValueError(
+            "encrypt_database needs the sqlcipher3 package (pip install sqlcipher3)."
+        )
+    return dbapi2
+
+
+def database_key(master_key: bytes) -> str:
+    """
+    The SQLCipher key for a master key, as a PRAGMA key value (a raw 256-bit
+    key, so SQLCipher skips its own key derivation).
+    """
+    key = hmac.new(master_key, b"macolint-database-key", hashlib.sha256).hexdigest()
+    return f"\"x'{key}'\""
+
+
+def is_plain_sqlite(db_path) -> Optional[bool]:
+    """Whether db_path is an unencrypted SQLite file (None if missing or empty)."""
+    if not os.path.exists(db_path) or os.path.getsize(db_path) == 0:
+        return None
+    with open(db_path, "rb") as f:
+        return f.read(len(SQLITE_HEADER)) == SQLITE_HEADER
+
+
+def check_integrity(db_path, key: Optional[str] = None) -> List[str]:
+    """
+    Run SQLite's integrity check on a database file without opening it
+    through Database (which may fail on a damaged file). `key` (from
+    database_key) opens an encrypted database.
+    Returns the problems found; an empty list means the file is sound.
+
+    Raises:
+        sqlite3.DatabaseError: If the file is not a readable SQLite database
+    """
+    if key is None:
+        conn = sqlite3.connect(db_path)
+    else:
+        conn = sqlcipher_module().connect(str(db_path))
+        conn.execute(f"PRAGMA key = {key}")
+    try:
+        results = [row[0] for row in conn.execute("PRAGMA integrity_check").fetchall()]
+    finally:
+        conn.close()
+    return [] if results == ["ok"] else results
 
 
 class Database:
     """Handles all database operations with encryption."""
     
-    def __init__(self):
-        self.db_path = get_db_path()
-        self.fernet = get_fernet()
+    def __init__(
+        self,
+        db_path=None,
+        master_key: Optional[bytes] = None,
+        encrypt_names: Optional[bool] = None,
+        encrypt_database: Optional[bool] = None,
+    ):
+        """
+        Open (creating or migrating) the snippet database. Without arguments
+        this is the active vault's database under its master key, with
+        encrypt_names and encrypt_database from settings.json; macolint.api
+        passes them explicitly.
+
+        Raises:
+            ValueError: If the file is (or is not) SQLCipher-encrypted while
+                encrypt_database says otherwise, or its key does not match
+        """
+        self.db_path = db_path or get_db_path()
+        master_key = master_key or get_master_key()
+        self.fernet = Fernet(master_key)
+        self._encrypt_names = encrypt_names
+        if encrypt_database is None:
+            encrypt_database = bool(load_settings()["encrypt_database"])
+        # The driver module (sqlite3, or SQLCipher's with the same API) and the
+        # PRAGMA key value every connection is opened with when encrypted
+        self._sqlite = sqlcipher_module() if encrypt_database else sqlite3
+        self._db_key: Optional[str] = database_key(master_key) if encrypt_database else None
+        self._check_file_encryption()
+        # Set while names are encrypted (see _load_names)
+        self._name_key: Optional[bytes] = None
+        self._names: Dict[str, str] = {}
+        # Loaded on first use (see _content_hash)
+        self._content_key: Optional[bytes] = None
         self._init_database()
     
     # ------------------------------------------------------------------
@@ -21,154 +171,305 @@ class Database:
     # ------------------------------------------------------------------
 
     def _init_database(self):
-        """Initialize or migrate the database schema."""
-        conn = sqlite3.connect(self.db_path)
+        """Initialize or migrate the database schema (see macolint.migrations)."""
+        conn = self._get_connection()
         cursor = conn.cursor()
+
+        # WAL lets readers and a writer work at the same time; the setting is
+        # stored in the database file and must be made outside a transaction
+        cursor.execute("PRAGMA journal_mode=WAL")
+        # Migrate in one write transaction so concurrent snip processes
+        # starting up wait for each other instead of migrating twice
+        cursor.execute("BEGIN IMMEDIATE")
+        try:
+            migrate(self, cursor)
+            # A wrong key rolls the migrations back with it
+            self._check_master_key(cursor)
+        except (KeyMismatchError, SchemaVersionError):
+            conn.rollback()
+            conn.close()
+            raise
+
+        # Names are stored as-is, or (encrypt_names setting) as keyed hashes
+        # with the encrypted name beside them; meta records which, and the key
+        self._load_names(cursor)
+        encrypt_names = self._encrypt_names
+        if encrypt_names is None:
+            encrypt_names = bool(load_settings()["encrypt_names"])
+        if encrypt_names != (self._name_key is not None):
+            self._migrate_names(cursor, encrypt_names)
         
-        # Modules table: hierarchical containers for snippets
-        cursor.execute(
-            """
-            CREATE TABLE IF NOT EXISTS modules (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT NOT NULL,
-                parent_id INTEGER NULL,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
-            )
-            """
-        )
+        conn.commit()
+        conn.close()
+    
+    def _check_master_key(self, cursor: sqlite3.Cursor) -> None:
+        """
+        Verify the master key against the key_check value in meta (a known
+        plaintext encrypted with the key), so a wrong key fails once with
+        a clear message instead of on every snippet. A database without one
+        gets it now, if its existing content (when any) decrypts.
 
+        Raises:
+            KeyMismatchError: If the database was written with another key
+        """
+        cursor.execute("SELECT value FROM meta WHERE key = 'key_check'")
+        row = cursor.fetchone()
+        if row is not None:
+            try:
+                matches = self.fernet.decrypt(row[0].encode("ascii")) == KEY_CHECK_PLAINTEXT
+            except InvalidToken:
+                matches = False
+            if not matches:
+                raise KeyMismatchError(KEY_MISMATCH_MESSAGE)
+            return
+
+        # Older databases: any of a few snippets decrypting proves the key
+        cursor.execute("SELECT content_encrypted FROM snippets LIMIT 5")
+        samples = [data for (data,) in cursor.fetchall()]
+        if samples:
+            for data in samples:
+                try:
+                    self.fernet.decrypt(blob_to_token(data))
+                    break
+                except InvalidToken:
+                    continue
+            else:
+                raise KeyMismatchError(KEY_MISMATCH_MESSAGE)
         cursor.execute(
-            """
-            CREATE UNIQUE INDEX IF NOT EXISTS idx_modules_parent_name
-            ON modules(parent_id, name)
-            """
+            "INSERT INTO meta (key, value) VALUES ('key_check', ?)",
+            (self.fernet.encrypt(KEY_CHECK_PLAINTEXT).decode("ascii"),),
         )
 
-        # Snippets table migration: ensure module_id + entity_type exist and
-        # uniqueness is on (module_id, name)
-        cursor.execute(
-            "SELECT name FROM sqlite_master WHERE type='table' AND name='snippets'"
-        )
-        exists = cursor.fetchone() is not None
+    def get_schema_version(self) -> int:
+        """The database's schema version (see migrations.SCHEMA_VERSION)."""
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        version = get_schema_version(cursor)
+        conn.close()
+        return version
 
-        if not exists:
-            cursor.execute(
-                """
-                CREATE TABLE snippets (
-                    id INTEGER PRIMARY KEY AUTOINCREMENT,
-                    name TEXT NOT NULL,
-                    module_id INTEGER NULL,
-                    entity_type TEXT NOT NULL DEFAULT 'snippet',
-                    content_encrypted BLOB NOT NULL,
-                    is_shared INTEGER NOT NULL DEFAULT 0,
-                    created_at TEXT NOT NULL,
-                    updated_at TEXT NOT NULL
-                )
-                """
+    def _get_connection(self, **kwargs):
+        """
+        Get a database connection. It waits up to BUSY_TIMEOUT_SECONDS for
+        locks held by other snip processes instead of failing at once.
+        With encrypt_database the connection is keyed before anything is read.
+        """
+        conn = self._sqlite.connect(str(self.db_path), timeout=BUSY_TIMEOUT_SECONDS, **kwargs)
+        if self._db_key is not None:
+            conn.execute(f"PRAGMA key = {self._db_key}")
+        return conn
+
+    def _check_file_encryption(self):
+        """
+        Refuse to open a database whose encryption does not match the
+        encrypt_database setting, with instructions instead of SQLite's
+        "file is not a database".
+        """
+        plain = is_plain_sqlite(self.db_path)
+        convert = (
+            "To convert it, run 'snip export FILE' with the old setting, move "
+            f"{self.db_path} aside, change the setting and run 'snip import FILE'."
+        )
+        if plain is True and self._db_key is not None:
+            raise ValueError(
+                f"The snippet database is not encrypted, but encrypt_database is on. {convert}"
             )
-            cursor.execute(
-                """
-                CREATE UNIQUE INDEX IF NOT EXISTS idx_snippets_module_name
-                ON snippets(module_id, name)
-                """
+        if plain is False and self._db_key is None:
+            raise ValueError(
+                "The snippet database is encrypted (or damaged): set \"encrypt_database\": "
+                f"true in settings.json to open it. {convert}"
             )
-        else:
-            # Check existing schema and migrate if needed
-            cursor.execute("PRAGMA table_info(snippets)")
-            cols = [row[1] for row in cursor.fetchall()]
-            needs_migration = "module_id" not in cols or "entity_type" not in cols
-            needs_is_shared = "is_shared" not in cols
-
-            # Add is_shared column if missing
-            if needs_is_shared:
-                cursor.execute(
-                    "ALTER TABLE snippets ADD COLUMN is_shared INTEGER NOT NULL DEFAULT 0"
-                )
-
-            if needs_migration:
-                cursor.execute(
-                    """
-                    CREATE TABLE snippets_new (
-                        id INTEGER PRIMARY KEY AUTOINCREMENT,
-                        name TEXT NOT NULL,
-                        module_id INTEGER NULL,
-                        entity_type TEXT NOT NULL DEFAULT 'snippet',
-                        content_encrypted BLOB NOT NULL,
-                        is_shared INTEGER NOT NULL DEFAULT 0,
-                        created_at TEXT NOT NULL,
-                        updated_at TEXT NOT NULL
-                    )
-                """
-                )
-                cursor.execute(
-                    """
-                    CREATE UNIQUE INDEX IF NOT EXISTS idx_snippets_module_name
-                    ON snippets_new(module_id, name)
-                    """
+        if plain is False:
+            conn = self._get_connection()
+            try:
+                conn.execute("SELECT count(*) FROM sqlite_master").fetchone()
+            except self._sqlite.DatabaseError:
+                raise ValueError(
+                    "Cannot open the encrypted snippet database: the master key does not "
+                    "match the one it was encrypted with."
                 )
-                # Migrate existing data; old table uses name as full identifier
-                # Check if is_shared exists in old table
-                has_is_shared = "is_shared" in cols
-                if has_is_shared:
-                    cursor.execute(
-                        """
-                        INSERT INTO snippets_new (
-                            id, name, module_id, entity_type,
-                            content_encrypted, is_shared, created_at, updated_at
-                        )
-                        SELECT
-                            id,
-                            name,
-                            NULL as module_id,
-                            'snippet' as entity_type,
-                            content_encrypted,
-                            is_shared,
-                            created_at,
-                            updated_at
-                        FROM snippets
-                        """
-                    )
-                else:
-                    cursor.execute(
-                        """
-                        INSERT INTO snippets_new (
-                            id, name, module_id, entity_type,
-                            content_encrypted, is_shared, created_at, updated_at
-                        )
-                        SELECT
-                            id,
-                            name,
-                            NULL as module_id,
-                            'snippet' as entity_type,
-                            content_encrypted,
-                            0 as is_shared,
-                            created_at,
-                            updated_at
-                        FROM snippets
-                        """
-                    )
-                cursor.execute("DROP TABLE snippets")
-                cursor.execute("ALTER TABLE snippets_new RENAME TO snippets")
-        
-        conn.commit()
-        conn.close()
-    
-    def _get_connection(self):
-        """Get a database connection."""
-        return sqlite3.connect(self.db_path)
+            finally:
+                conn.close()
     
     # ------------------------------------------------------------------
     # Encryption helpers
     # ------------------------------------------------------------------
 
     def _encrypt_content(self, content: str) -> bytes:
-        """Encrypt snippet content."""
-        return self.fernet.encrypt(content.encode("utf-8"))
+        """
+        Encrypt snippet content (compressed first when that shrinks it),
+        as the raw token bytes stored in BLOB columns (see token_to_blob).
+        """
+        return token_to_blob(self.fernet.encrypt(pack_content(content)))
+
+    def _encrypt_bytes(self, data: bytes) -> bytes:
+        """Encrypt raw bytes (attachment data)."""
+        return token_to_blob(self.fernet.encrypt(data))
+
+    def _decrypt_bytes(self, encrypted: bytes) -> bytes:
+        """Decrypt raw bytes (attachment data)."""
+        return self.fernet.decrypt(blob_to_token(encrypted))
     
+    @staticmethod
+    def _content_size(content: str) -> Tuple[Optional[int], Optional[int]]:
+        """
+        (byte_len, line_count) of plaintext content, or (None, None) for
+        protected content, whose plaintext is not known here.
+        """
+        if is_protected(content):
+            return None, None
+        return len(content.encode("utf-8")), len(content.splitlines())
+
     def _decrypt_content(self, encrypted: bytes) -> str:
-        """Decrypt snippet content."""
-        return self.fernet.decrypt(encrypted).decode("utf-8")
+        """
+        Decrypt snippet content.
+
+        Raises:
+            DecryptionError: If it was not encrypted with this master key
+        """
+        try:
+            data = self.fernet.decrypt(blob_to_token(encrypted))
+        except InvalidToken:
+            raise DecryptionError(
+                "Cannot decrypt the snippet: the master key does not match the one it "
+                "was saved with. Run 'snip doctor' to check."
+            )
+        return unpack_content(data)
+
+    # ------------------------------------------------------------------
+    # Name encryption
+    # ------------------------------------------------------------------
+
+    def _load_names(self, cursor: sqlite3.Cursor) -> None:
+        """
+        Load the name key and decrypt every module and snippet name into
+        the stored-name -> name map. Does nothing while names are plaintext.
+
+        Raises:
+            ValueError: If the name key cannot be decrypted with the master key
+        """
+        cursor.execute("SELECT value FROM meta WHERE key = 'name_key'")
+        row = cursor.fetchone()
+        if row is None:
+            self._name_key = None
+            self._names = {}
+            return
+        try:
+            self._name_key = self.fernet.decrypt(row[0].encode("ascii"))
+        except InvalidToken:
+            raise ValueError(
+                "Cannot decrypt snippet names: the master key does not match this database."
+            )
+        names: Dict[str, str] = {}
+        for table in ("modules", "snippets"):
+            cursor.execute(f"SELECT name, name_encrypted FROM {table}")
+            for stored, encrypted in cursor.fetchall():
+                try:
+                    names[stored] = self._decrypt_content(encrypted)
+                except Exception:
+                    # Shown by its hash; snip doctor reports the bad content
+                    continue
+        self._names = names
+
+    def _migrate_names(self, cursor: sqlite3.Cursor, encrypt: bool) -> None:
+        """Switch every stored name to a keyed hash plus ciphertext, or back (caller commits)."""
+        if encrypt:
+            self._name_key = os.urandom(32)
+            cursor.execute(
+                "INSERT INTO meta (key, value) VALUES ('name_key', ?)",
+                (self.fernet.encrypt(self._name_key).decode("ascii"),),
+            )
+        for table in ("modules", "snippets"):
+            cursor.execute(f"SELECT id, name, name_encrypted FROM {table}")
+            for row_id, name, encrypted in cursor.fetchall():
+                if encrypt:
+                    values = self._name_columns(name)
+                else:
+                    values = (self._decrypt_content(encrypted), None)
+                cursor.execute(
+                    f"UPDATE {table} SET name = ?, name_encrypted = ? WHERE id = ?",
+                    values + (row_id,),
+                )
+        # Queued server deletions keep whole paths (see _queue_sync_deletions)
+        cursor.execute("SELECT id, path FROM sync_deletions")
+        for deletion_id, path in cursor.fetchall():
+            if encrypt:
+                path = self.fernet.encrypt(path.encode("utf-8")).decode("ascii")
+            else:
+                path = self.fernet.decrypt(path.encode("ascii")).decode("utf-8")
+            cursor.execute("UPDATE sync_deletions SET path = ? WHERE id = ?", (path, deletion_id))
+        if not encrypt:
+            cursor.execute("DELETE FROM meta WHERE key = 'name_key'")
+            self._name_key = None
+            self._names = {}
+
+    def _content_hash(self, cursor: sqlite3.Cursor, content: str) -> Optional[str]:
+        """
+        HMAC-SHA256 of plaintext content under a random per-database key
+        (kept in meta, created on first use), or None for protected content.
+        Equal content has an equal hash; the hash reveals nothing without the key.
+        """
+        if is_protected(content):
+            return None
+        return self._keyed_hash(cursor, content)
+
+    def _keyed_hash(self, cursor: sqlite3.Cursor, content: str) -> str:
+        """HMAC-SHA256 of content under the content key (see _content_hash)."""
+        if self._content_key is None:
+            cursor.execute("SELECT value FROM meta WHERE key = 'content_key'")
+            row = cursor.fetchone()
+            if row is None:
+                self._content_key = os.urandom(32)
+                cursor.execute(
+                    "INSERT INTO meta (key, value) VALUES ('content_key', ?)",
+                    (self.fernet.encrypt(self._content_key).decode("ascii"),),
+                )
+            else:
+                self._content_key = self.fernet.decrypt(row[0].encode("ascii"))
+        return hmac.new(self._content_key, content.encode("utf-8"), hashlib.sha256).hexdigest()
+
+    def _stored_name(self, name: str) -> str:
+        """The value kept in a name column for `name`: the name itself or its keyed hash."""
+        if self._name_key is None:
+            return name
+        return hmac.new(self._name_key, name.encode("utf-8"), hashlib.sha256).hexdigest()
+
+    def _name_columns(self, name: str) -> Tuple[str, Optional[bytes]]:
+        """(name, name_encrypted) values for writing a module or snippet name."""
+        stored = self._stored_name(name)
+        if self._name_key is None:
+            return stored, None
+        self._names[stored] = name
+        return stored, self._encrypt_content(name)
+
+    def _display_name(self, stored: str) -> str:
+        """The name for a name column value (see _stored_name)."""
+        if self._name_key is None:
+            return stored
+        if stored not in self._names:
+            # Written by another snip process after this one loaded the names
+            conn = self._get_connection()
+            self._load_names(conn.cursor())
+            conn.close()
+        return self._names.get(stored, stored)
+
+    def _module_from_row(self, row: tuple) -> Module:
+        """Module.from_row with the name decrypted."""
+        return Module.from_row((row[0], self._display_name(row[1])) + tuple(row[2:]))
+
+    def _sorted_by_name(self, paths: List[str], pinned: Optional[List[bool]] = None) -> List[str]:
+        """
+        Order full paths by snippet name, pinned first when `pinned` is given,
+        as ORDER BY [pinned DESC,] name does. Hashed names do not sort like
+        the names, so this only reorders when names are encrypted.
+        """
+        if self._name_key is None:
+            return paths
+        order = sorted(
+            range(len(paths)),
+            key=lambda i: (not pinned[i] if pinned else False, paths[i].rsplit("/", 1)[-1]),
+        )
+        return [paths[i] for i in order]
 
     # ------------------------------------------------------------------
     # Path and module helpers
@@ -198,8 +499,19 @@ class Database:
 
         conn = self._get_connection()
         cursor = conn.cursor()
-        now = datetime.now().isoformat()
+        module = self._resolve_module_path_with_cursor(cursor, module_path, create)
+        conn.commit()
+        conn.close()
+        return module
+
+    def _resolve_module_path_with_cursor(
+        self, cursor: sqlite3.Cursor, module_path: Optional[str], create: bool
+    ) -> Optional[Module]:
+        """Resolve a module path using an existing cursor (caller commits)."""
+        if not module_path:
+            return None
 
+        now = datetime.now().isoformat()
         parent_id: Optional[int] = None
         current_module: Optional[Module] = None
 
@@ -211,7 +523,7 @@ class Database:
                     FROM modules
                     WHERE name = ? AND parent_id IS NULL
                     """,
-                    (segment,),
+                    (self._stored_name(segment),),
                 )
             else:
                 cursor.execute(
@@ -220,30 +532,28 @@ class Database:
                     FROM modules
                     WHERE name = ? AND parent_id = ?
                     """,
-                    (segment, parent_id),
+                    (self._stored_name(segment), parent_id),
                 )
             row = cursor.fetchone()
 
             if row is None:
                 if not create:
-                    conn.close()
                     return None
                 # Create missing module
+                stored, encrypted_name = self._name_columns(segment)
                 cursor.execute(
                     """
-                    INSERT INTO modules (name, parent_id, created_at, updated_at)
-                    VALUES (?, ?, ?, ?)
+                    INSERT INTO modules (name, name_encrypted, parent_id, created_at, updated_at)
+                    VALUES (?, ?, ?, ?, ?)
                     """,
-                    (segment, parent_id, now, now),
+                    (stored, encrypted_name, parent_id, now, now),
                 )
                 module_id = cursor.lastrowid
-                row = (module_id, segment, parent_id, now, now)
+                row = (module_id, stored, parent_id, now, now)
 
-            current_module = Module.from_row(row)
+            current_module = self._module_from_row(row)
             parent_id = current_module.id
 
-        conn.commit()
-        conn.close()
         return current_module
 
     def get_module_by_path(self, module_path: str) -> Optional[Module]:
@@ -252,7 +562,7 @@ class Database:
 
     def create_module_path(self, module_path: str) -> Module:
         """Ensure a module path exists and return the deepest module."""
-        module = self._resolve_module_path(module_path, create=True)
+        module = self._resolve_module_path(validate_name(module_path), create=True)
         assert module is not None  # For type checkers; create=True guarantees this
         return module
 
@@ -286,7 +596,10 @@ class Database:
 
         rows = cursor.fetchall()
         conn.close()
-        return [Module.from_row(row) for row in rows]
+        modules = [self._module_from_row(row) for row in rows]
+        if self._name_key is not None:
+            modules.sort(key=lambda child: child.name)
+        return modules
 
     # ------------------------------------------------------------------
     # Snippet operations (path-aware)
@@ -294,173 +607,524 @@ class Database:
 
     def _get_snippet_row_by_path(
         self, full_path: str
-    ) -> Optional[Tuple[int, str, bytes, int, str, str]]:
-        """Internal helper to fetch a snippet row by hierarchical path."""
+    ) -> Optional[Tuple[int, str, bytes, int, str, str, str, Optional[str], Optional[str], int]]:
+        """
+        Internal helper to fetch a snippet row by hierarchical path.
+        The first six columns are the core Snippet row; extra metadata follows.
+        Expired snippets are treated as absent.
+        """
         module_path, snippet_name = self._split_path(full_path)
         module = self._resolve_module_path(module_path, create=False)
+        if module_path and module is None:
+            # A missing module must not fall back to a root-level snippet
+            return None
 
         conn = self._get_connection()
         cursor = conn.cursor()
 
         if module is None:
             cursor.execute(
-                """
-                SELECT id, name, content_encrypted, is_shared, created_at, updated_at
+                f"""
+                SELECT id, name, content_encrypted, is_shared, created_at, updated_at, kind,
+                       language, expires_at, pinned, protected, byte_len, line_count,
+                       content_type, last_accessed_at, description
                 FROM snippets
-                WHERE name = ? AND module_id IS NULL
+                WHERE name = ? AND module_id IS NULL AND {LIVE_SQL}
                 """,
-                (snippet_name,),
+                (self._stored_name(snippet_name),),
             )
         else:
             cursor.execute(
-                """
-                SELECT id, name, content_encrypted, is_shared, created_at, updated_at
+                f"""
+                SELECT id, name, content_encrypted, is_shared, created_at, updated_at, kind,
+                       language, expires_at, pinned, protected, byte_len, line_count,
+                       content_type, last_accessed_at, description
                 FROM snippets
-                WHERE name = ? AND module_id = ?
+                WHERE name = ? AND module_id = ? AND {LIVE_SQL}
                 """,
-                (snippet_name, module.id),
+                (self._stored_name(snippet_name), module.id),
             )
 
         row = cursor.fetchone()
         conn.close()
         return row
 
-    def save_snippet(self, full_path: str, content: str) -> bool:
+    def save_snippet(
+        self,
+        full_path: str,
+        content: str,
+        content_type: Optional[str] = None,
+        kind: Optional[str] = None,
+        tags: Optional[List[str]] = None,
+        language: Optional[str] = None,
+        expires_at: Optional[datetime] = None,
+        description: Optional[str] = None,
+        detected_type: Optional[str] = None,
+    ) -> bool:
         """
         Save a snippet at the given hierarchical path.
+        The content type is auto-detected (detected_type stands in for
+        detection, e.g. from the plaintext of protected content) unless
+        content_type is given; a given type is kept by later updates that
+        give none. New snippets default to kind 'note'; updates keep the
+        existing kind (and tags) unless one is given. A tags list replaces
+        existing tags.
+        The language is guessed when not given, and kept once set.
+        With expires_at the snippet expires at that time; otherwise new
+        snippets never expire and updates keep their expiry.
+        Updates keep the description unless one is given ("" removes it).
         Returns True if created, False if updated.
         """
-        module_path, snippet_name = self._split_path(full_path)
-        module = self._resolve_module_path(module_path, create=True)
-        module_id = module.id if module is not None else None
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        created = self._upsert_snippet(
+            cursor, full_path, content, content_type, kind, tags, language, expires_at,
+            description, detected_type,
+        )
+        conn.commit()
+        conn.close()
+        return created
 
+    def save_snippets_bulk(
+        self,
+        records: List[Tuple[str, str, Optional[str], Optional[str],
+                            Optional[List[str]], Optional[str], Optional[str]]],
+        dry_run: bool = False,
+        atomic: bool = False,
+    ) -> List[Tuple[Optional[bool], Optional[str]]]:
+        """
+        Save many (full_path, content, content_type, kind, tags, language,
+        description) records in a single transaction. A failing record is reported and
+        skipped without affecting the others; with atomic, any failure
+        rolls back the whole transaction instead (every record is still
+        tried, so all failures are reported). With dry_run the transaction
+        is rolled back. Returns one (created, error) pair per record, in
+        order; created is None when the record failed.
+        """
         conn = self._get_connection()
         cursor = conn.cursor()
-        
+        results: List[Tuple[Optional[bool], Optional[str]]] = []
+
+        try:
+            cursor.execute("BEGIN IMMEDIATE")
+            for full_path, content, content_type, kind, tags, language, description in records:
+                cursor.execute("SAVEPOINT bulk_record")
+                try:
+                    created = self._upsert_snippet(
+                        cursor, full_path, content, content_type, kind, tags, language,
+                        description=description,
+                    )
+                    cursor.execute("RELEASE SAVEPOINT bulk_record")
+                    results.append((created, None))
+                except (self._sqlite.Error, ValueError) as e:
+                    cursor.execute("ROLLBACK TO SAVEPOINT bulk_record")
+                    cursor.execute("RELEASE SAVEPOINT bulk_record")
+                    results.append((None, str(e)))
+            if dry_run or (atomic and any(error for _, error in results)):
+                conn.rollback()
+            else:
+                conn.commit()
+        finally:
+            conn.close()
+        return results
+
+    def _upsert_snippet(
+        self,
+        cursor: sqlite3.Cursor,
+        full_path: str,
+        content: str,
+        content_type: Optional[str],
+        kind: Optional[str],
+        tags: Optional[List[str]] = None,
+        language: Optional[str] = None,
+        expires_at: Optional[datetime] = None,
+        description: Optional[str] = None,
+        detected_type: Optional[str] = None,
+    ) -> bool:
+        """
+        Insert or update one snippet using an existing cursor (caller commits).
+        If tags is not None, it replaces the snippet's tags. A content_type
+        is stored as given (content_type_explicit); without one, a given
+        type is kept and otherwise the detected one (or detected_type)
+        stored. Without a language, an existing one is kept or a detected
+        one stored.
+        A description replaces the stored one; "" removes it.
+        An expired snippet at the same path is replaced by a new one.
+        Content made by crypto.protect() marks the snippet protected; its
+        type and language are never guessed from the ciphertext.
+
+        Raises:
+            ValueError: If full_path is not a valid name (see validate_name)
+        """
+        full_path = validate_name(full_path)
+        module_path, snippet_name = self._split_path(full_path)
+        module = self._resolve_module_path_with_cursor(cursor, module_path, create=True)
+        module_id = module.id if module is not None else None
+
         now = datetime.now().isoformat()
+        protected = is_protected(content)
+        if detected_type is None:
+            detected_type = "plaintext" if protected else detect_content_type(content)
+        detected_language = (
+            detect_language(content) if language is None and not protected else None
+        )
+        byte_len, line_count = self._content_size(content)
+        content_hash = self._content_hash(cursor, content)
         encrypted_content = self._encrypt_content(content)
-        
-        # Try to insert new snippet
-        try:
+        expires = expires_at.isoformat(timespec="seconds") if expires_at else None
+        if description is not None:
+            description = description.strip()
+        self._delete_expired_with_cursor(cursor)
+
+        # Look the snippet up first: the UNIQUE(name, module_id) constraint
+        # does not catch duplicates at root level, where module_id is NULL
+        if module_id is None:
+            cursor.execute(
+                "SELECT id FROM snippets WHERE name = ? AND module_id IS NULL",
+                (self._stored_name(snippet_name),),
+            )
+        else:
+            cursor.execute(
+                "SELECT id FROM snippets WHERE name = ? AND module_id = ?",
+                (self._stored_name(snippet_name), module_id),
+            )
+        existing = cursor.fetchone()
+
+        if existing is None:
             cursor.execute(
                 """
                 INSERT INTO snippets (
-                    name, module_id, entity_type,
-                    content_encrypted, is_shared, created_at, updated_at
+                    name, name_encrypted, module_id, entity_type,
+                    content_encrypted, is_shared, content_type, content_type_explicit,
+                    kind, language, expires_at, protected, byte_len, line_count,
+                    content_hash, description, created_at, updated_at
                 )
-                VALUES (?, ?, 'snippet', ?, 0, ?, ?)
+                VALUES (?, ?, ?, 'snippet', ?, 0, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
                 """,
-                (snippet_name, module_id, encrypted_content, now, now),
+                self._name_columns(snippet_name) + (module_id, encrypted_content,
+                 content_type or detected_type, int(content_type is not None), kind or "note",
+                 language or detected_language, expires, int(protected), byte_len, line_count,
+                 content_hash, description or None, now, now),
             )
-            conn.commit()
-            conn.close()
+            snippet_id = cursor.lastrowid
+            self._add_version_with_cursor(cursor, snippet_id, encrypted_content, now)
+            if tags is not None:
+                self._set_tags_with_cursor(cursor, snippet_id, tags)
             return True
-        except sqlite3.IntegrityError:
-            # Snippet exists; update instead
-            if module_id is None:
-                cursor.execute(
-                    """
-                    UPDATE snippets
-                    SET content_encrypted = ?, updated_at = ?
-                    WHERE name = ? AND module_id IS NULL
-                    """,
-                    (encrypted_content, now, snippet_name),
-                )
-            else:
-                cursor.execute(
-                    """
-                UPDATE snippets 
-                SET content_encrypted = ?, updated_at = ?
-                    WHERE name = ? AND module_id = ?
-                    """,
-                    (encrypted_content, now, snippet_name, module_id),
-                )
-            conn.commit()
-            conn.close()
-            return False
+
+        if tags is not None:
+            self._set_tags_with_cursor(cursor, existing[0], tags)
+        cursor.execute(
+            """
+            UPDATE snippets
+            SET content_encrypted = ?,
+                content_type = CASE WHEN ? IS NOT NULL THEN ?
+                    WHEN content_type_explicit = 1 THEN content_type ELSE ? END,
+                content_type_explicit = CASE WHEN ? IS NOT NULL THEN 1
+                    ELSE content_type_explicit END,
+                kind = COALESCE(?, kind), language = COALESCE(?, language, ?),
+                expires_at = COALESCE(?, expires_at), protected = ?,
+                byte_len = ?, line_count = ?, content_hash = ?,
+                description = CASE WHEN ? IS NULL THEN description ELSE NULLIF(?, '') END,
+                updated_at = ?
+            WHERE id = ?
+            """,
+            (encrypted_content, content_type, content_type, detected_type, content_type, kind,
+             language, detected_language, expires, int(protected), byte_len, line_count,
+             content_hash, description, description, now, existing[0]),
+        )
+        self._add_version_with_cursor(cursor, existing[0], encrypted_content, now)
+        self._mark_pending_with_cursor(cursor, existing[0])
+        return False
     
+    def snippet_exists(self, full_path: str) -> bool:
+        """True if a live snippet is stored at full_path; nothing is decrypted."""
+        return self._get_snippet_row_by_path(full_path) is not None
+
     def get_snippet(self, full_path: str) -> Optional[Snippet]:
-        """Retrieve a snippet by hierarchical path."""
+        """
+        Retrieve a snippet by hierarchical path.
+        Snippets saved before sizes were recorded get their byte and line
+        counts stored here.
+        """
         row = self._get_snippet_row_by_path(full_path)
         if row is None:
             return None
-        encrypted_content = row[2]
-        is_shared = bool(row[3]) if len(row) > 3 else False
+        core, extra = row[:6], row[6:]
+        encrypted_content = core[2]
+        is_shared = bool(core[3])
         content = self._decrypt_content(encrypted_content)
-        return Snippet.from_row(row, content, is_shared=is_shared)
-    
-    def update_snippet(self, full_path: str, content: str) -> bool:
-        """Update an existing snippet by hierarchical path."""
-        module_path, snippet_name = self._split_path(full_path)
-        module = self._resolve_module_path(module_path, create=False)
-        module_id = module.id if module is not None else None
+        snippet = Snippet.from_row(core, content, is_shared=is_shared)
+        snippet.name = self._display_name(core[1])
+        snippet.kind = extra[0] or "note"
+        snippet.language = extra[1]
+        snippet.expires_at = parse_timestamp(extra[2]) if extra[2] else None
+        snippet.pinned = bool(extra[3])
+        snippet.protected = bool(extra[4])
+        snippet.byte_len, snippet.line_count = extra[5], extra[6]
+        snippet.content_type = extra[7]
+        snippet.last_accessed_at = parse_timestamp(extra[8]) if extra[8] else None
+        snippet.description = extra[9]
 
         conn = self._get_connection()
         cursor = conn.cursor()
-        
-        now = datetime.now().isoformat()
-        encrypted_content = self._encrypt_content(content)
-        
-        if module_id is None:
-            cursor.execute(
-                """
-                UPDATE snippets
-                SET content_encrypted = ?, updated_at = ?
-                WHERE name = ? AND module_id IS NULL
-                """,
-                (encrypted_content, now, snippet_name),
-            )
-        else:
+        if snippet.byte_len is None and not snippet.protected:
+            snippet.byte_len, snippet.line_count = self._content_size(content)
             cursor.execute(
-                """
-            UPDATE snippets 
-            SET content_encrypted = ?, updated_at = ?
-                WHERE name = ? AND module_id = ?
-                """,
-                (encrypted_content, now, snippet_name, module_id),
+                "UPDATE snippets SET byte_len = ?, line_count = ? WHERE id = ?",
+                (snippet.byte_len, snippet.line_count, snippet.id),
             )
-        
-        updated = cursor.rowcount > 0
-        conn.commit()
+            conn.commit()
+        snippet.tags = self._get_tags_for_ids(cursor, [snippet.id])[snippet.id]
         conn.close()
-        return updated
+        return snippet
     
-    def delete_snippet(self, full_path: str) -> bool:
-        """Delete a snippet by hierarchical path."""
-        module_path, snippet_name = self._split_path(full_path)
-        module = self._resolve_module_path(module_path, create=False)
-        module_id = module.id if module is not None else None
-
+    def update_snippet(self, full_path: str, content: str) -> bool:
+        """
+        Update an existing snippet by hierarchical path (re-detecting its
+        content type unless it was given).
+        """
         conn = self._get_connection()
         cursor = conn.cursor()
-        
-        if module_id is None:
-            cursor.execute(
-                """
-                DELETE FROM snippets
-                WHERE name = ? AND module_id IS NULL
-                """,
-                (snippet_name,),
-            )
-        else:
-            cursor.execute(
-                """
-                DELETE FROM snippets
-                WHERE name = ? AND module_id = ?
-                """,
-                (snippet_name, module_id),
-            )
 
-        deleted = cursor.rowcount > 0
+        snippet_id = self._find_snippet_id_with_cursor(cursor, full_path)
+        if snippet_id is not None:
+            self._set_content_with_cursor(cursor, snippet_id, content)
+
         conn.commit()
         conn.close()
-        return deleted
-    
+        return snippet_id is not None
+
+    def _set_content_with_cursor(
+        self, cursor: sqlite3.Cursor, snippet_id: int, content: str
+    ) -> None:
+        """
+        Replace a snippet's content and record it as a new version (caller commits).
+        The content type is detected again, unless it was given (see
+        _upsert_snippet); protected content keeps the stored one.
+        """
+        now = datetime.now().isoformat()
+        protected = is_protected(content)
+        byte_len, line_count = self._content_size(content)
+        encrypted_content = self._encrypt_content(content)
+        cursor.execute(
+            """
+            UPDATE snippets
+            SET content_encrypted = ?,
+                content_type = CASE WHEN content_type_explicit = 1 THEN content_type
+                    ELSE COALESCE(?, content_type) END,
+                protected = ?, byte_len = ?, line_count = ?, content_hash = ?, updated_at = ?
+            WHERE id = ?
+            """,
+            (encrypted_content, None if protected else detect_content_type(content),
+             int(protected), byte_len, line_count, self._content_hash(cursor, content), now,
+             snippet_id),
+        )
+        self._add_version_with_cursor(cursor, snippet_id, encrypted_content, now)
+        self._mark_pending_with_cursor(cursor, snippet_id)
+
     # ------------------------------------------------------------------
-    # Listing and search
+    # Versions
     # ------------------------------------------------------------------
 
-    def _build_snippet_full_path_rows(
+    def _add_version_with_cursor(
+        self, cursor: sqlite3.Cursor, snippet_id: int, encrypted_content: bytes, created_at: str
+    ) -> None:
+        cursor.execute(
+            """
+            INSERT INTO snippet_versions (snippet_id, content_encrypted, created_at)
+            VALUES (?, ?, ?)
+            """,
+            (snippet_id, encrypted_content, created_at),
+        )
+
+    def _get_version_rows(self, cursor: sqlite3.Cursor, snippet_id: int) -> List[tuple]:
+        """(content_encrypted, created_at) of every version, oldest first."""
+        cursor.execute(
+            """
+            SELECT content_encrypted, created_at FROM snippet_versions
+            WHERE snippet_id = ?
+            ORDER BY id
+            """,
+            (snippet_id,),
+        )
+        return cursor.fetchall()
+
+    def list_versions(self, full_path: str) -> Optional[List[SnippetVersion]]:
+        """
+        List the saved versions of a snippet, oldest first; the last one is current.
+        Returns None if the snippet does not exist.
+        """
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        snippet_id = self._find_snippet_id_with_cursor(cursor, full_path)
+        rows = self._get_version_rows(cursor, snippet_id) if snippet_id is not None else []
+        conn.close()
+        if snippet_id is None:
+            return None
+
+        return [
+            SnippetVersion(
+                number=number,
+                created_at=parse_timestamp(created_at),
+                size=len(self._decrypt_content(encrypted)),
+                is_current=number == len(rows),
+            )
+            for number, (encrypted, created_at) in enumerate(rows, start=1)
+        ]
+
+    def restore_version(self, full_path: str, number: int) -> bool:
+        """
+        Make version `number` (see list_versions) the current content.
+        The restore is itself saved as a new version, so nothing is lost.
+        Returns False if the snippet does not exist.
+
+        Raises:
+            ValueError: If the snippet has no such version
+        """
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        try:
+            snippet_id = self._find_snippet_id_with_cursor(cursor, full_path)
+            if snippet_id is None:
+                return False
+
+            rows = self._get_version_rows(cursor, snippet_id)
+            if not 1 <= number <= len(rows):
+                raise ValueError(
+                    f"Snippet '{full_path}' has no version {number} (versions 1-{len(rows)})."
+                )
+            content = self._decrypt_content(rows[number - 1][0])
+            self._set_content_with_cursor(cursor, snippet_id, content)
+            conn.commit()
+            return True
+        finally:
+            conn.close()
+    
+    def delete_snippet(self, full_path: str) -> bool:
+        """Delete a snippet by hierarchical path."""
+        module_path, snippet_name = self._split_path(full_path)
+        module = self._resolve_module_path(module_path, create=False)
+        if module_path and module is None:
+            return False
+        module_id = module.id if module is not None else None
+
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        snippet_id = self._find_snippet_id_with_cursor(cursor, full_path)
+        if snippet_id is not None:
+            self._queue_sync_deletions(cursor, [snippet_id])
+        
+        if module_id is None:
+            cursor.execute(
+                """
+                DELETE FROM snippets
+                WHERE name = ? AND module_id IS NULL
+                """,
+                (self._stored_name(snippet_name),),
+            )
+        else:
+            cursor.execute(
+                """
+                DELETE FROM snippets
+                WHERE name = ? AND module_id = ?
+                """,
+                (self._stored_name(snippet_name), module_id),
+            )
+
+        deleted = cursor.rowcount > 0
+        self._delete_orphans(cursor)
+        conn.commit()
+        conn.close()
+        return deleted
+
+    def _delete_expired_with_cursor(self, cursor: sqlite3.Cursor) -> int:
+        """Delete expired snippets and their data (caller commits). Returns the count."""
+        cursor.execute(f"DELETE FROM snippets WHERE NOT {LIVE_SQL}")
+        deleted = cursor.rowcount
+        if deleted:
+            self._delete_orphans(cursor)
+        return deleted
+
+    def prune_expired(self, dry_run: bool = False) -> List[str]:
+        """
+        Delete every expired snippet (with its versions, tags and attachments).
+        Returns the full paths of the expired snippets, sorted; with dry_run
+        nothing is deleted.
+        """
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        cursor.execute(f"SELECT id, name, module_id FROM snippets WHERE NOT {LIVE_SQL}")
+        rows = cursor.fetchall()
+        conn.close()
+        paths = sorted(self._build_snippet_full_path_rows(rows))
+
+        if paths and not dry_run:
+            conn = self._get_connection()
+            self._delete_expired_with_cursor(conn.cursor())
+            conn.commit()
+            conn.close()
+        return paths
+    
+    def prune_namespace(
+        self,
+        prefix: str,
+        keep_n: Optional[int] = None,
+        max_age: Optional[timedelta] = None,
+        dry_run: bool = False,
+    ) -> List[str]:
+        """
+        Delete old snippets directly in the module `prefix` (e.g. 'clip'):
+        all but the keep_n most recently updated, and any not updated within
+        max_age. Either limit may be None. Pinned snippets, and snippets in
+        other modules (including ones nested under prefix), are never
+        touched. Returns the full paths deleted (or, with dry_run, that
+        would be), newest first.
+        """
+        module = self.get_module_by_path(prefix)
+        if module is None:
+            return []
+
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        try:
+            cursor.execute("BEGIN IMMEDIATE")
+            cursor.execute(
+                f"""
+                SELECT id, name, updated_at FROM snippets
+                WHERE module_id = ? AND pinned = 0 AND {LIVE_SQL}
+                ORDER BY updated_at DESC, id DESC
+                """,
+                (module.id,),
+            )
+            rows = cursor.fetchall()
+            cutoff = datetime.now() - max_age if max_age is not None else None
+            doomed = [
+                (snippet_id, name) for index, (snippet_id, name, updated_at) in enumerate(rows)
+                if (keep_n is not None and index >= keep_n)
+                or (cutoff is not None and parse_timestamp(updated_at) < cutoff)
+            ]
+            if doomed and not dry_run:
+                self._queue_sync_deletions(cursor, [snippet_id for snippet_id, _ in doomed])
+                placeholders = ",".join("?" for _ in doomed)
+                cursor.execute(
+                    f"DELETE FROM snippets WHERE id IN ({placeholders})",
+                    tuple(snippet_id for snippet_id, _ in doomed),
+                )
+                self._delete_orphans(cursor)
+                conn.commit()
+            else:
+                conn.rollback()
+        finally:
+            conn.close()
+        base = self.get_module_full_path(module)
+        return [f"{base}/{self._display_name(name)}" for _, name in doomed]
+
+    # ------------------------------------------------------------------
+    # Listing and search
+    # ------------------------------------------------------------------
+
+    def _build_snippet_full_path_rows(
         self,
         rows: List[Tuple[int, str, Optional[int], bytes, int, str, str]],
     ) -> List[str]:
@@ -485,7 +1149,7 @@ class Database:
             )
             mod_rows = cursor.fetchall()
 
-            modules = {row[0]: Module.from_row(row) for row in mod_rows}
+            modules = {row[0]: self._module_from_row(row) for row in mod_rows}
 
             # Build full path for each module via parent chain
             def build_module_path(mid: int) -> str:
@@ -506,7 +1170,7 @@ class Database:
                     if row is None:
                         module_paths[mid] = ""
                         return ""
-                    mod = Module.from_row(row)
+                    mod = self._module_from_row(row)
                     modules[mid] = mod
                 if mod.parent_id is None:
                     path = mod.name
@@ -523,7 +1187,7 @@ class Database:
 
         full_paths: List[str] = []
         for row in rows:
-            snippet_name = row[1]
+            snippet_name = self._display_name(row[1])
             module_id = row[2]
             if module_id is None:
                 full_paths.append(snippet_name)
@@ -556,7 +1220,7 @@ class Database:
         if not rows:
             return []
 
-        modules = {row[0]: Module.from_row(row) for row in rows}
+        modules = {row[0]: self._module_from_row(row) for row in rows}
         module_paths: dict[int, str] = {}
 
         def build_module_path(mid: int) -> str:
@@ -585,81 +1249,998 @@ class Database:
 
     def list_snippets(self, keyword: Optional[str] = None) -> List[str]:
         """
-        List all snippet full paths, optionally filtered by keyword
-        (case-insensitive match on the full path).
+        List all snippet full paths, pinned snippets first, optionally
+        filtered by keyword (case-insensitive match on the full path).
         """
         conn = self._get_connection()
         cursor = conn.cursor()
         
+        cursor.execute(
+            f"""
+            SELECT id, name, module_id, content_encrypted, is_shared, created_at, updated_at,
+                   pinned
+            FROM snippets
+            WHERE {LIVE_SQL}
+            ORDER BY pinned DESC, name
+            """
+        )
+        rows = cursor.fetchall()
+        conn.close()
+        paths = self._sorted_by_name(
+            self._build_snippet_full_path_rows(rows), [bool(row[7]) for row in rows]
+        )
         if keyword:
-            # Fetch all, then filter in Python on full path
+            # Filter in Python on full path
+            keyword_lower = keyword.lower()
+            return [p for p in paths if keyword_lower in p.lower()]
+        return paths
+    
+    def search_snippets(self, query: str) -> List[str]:
+        """
+        Fuzzy search for snippet full paths.
+        Returns list of full paths matching the query (case-insensitive).
+        """
+        all_paths = self.list_snippets()
+        query_lower = query.lower()
+        return [p for p in all_paths if query_lower in p.lower()]
+
+    def find_identical(self, content: str) -> List[str]:
+        """
+        Full paths, sorted, of snippets whose content is exactly `content`
+        (compared by content hash; nothing is decrypted). Protected snippets
+        never match.
+        """
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        content_hash = self._content_hash(cursor, content)
+        cursor.execute(
+            f"SELECT id, name, module_id FROM snippets WHERE content_hash = ? AND {LIVE_SQL}",
+            (content_hash,),
+        )
+        rows = cursor.fetchall()
+        # Keeps the content key if this was its first use
+        conn.commit()
+        conn.close()
+        return sorted(self._build_snippet_full_path_rows(rows))
+
+    def get_all_snippet_names(self) -> List[str]:
+        """Get all snippet full paths for fuzzy search."""
+        return self.list_snippets()
+
+    def find_name_variants(self, full_path: str) -> List[str]:
+        """
+        Full paths of the snippets whose name equals full_path ignoring case
+        and accents (see models.fold_name), sorted; an exact match is one of
+        them. Names are compared after decryption, so this works with
+        encrypted names too.
+        """
+        folded = fold_name(full_path)
+        return [name for name in self.get_all_snippet_names() if fold_name(name) == folded]
+
+    def get_all_snippet_contents(self) -> List[Tuple[str, str, str]]:
+        """
+        Return (full_path, decrypted content, kind) for every snippet, sorted by path.
+        Content is decrypted in memory only.
+        """
+        return list(self.iter_snippet_contents())
+
+    def iter_snippet_contents(self) -> Iterator[Tuple[str, str, str]]:
+        """
+        Yield (full_path, decrypted content, kind) for every snippet, sorted
+        by path. Each snippet is decrypted only when it is reached, so a
+        caller that drops it holds one plaintext at a time.
+        """
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        cursor.execute(
+            f"""
+            SELECT id, name, module_id, content_encrypted, kind
+            FROM snippets
+            WHERE {LIVE_SQL}
+            """
+        )
+        rows = cursor.fetchall()
+        conn.close()
+
+        paths = self._build_snippet_full_path_rows(rows)
+        for path, row in sorted(zip(paths, rows), key=lambda item: item[0]):
+            yield path, self._decrypt_content(row[3]), row[4] or "note"
+
+    def get_ranking_metadata(self) -> Dict[str, Tuple[datetime, int]]:
+        """Map each snippet full path to its (updated_at, use_count) for search ranking."""
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        cursor.execute(
+            f"SELECT name, module_id, updated_at, use_count FROM snippets WHERE {LIVE_SQL}"
+        )
+        rows = cursor.fetchall()
+        conn.close()
+
+        module_paths: Dict[int, str] = {}
+        metadata: Dict[str, Tuple[datetime, int]] = {}
+        for name, module_id, updated_at, use_count in rows:
+            name = self._display_name(name)
+            if module_id is None:
+                full_path = name
+            else:
+                if module_id not in module_paths:
+                    module_paths[module_id] = self._get_module_path_by_id(module_id)
+                module_path = module_paths[module_id]
+                full_path = f"{module_path}/{name}" if module_path else name
+            metadata[full_path] = (parse_timestamp(updated_at), use_count)
+        return metadata
+
+    def get_search_terms(
+        self,
+    ) -> Dict[str, Tuple[List[str], Optional[str], Optional[str]]]:
+        """
+        Map each snippet full path to its (tags, language, description), for
+        matching interactive search on more than the name.
+        """
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        cursor.execute(
+            f"SELECT id, name, module_id, language, description FROM snippets WHERE {LIVE_SQL}"
+        )
+        rows = cursor.fetchall()
+        tags_by_id = self._get_tags_for_ids(cursor, [row[0] for row in rows])
+        conn.close()
+
+        paths = self._build_snippet_full_path_rows([row[:3] for row in rows])
+        return {
+            path: (tags_by_id[row[0]], row[3], row[4])
+            for row, path in zip(rows, paths)
+        }
+
+    def record_snippet_use(self, full_path: str, track_access: bool = True) -> None:
+        """
+        Increment a snippet's use count (used to rank interactive search) and,
+        with track_access, store the time as its last access (see list_recent).
+        """
+        row = self._get_snippet_row_by_path(full_path)
+        if row is None:
+            return
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        if track_access:
+            cursor.execute(
+                "UPDATE snippets SET use_count = use_count + 1, last_accessed_at = ? WHERE id = ?",
+                (datetime.now().isoformat(timespec="seconds"), row[0]),
+            )
+        else:
             cursor.execute(
+                "UPDATE snippets SET use_count = use_count + 1 WHERE id = ?", (row[0],)
+            )
+        conn.commit()
+        conn.close()
+
+    def list_snippets_in_module(
+        self,
+        module: Optional[Module],
+        content_type: Optional[str] = None,
+        tags: Optional[List[str]] = None,
+    ) -> List[str]:
+        """
+        List snippet names directly under the given module (not including descendants),
+        optionally restricted to a detected content type and to snippets carrying all
+        of the given tags.
+        Returned values are full paths.
+        """
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        
+        if module is None:
+            query = f"""
+                SELECT id, name, module_id, content_encrypted, created_at, updated_at
+                FROM snippets
+                WHERE module_id IS NULL AND {LIVE_SQL}
                 """
-                SELECT id, name, module_id, content_encrypted, is_shared, created_at, updated_at
+            params: tuple = ()
+        else:
+            query = f"""
+                SELECT id, name, module_id, content_encrypted, created_at, updated_at
                 FROM snippets
-                ORDER BY name
+                WHERE module_id = ? AND {LIVE_SQL}
+                """
+            params = (module.id,)
+        filter_sql, filter_params = self._snippet_filter_sql(content_type, tags)
+        cursor.execute(query + filter_sql + " ORDER BY name", params + filter_params)
+        rows = cursor.fetchall()
+        conn.close()
+        return self._sorted_by_name(self._build_snippet_full_path_rows(rows))
+
+    def list_snippet_infos_in_module(
+        self,
+        module: Optional[Module],
+        content_type: Optional[str] = None,
+        tags: Optional[List[str]] = None,
+        sort: Optional[str] = None,
+        reverse: bool = False,
+        limit: Optional[int] = None,
+        min_lines: Optional[int] = None,
+        since: Optional[datetime] = None,
+        until: Optional[datetime] = None,
+        date_field: str = "updated",
+        team: Optional[str] = None,
+        offset: int = 0,
+    ) -> List[SnippetInfo]:
+        """
+        List metadata for snippets directly under the given module,
+        optionally restricted to a detected content type, tags, snippets
+        of at least `min_lines` lines (an unknown line count never matches)
+        and snippets shared with a team (by id or name).
+        `since` (inclusive) and `until` (exclusive) bound the "created" or
+        "updated" date, chosen by `date_field`.
+        Content is never decrypted.
+
+        Pinned snippets come first. Then `sort` is one of SORT_COLUMNS
+        (default "name"); names sort A-Z and dates newest first, and
+        `reverse` flips the order. `limit` caps the number of rows returned,
+        after skipping the first `offset` rows (for paging through large stores).
+        """
+        if sort is None:
+            sort = "name"
+        if sort not in SORT_COLUMNS:
+            raise ValueError(f"Unknown sort order: {sort}")
+        column, descending = SORT_COLUMNS[sort]
+        if reverse:
+            descending = not descending
+        # The column comes from the allowlist above, never from user input
+        order_sql = f" ORDER BY pinned DESC, {column} {'DESC' if descending else 'ASC'}, name"
+
+        conn = self._get_connection()
+        cursor = conn.cursor()
+
+        if module is None:
+            query = f"""
+                SELECT id, name, module_id, is_shared, created_at, updated_at, content_type, kind,
+                       language, expires_at, pinned, protected, byte_len, line_count,
+                       last_accessed_at, description, team_name
+                FROM snippets
+                WHERE module_id IS NULL AND {LIVE_SQL}
                 """
+            params: tuple = ()
+        else:
+            query = f"""
+                SELECT id, name, module_id, is_shared, created_at, updated_at, content_type, kind,
+                       language, expires_at, pinned, protected, byte_len, line_count,
+                       last_accessed_at, description, team_name
+                FROM snippets
+                WHERE module_id = ? AND {LIVE_SQL}
+                """
+            params = (module.id,)
+        filter_sql, filter_params = self._snippet_filter_sql(content_type, tags)
+        if team is not None:
+            filter_sql += f" AND {TEAM_MATCH_SQL}"
+            filter_params += (team, team)
+        if min_lines is not None:
+            filter_sql += " AND line_count >= ?"
+            filter_params += (min_lines,)
+        if since is not None or until is not None:
+            if date_field not in ("created", "updated"):
+                raise ValueError(f"Unknown date field: {date_field}")
+            # Stored as local ISO strings, which compare in date order
+            date_column = SORT_COLUMNS[date_field][0]
+            if since is not None:
+                filter_sql += f" AND {date_column} >= ?"
+                filter_params += (since.isoformat(),)
+            if until is not None:
+                filter_sql += f" AND {date_column} < ?"
+                filter_params += (until.isoformat(),)
+        params = params + filter_params
+        # Hashed names do not sort like the names, so then sort and limit below
+        if (limit is not None or offset) and self._name_key is None:
+            # SQLite only takes OFFSET after a LIMIT; -1 means no limit
+            order_sql += " LIMIT ? OFFSET ?"
+            params += (-1 if limit is None else limit, offset)
+        cursor.execute(query + filter_sql + order_sql, params)
+        rows = cursor.fetchall()
+        tags_by_id = self._get_tags_for_ids(cursor, [row[0] for row in rows])
+        conn.close()
+        paths = self._build_snippet_full_path_rows(rows)
+        infos = [SnippetInfo.from_row(row, path) for row, path in zip(rows, paths)]
+        for info in infos:
+            info.tags = tags_by_id[info.id]
+        if self._name_key is not None:
+            # The same order as order_sql; paths share the module, so they sort like names
+            attribute = "path" if column == "name" else column
+            infos.sort(key=lambda info: info.path)
+            infos.sort(key=lambda info: getattr(info, attribute), reverse=descending)
+            infos.sort(key=lambda info: not info.pinned)
+            infos = infos[offset:None if limit is None else offset + limit]
+        return infos
+
+    def list_recent(self, limit: Optional[int] = None) -> List[SnippetInfo]:
+        """
+        Metadata for snippets in any module that have been retrieved,
+        most recently accessed first. Content is never decrypted.
+        """
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        query = f"""
+            SELECT id, name, module_id, is_shared, created_at, updated_at, content_type, kind,
+                   language, expires_at, pinned, protected, byte_len, line_count,
+                   last_accessed_at, description, team_name
+            FROM snippets
+            WHERE last_accessed_at IS NOT NULL AND {LIVE_SQL}
+            ORDER BY last_accessed_at DESC, id DESC
+            """
+        params: tuple = ()
+        if limit is not None:
+            query += " LIMIT ?"
+            params = (limit,)
+        cursor.execute(query, params)
+        rows = cursor.fetchall()
+        tags_by_id = self._get_tags_for_ids(cursor, [row[0] for row in rows])
+        conn.close()
+        paths = self._build_snippet_full_path_rows(rows)
+        infos = [SnippetInfo.from_row(row, path) for row, path in zip(rows, paths)]
+        for info in infos:
+            info.tags = tags_by_id[info.id]
+        return infos
+
+    # ------------------------------------------------------------------
+    # Statistics
+    # ------------------------------------------------------------------
+
+    def get_stats(self) -> SnippetStats:
+        """
+        Compute aggregate metrics for the snippet store.
+        Uses aggregate queries only; no snippet content is decrypted.
+        """
+        conn = self._get_connection()
+        cursor = conn.cursor()
+
+        cursor.execute(
+            f"""
+            SELECT COUNT(*), COALESCE(SUM(LENGTH(content_encrypted)), 0),
+                   MIN(created_at), MAX(created_at), MAX(updated_at),
+                   COALESCE(SUM(byte_len), 0), COALESCE(SUM(line_count), 0),
+                   COUNT(*) - COUNT(byte_len)
+            FROM snippets
+            WHERE {LIVE_SQL}
+            """
+        )
+        (count, total_size, oldest, newest, last_updated,
+         content_bytes, content_lines, unsized) = cursor.fetchone()
+
+        cursor.execute("SELECT COUNT(*) FROM modules")
+        module_count = cursor.fetchone()[0]
+
+        cursor.execute(
+            f"""
+            SELECT module_id, COUNT(*)
+            FROM snippets
+            WHERE {LIVE_SQL}
+            GROUP BY module_id
+            """
+        )
+        module_rows = cursor.fetchall()
+
+        cursor.execute(
+            f"""
+            SELECT tag, COUNT(*) FROM snippet_tags
+            WHERE snippet_id IN (SELECT id FROM snippets WHERE {LIVE_SQL})
+            GROUP BY tag
+            ORDER BY COUNT(*) DESC, tag
+            """
+        )
+        by_tag = dict(cursor.fetchall())
+
+        cursor.execute(
+            f"""
+            SELECT COALESCE(language, ''), COUNT(*) FROM snippets
+            WHERE {LIVE_SQL}
+            GROUP BY COALESCE(language, '')
+            ORDER BY COUNT(*) DESC, 1
+            """
+        )
+        by_language = dict(cursor.fetchall())
+        conn.close()
+
+        by_module = {}
+        for module_id, module_snippets in module_rows:
+            if module_id is None:
+                label = "/"
+            else:
+                label = self._get_module_path_by_id(module_id) or "/"
+            by_module[label] = by_module.get(label, 0) + module_snippets
+
+        def parse(value: Optional[str]) -> Optional[datetime]:
+            return parse_timestamp(value) if value else None
+
+        return SnippetStats(
+            snippet_count=count,
+            module_count=module_count,
+            total_size=total_size,
+            oldest_created_at=parse(oldest),
+            newest_created_at=parse(newest),
+            last_updated_at=parse(last_updated),
+            by_module=dict(sorted(by_module.items())),
+            by_tag=by_tag,
+            by_language=by_language,
+            content_bytes=content_bytes,
+            content_lines=content_lines,
+            unsized_count=unsized,
+        )
+
+    # ------------------------------------------------------------------
+    # Attachments
+    # ------------------------------------------------------------------
+
+    def set_attachments(self, full_path: str, files: List[Tuple[str, bytes]]) -> None:
+        """
+        Attach (filename, data) files to a snippet, encrypted.
+        A file with the same name as an existing attachment replaces it.
+
+        Raises:
+            ValueError: If the snippet does not exist
+        """
+        row = self._get_snippet_row_by_path(full_path)
+        if row is None:
+            raise ValueError(f"Snippet '{full_path}' not found.")
+
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        now = datetime.now().isoformat()
+        for filename, data in files:
+            cursor.execute(
+                """
+                INSERT OR REPLACE INTO attachments
+                    (snippet_id, filename, data_encrypted, created_at)
+                VALUES (?, ?, ?, ?)
+                """,
+                (row[0], filename, self._encrypt_bytes(data), now),
             )
-            rows = cursor.fetchall()
+        conn.commit()
+        conn.close()
+
+    def get_attachments(self, full_path: str) -> List[Tuple[str, bytes]]:
+        """Return a snippet's decrypted (filename, data) attachments, by filename."""
+        row = self._get_snippet_row_by_path(full_path)
+        if row is None:
+            return []
+
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        cursor.execute(
+            """
+            SELECT filename, data_encrypted
+            FROM attachments
+            WHERE snippet_id = ?
+            ORDER BY filename
+            """,
+            (row[0],),
+        )
+        rows = cursor.fetchall()
+        conn.close()
+        return [(filename, self._decrypt_bytes(data)) for filename, data in rows]
+
+    def _delete_orphans(self, cursor: sqlite3.Cursor) -> None:
+        """Remove attachments, tags and versions whose snippet no longer exists."""
+        cursor.execute(
+            "DELETE FROM attachments WHERE snippet_id NOT IN (SELECT id FROM snippets)"
+        )
+        cursor.execute(
+            "DELETE FROM snippet_versions WHERE snippet_id NOT IN (SELECT id FROM snippets)"
+        )
+        cursor.execute(
+            "DELETE FROM snippet_tags WHERE snippet_id NOT IN (SELECT id FROM snippets)"
+        )
+
+    # ------------------------------------------------------------------
+    # Tags
+    # ------------------------------------------------------------------
+
+    @staticmethod
+    def normalize_tags(tags: List[str]) -> List[str]:
+        """Lowercase, strip and de-duplicate tags, dropping empty ones (order kept)."""
+        normalized: List[str] = []
+        for tag in tags:
+            tag = tag.strip().lower()
+            if tag and tag not in normalized:
+                normalized.append(tag)
+        return normalized
+
+    def _set_tags_with_cursor(
+        self, cursor: sqlite3.Cursor, snippet_id: int, tags: List[str]
+    ) -> None:
+        """Replace a snippet's tags using an existing cursor (caller commits)."""
+        cursor.execute("DELETE FROM snippet_tags WHERE snippet_id = ?", (snippet_id,))
+        cursor.executemany(
+            "INSERT INTO snippet_tags (snippet_id, tag) VALUES (?, ?)",
+            [(snippet_id, tag) for tag in self.normalize_tags(tags)],
+        )
+
+    def _get_tags_for_ids(
+        self, cursor: sqlite3.Cursor, snippet_ids: List[int]
+    ) -> Dict[int, List[str]]:
+        """Map each snippet id to its sorted tags."""
+        tags: Dict[int, List[str]] = {snippet_id: [] for snippet_id in snippet_ids}
+        if not snippet_ids:
+            return tags
+        placeholders = ",".join("?" for _ in snippet_ids)
+        cursor.execute(
+            f"""
+            SELECT snippet_id, tag FROM snippet_tags
+            WHERE snippet_id IN ({placeholders})
+            ORDER BY tag
+            """,
+            tuple(snippet_ids),
+        )
+        for snippet_id, tag in cursor.fetchall():
+            tags[snippet_id].append(tag)
+        return tags
+
+    def _snippet_filter_sql(
+        self, content_type: Optional[str], tags: Optional[List[str]]
+    ) -> Tuple[str, tuple]:
+        """Extra WHERE conditions for content type and tag filters (tags must all match)."""
+        sql = ""
+        params: tuple = ()
+        if content_type:
+            sql += " AND content_type = ?"
+            params += (content_type,)
+        for tag in self.normalize_tags(tags or []):
+            sql += " AND id IN (SELECT snippet_id FROM snippet_tags WHERE tag = ?)"
+            params += (tag,)
+        return sql, params
+
+    # ------------------------------------------------------------------
+    # Export / import
+    # ------------------------------------------------------------------
+
+    def get_export_records(self) -> List[dict]:
+        """
+        Return every snippet as a dict of metadata plus its still-encrypted
+        content and attachments (Fernet tokens under the master key).
+        Nothing is decrypted. Sorted by full path.
+        """
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        cursor.execute(
+            f"""
+            SELECT id, name, module_id, content_encrypted, content_type, kind,
+                   created_at, updated_at, language, description, content_type_explicit
+            FROM snippets
+            WHERE {LIVE_SQL}
+            """
+        )
+        rows = cursor.fetchall()
+        tags_by_id = self._get_tags_for_ids(cursor, [row[0] for row in rows])
+
+        cursor.execute(
+            "SELECT snippet_id, filename, data_encrypted FROM attachments ORDER BY filename"
+        )
+        attachments_by_id: Dict[int, List[Tuple[str, bytes]]] = {}
+        for snippet_id, filename, data in cursor.fetchall():
+            attachments_by_id.setdefault(snippet_id, []).append((filename, blob_to_token(data)))
+        conn.close()
+
+        paths = self._build_snippet_full_path_rows(rows)
+        records = []
+        for row, path in zip(rows, paths):
+            records.append({
+                "name": path,
+                "content_encrypted": blob_to_token(row[3]),
+                "content_type": row[4],
+                "content_type_explicit": bool(row[10]),
+                "kind": row[5] or "note",
+                "language": row[8],
+                "description": row[9],
+                "tags": tags_by_id[row[0]],
+                "created_at": row[6],
+                "updated_at": row[7],
+                "attachments": attachments_by_id.get(row[0], []),
+            })
+        return sorted(records, key=lambda record: record["name"])
+
+    def import_snippets(
+        self, records: List[dict], strategy: str = "skip", dry_run: bool = False
+    ) -> List[Tuple[str, str, Optional[str]]]:
+        """
+        Import decrypted archive records (see archive.read_archive_snippets)
+        in a single transaction, re-encrypting them with the local key and
+        keeping their created_at/updated_at.
+
+        strategy decides what happens when a name already exists:
+        'skip' leaves the local snippet, 'overwrite' replaces it (including
+        its attachments), 'rename' imports under the first free 'name-N'.
+
+        Returns one (outcome, final_name, error) per record, where outcome is
+        'imported', 'overwritten', 'renamed', 'skipped' or 'failed'.
+        With dry_run the outcomes are computed the same way and the
+        transaction is rolled back.
+        """
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        results: List[Tuple[str, str, Optional[str]]] = []
+
+        try:
+            cursor.execute("BEGIN IMMEDIATE")
+            for record in records:
+                name = record["name"]
+                cursor.execute("SAVEPOINT import_record")
+                try:
+                    outcome = "imported"
+                    if self._find_snippet_id_with_cursor(cursor, name) is not None:
+                        if strategy == "skip":
+                            outcome = "skipped"
+                        elif strategy == "rename":
+                            outcome = "renamed"
+                            suffix = 1
+                            while self._find_snippet_id_with_cursor(
+                                cursor, f"{record['name']}-{suffix}"
+                            ) is not None:
+                                suffix += 1
+                            name = f"{record['name']}-{suffix}"
+                        else:
+                            outcome = "overwritten"
+
+                    if outcome != "skipped":
+                        self._upsert_snippet(
+                            cursor, name, record["content"], None,
+                            record["kind"], record["tags"], record.get("language"),
+                            description=record.get("description") or "",
+                        )
+                        content_type = record["content_type"]
+                        explicit = record.get("content_type_explicit")
+                        if explicit is None:
+                            # Archives from before the flag: a type other than the
+                            # detected one was given
+                            explicit = not is_protected(record["content"]) and (
+                                content_type not in (None, detect_content_type(record["content"]))
+                            )
+                        snippet_id = self._find_snippet_id_with_cursor(cursor, name)
+                        cursor.execute(
+                            """
+                            UPDATE snippets SET created_at = ?, updated_at = ?,
+                                content_type = COALESCE(?, content_type),
+                                content_type_explicit = ?
+                            WHERE id = ?
+                            """,
+                            (record["created_at"], record["updated_at"], content_type,
+                             int(explicit), snippet_id),
+                        )
+                        cursor.execute(
+                            """
+                            UPDATE snippet_versions SET created_at = ?
+                            WHERE id = (SELECT MAX(id) FROM snippet_versions WHERE snippet_id = ?)
+                            """,
+                            (record["updated_at"], snippet_id),
+                        )
+                        cursor.execute(
+                            "DELETE FROM attachments WHERE snippet_id = ?", (snippet_id,)
+                        )
+                        for filename, data in record["attachments"]:
+                            cursor.execute(
+                                """
+                                INSERT INTO attachments
+                                    (snippet_id, filename, data_encrypted, created_at)
+                                VALUES (?, ?, ?, ?)
+                                """,
+                                (snippet_id, filename, self._encrypt_bytes(data),
+                                 record["created_at"]),
+                            )
+
+                    cursor.execute("RELEASE SAVEPOINT import_record")
+                    results.append((outcome, name, None))
+                except (self._sqlite.Error, ValueError) as e:
+                    cursor.execute("ROLLBACK TO SAVEPOINT import_record")
+                    cursor.execute("RELEASE SAVEPOINT import_record")
+                    results.append(("failed", name, str(e)))
+            if dry_run:
+                conn.rollback()
+            else:
+                conn.commit()
+        finally:
             conn.close()
-            paths = self._build_snippet_full_path_rows(rows)
-            keyword_lower = keyword.lower()
-            return [p for p in paths if keyword_lower in p.lower()]
+        return results
+
+    def _find_snippet_id_with_cursor(
+        self, cursor: sqlite3.Cursor, full_path: str
+    ) -> Optional[int]:
+        """Look up a snippet id by full path using an existing cursor."""
+        module_path, snippet_name = self._split_path(full_path)
+        if module_path:
+            module = self._resolve_module_path_with_cursor(cursor, module_path, create=False)
+            if module is None:
+                return None
+            cursor.execute(
+                f"SELECT id FROM snippets WHERE name = ? AND module_id = ? AND {LIVE_SQL}",
+                (self._stored_name(snippet_name), module.id),
+            )
         else:
             cursor.execute(
-                """
-                SELECT id, name, module_id, content_encrypted, is_shared, created_at, updated_at
-                FROM snippets
-                ORDER BY name
-                """
+                f"SELECT id FROM snippets WHERE name = ? AND module_id IS NULL AND {LIVE_SQL}",
+                (self._stored_name(snippet_name),),
             )
-            rows = cursor.fetchall()
+        row = cursor.fetchone()
+        return row[0] if row else None
+
+    # ------------------------------------------------------------------
+    # Key rotation
+    # ------------------------------------------------------------------
+
+    def count_encrypted_rows(self) -> Tuple[int, int]:
+        """Return the number of (snippets, attachments) encrypted with the master key."""
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        cursor.execute("SELECT COUNT(*) FROM snippets")
+        snippets = cursor.fetchone()[0]
+        cursor.execute("SELECT COUNT(*) FROM attachments")
+        attachments = cursor.fetchone()[0]
+        conn.close()
+        return snippets, attachments
+
+    def reencrypt_all(self, new_key: bytes) -> Tuple[int, int]:
+        """
+        Re-encrypt every snippet (and its versions), attachment and encrypted
+        name under the master key new_key in a single transaction, then switch this Database over to
+        it. Everything is decrypted before anything is written, so a bad row
+        aborts cleanly. An encrypted database file is rekeyed first and
+        rekeyed back if the rewrite fails.
+        Returns the number of (snippets, attachments) re-encrypted.
+        """
+        new_fernet = Fernet(new_key)
+        old_db_key = self._db_key
+        if old_db_key is not None:
+            self._rekey(database_key(new_key))
+        try:
+            rotated = self._reencrypt_rows(new_fernet)
+        except Exception:
+            if old_db_key is not None:
+                self._rekey(old_db_key)
+            raise
+        self.fernet = new_fernet
+        return rotated
+
+    def _rekey(self, new_db_key: str) -> None:
+        """Change the SQLCipher key of the database file (PRAGMA rekey)."""
+        conn = self._get_connection(isolation_level=None)
+        try:
+            conn.execute("PRAGMA wal_checkpoint(TRUNCATE)")
+            conn.execute(f"PRAGMA rekey = {new_db_key}")
+        finally:
+            conn.close()
+        self._db_key = new_db_key
+
+    def _reencrypt_rows(self, new_fernet: Fernet) -> Tuple[int, int]:
+        def reencrypt(data: bytes) -> bytes:
+            return token_to_blob(new_fernet.encrypt(self.fernet.decrypt(blob_to_token(data))))
+
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        try:
+            # Hold the write lock from the first read so no save slips in between
+            cursor.execute("BEGIN IMMEDIATE")
+            cursor.execute("SELECT id, content_encrypted FROM snippets")
+            snippet_rows = [
+                (reencrypt(data), snippet_id)
+                for snippet_id, data in cursor.fetchall()
+            ]
+            cursor.execute(
+                "SELECT id, conflict_content_encrypted FROM snippets "
+                "WHERE conflict_content_encrypted IS NOT NULL"
+            )
+            conflict_rows = [
+                (reencrypt(data), snippet_id)
+                for snippet_id, data in cursor.fetchall()
+            ]
+            cursor.execute("SELECT id, data_encrypted FROM attachments")
+            attachment_rows = [
+                (reencrypt(data), attachment_id)
+                for attachment_id, data in cursor.fetchall()
+            ]
+            cursor.execute("SELECT id, content_encrypted FROM snippet_versions")
+            version_rows = [
+                (reencrypt(data), version_id)
+                for version_id, data in cursor.fetchall()
+            ]
+            # Encrypted names and the meta keys (hashes stay valid as the keys are kept)
+            cursor.execute("SELECT key, value FROM meta")
+            meta_rows = [
+                (new_fernet.encrypt(self.fernet.decrypt(value.encode("ascii"))).decode("ascii"),
+                 key)
+                for key, value in cursor.fetchall()
+            ]
+            # Queued deletions and quarantined paths are token text when names
+            # are encrypted (see _queue_sync_deletions and quarantine_snippets)
+            path_rows: Dict[str, List[Tuple[str, int]]] = {}
+            if self._name_key is not None:
+                for table in ("sync_deletions", "quarantined_snippets"):
+                    cursor.execute(f"SELECT id, path FROM {table}")
+                    path_rows[table] = [
+                        (new_fernet.encrypt(self.fernet.decrypt(path.encode("ascii")))
+                         .decode("ascii"), row_id)
+                        for row_id, path in cursor.fetchall()
+                    ]
+            name_rows = {}
+            for table in ("modules", "snippets"):
+                cursor.execute(
+                    f"SELECT id, name_encrypted FROM {table} WHERE name_encrypted IS NOT NULL"
+                )
+                name_rows[table] = [
+                    (reencrypt(data), row_id)
+                    for row_id, data in cursor.fetchall()
+                ]
+
+            cursor.executemany(
+                "UPDATE snippets SET content_encrypted = ? WHERE id = ?", snippet_rows
+            )
+            cursor.executemany(
+                "UPDATE snippets SET conflict_content_encrypted = ? WHERE id = ?", conflict_rows
+            )
+            cursor.executemany(
+                "UPDATE attachments SET data_encrypted = ? WHERE id = ?", attachment_rows
+            )
+            cursor.executemany(
+                "UPDATE snippet_versions SET content_encrypted = ? WHERE id = ?", version_rows
+            )
+            for table, rows in name_rows.items():
+                cursor.executemany(f"UPDATE {table} SET name_encrypted = ? WHERE id = ?", rows)
+            for table, rows in path_rows.items():
+                cursor.executemany(f"UPDATE {table} SET path = ? WHERE id = ?", rows)
+            cursor.executemany("UPDATE meta SET value = ? WHERE key = ?", meta_rows)
+            conn.commit()
+        except Exception:
+            conn.rollback()
+            raise
+        finally:
+            conn.close()
+
+        return len(snippet_rows), len(attachment_rows)
+
+    # ------------------------------------------------------------------
+    # Maintenance
+    # ------------------------------------------------------------------
+
+    def _database_file_size(self) -> int:
+        """Size in bytes of the database file plus any WAL file beside it."""
+        size = 0
+        for path in (str(self.db_path), f"{self.db_path}-wal"):
+            if os.path.exists(path):
+                size += os.path.getsize(path)
+        return size
+
+    def vacuum(self) -> Tuple[int, int]:
+        """
+        Reclaim free pages left behind by deletes and overwrites.
+        Checkpoints and truncates the WAL (a no-op outside WAL mode), then
+        runs VACUUM. Returns the (before, after) file sizes in bytes.
+
+        Raises:
+            RuntimeError: If another process is using the database
+        """
+        before = self._database_file_size()
+
+        # VACUUM cannot run inside a transaction and needs exclusive access
+        conn = self._get_connection(isolation_level=None)
+        try:
+            conn.execute("PRAGMA wal_checkpoint(TRUNCATE)")
+            conn.execute("VACUUM")
+        except self._sqlite.OperationalError as e:
+            if "locked" in str(e) or "busy" in str(e):
+                raise RuntimeError(
+                    "The database is in use by another snip process; try again later."
+                ) from e
+            raise
+        finally:
             conn.close()
-            return self._build_snippet_full_path_rows(rows)
-    
-    def search_snippets(self, query: str) -> List[str]:
+
+        return before, self._database_file_size()
+
+    def backup_to(self, path) -> None:
         """
-        Fuzzy search for snippet full paths.
-        Returns list of full paths matching the query (case-insensitive).
+        Write a consistent copy of the whole database (WAL contents included)
+        to a new file at path, encrypted like the original.
         """
-        all_paths = self.list_snippets()
-        query_lower = query.lower()
-        return [p for p in all_paths if query_lower in p.lower()]
+        source = self._get_connection()
+        target = self._sqlite.connect(str(path))
+        try:
+            if self._db_key is not None:
+                target.execute(f"PRAGMA key = {self._db_key}")
+            source.backup(target)
+        finally:
+            target.close()
+            source.close()
+
+    def find_undecryptable(self) -> List[Tuple[int, str, str]]:
+        """
+        Try to decrypt every snippet, version and attachment (in memory only).
+        Returns (snippet_id, full_path, problem) for each failure, where
+        problem is 'content', 'version N' or 'attachment FILENAME'.
+        Expired snippets are checked too.
+        """
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        cursor.execute("SELECT id, name, module_id, content_encrypted FROM snippets ORDER BY id")
+        rows = cursor.fetchall()
+        cursor.execute(
+            "SELECT snippet_id, content_encrypted FROM snippet_versions ORDER BY snippet_id, id"
+        )
+        version_rows = cursor.fetchall()
+        cursor.execute(
+            "SELECT snippet_id, filename, data_encrypted FROM attachments ORDER BY filename"
+        )
+        attachment_rows = cursor.fetchall()
+        conn.close()
 
-    def get_all_snippet_names(self) -> List[str]:
-        """Get all snippet full paths for fuzzy search."""
-        return self.list_snippets()
+        paths = dict(zip((row[0] for row in rows), self._build_snippet_full_path_rows(rows)))
+
+        def readable(encrypted, decrypt) -> bool:
+            try:
+                decrypt(encrypted)
+                return True
+            except Exception:
+                return False
+
+        problems: List[Tuple[int, str, str]] = []
+        for snippet_id, _, _, encrypted in rows:
+            if not readable(encrypted, self._decrypt_content):
+                problems.append((snippet_id, paths[snippet_id], "content"))
+        numbers: Dict[int, int] = {}
+        for snippet_id, encrypted in version_rows:
+            numbers[snippet_id] = numbers.get(snippet_id, 0) + 1
+            if snippet_id in paths and not readable(encrypted, self._decrypt_content):
+                problems.append(
+                    (snippet_id, paths[snippet_id], f"version {numbers[snippet_id]}")
+                )
+        for snippet_id, filename, data in attachment_rows:
+            if snippet_id in paths and not readable(data, self._decrypt_bytes):
+                problems.append((snippet_id, paths[snippet_id], f"attachment {filename}"))
+        return problems
 
-    def list_snippets_in_module(self, module: Optional[Module]) -> List[str]:
+    def quarantine_snippets(self, snippet_ids: List[int]) -> int:
         """
-        List snippet names directly under the given module (not including descendants).
-        Returned values are full paths.
+        Move snippets into the quarantined_snippets table (keeping their
+        encrypted content and metadata) so they no longer break listing or
+        retrieval. Their history, tags and attachments are removed.
+        Returns the number of snippets moved.
         """
+        if not snippet_ids:
+            return 0
         conn = self._get_connection()
         cursor = conn.cursor()
-        
-        if module is None:
+        try:
+            cursor.execute("BEGIN IMMEDIATE")
+            placeholders = ",".join("?" for _ in snippet_ids)
             cursor.execute(
-                """
-                SELECT id, name, module_id, content_encrypted, created_at, updated_at
-                FROM snippets
-                WHERE module_id IS NULL
-                ORDER BY name
-                """
+                f"""
+                SELECT id, name, module_id, content_encrypted, content_type, kind,
+                       created_at, updated_at
+                FROM snippets WHERE id IN ({placeholders})
+                """,
+                tuple(snippet_ids),
             )
-        else:
+            rows = cursor.fetchall()
+            now = datetime.now().isoformat()
+            for row, path in zip(rows, self._build_snippet_full_path_rows(rows)):
+                if self._name_key is not None:
+                    # Keep the path as secret as the names it is made of
+                    path = blob_to_token(self._encrypt_content(path)).decode("ascii")
+                cursor.execute(
+                    """
+                    INSERT INTO quarantined_snippets (
+                        path, content_encrypted, content_type, kind,
+                        created_at, updated_at, quarantined_at
+                    )
+                    VALUES (?, ?, ?, ?, ?, ?, ?)
+                    """,
+                    (path, row[3], row[4], row[5], row[6], row[7], now),
+                )
             cursor.execute(
-                """
-                SELECT id, name, module_id, content_encrypted, created_at, updated_at
-                FROM snippets
-                WHERE module_id = ?
-            ORDER BY name
-                """,
-                (module.id,),
+                f"DELETE FROM snippets WHERE id IN ({placeholders})", tuple(snippet_ids)
             )
-        rows = cursor.fetchall()
-        conn.close()
-        return self._build_snippet_full_path_rows(rows)
+            moved = cursor.rowcount
+            self._delete_orphans(cursor)
+            conn.commit()
+        except Exception:
+            conn.rollback()
+            raise
+        finally:
+            conn.close()
+        return moved
 
     # ------------------------------------------------------------------
     # Module deletion
@@ -676,19 +2257,9 @@ class Database:
 
         conn = self._get_connection()
         cursor = conn.cursor()
+        cursor.execute("BEGIN IMMEDIATE")
 
-        # Collect all descendant module IDs (including the root)
-        to_visit = [module.id]
-        all_ids: List[int] = []
-
-        while to_visit:
-            mid = to_visit.pop()
-            all_ids.append(mid)
-            cursor.execute(
-                "SELECT id FROM modules WHERE parent_id = ?", (mid,)
-            )
-            children = [row[0] for row in cursor.fetchall()]
-            to_visit.extend(children)
+        all_ids = self._module_tree_ids_with_cursor(cursor, module.id)
 
         if not all_ids:
             conn.close()
@@ -697,6 +2268,10 @@ class Database:
         placeholders = ",".join("?" for _ in all_ids)
 
         # Delete snippets in any of these modules
+        cursor.execute(
+            f"SELECT id FROM snippets WHERE module_id IN ({placeholders})", tuple(all_ids)
+        )
+        self._queue_sync_deletions(cursor, [row[0] for row in cursor.fetchall()])
         cursor.execute(
             f"DELETE FROM snippets WHERE module_id IN ({placeholders})",
             tuple(all_ids),
@@ -706,6 +2281,7 @@ class Database:
             f"DELETE FROM modules WHERE id IN ({placeholders})",
             tuple(all_ids),
         )
+        self._delete_orphans(cursor)
 
         conn.commit()
         conn.close()
@@ -717,11 +2293,15 @@ class Database:
 
     def get_module_full_path(self, module: Module) -> str:
         """Compute the full hierarchical path for a module."""
+        return self._get_module_path_by_id(module.id)
+
+    def _get_module_path_by_id(self, module_id: int) -> str:
+        """Compute the full hierarchical path for a module id."""
         conn = self._get_connection()
         cursor = conn.cursor()
 
         parts: List[str] = []
-        current_id: Optional[int] = module.id
+        current_id: Optional[int] = module_id
 
         while current_id is not None:
             cursor.execute(
@@ -735,7 +2315,7 @@ class Database:
             row = cursor.fetchone()
             if row is None:
                 break
-            mod = Module.from_row(row)
+            mod = self._module_from_row(row)
             parts.append(mod.name)
             current_id = mod.parent_id
 
@@ -746,6 +2326,21 @@ class Database:
     # Rename operations
     # ------------------------------------------------------------------
 
+    def _module_tree_ids_with_cursor(self, cursor: sqlite3.Cursor, module_id: int) -> List[int]:
+        """IDs of a module and all its descendant modules."""
+        to_visit = [module_id]
+        all_ids: List[int] = []
+
+        while to_visit:
+            mid = to_visit.pop()
+            all_ids.append(mid)
+            cursor.execute(
+                "SELECT id FROM modules WHERE parent_id = ?", (mid,)
+            )
+            children = [row[0] for row in cursor.fetchall()]
+            to_visit.extend(children)
+        return all_ids
+
     def rename_module(self, old_path: str, new_path: str) -> bool:
         """
         Rename a module from old_path to new_path.
@@ -753,7 +2348,13 @@ class Database:
         - Just a new name (if staying in same parent): "new_name"
         - Full path: "parent/new_name" or "new_parent/new_name"
         Returns True if successful, False if module not found or new path conflicts.
+        Synced personal snippets in the module are queued for deletion on the
+        server under their old paths, and pushed again under the new ones.
+
+        Raises:
+            ValueError: If new_path is not a valid name (see validate_name)
         """
+        new_path = validate_name(new_path)
         old_module = self.get_module_by_path(old_path)
         if old_module is None:
             return False
@@ -785,7 +2386,7 @@ class Database:
                 SELECT id FROM modules
                 WHERE name = ? AND parent_id IS NULL AND id != ?
                 """,
-                (new_name, old_module.id),
+                (self._stored_name(new_name), old_module.id),
             )
         else:
             cursor.execute(
@@ -793,7 +2394,7 @@ class Database:
                 SELECT id FROM modules
                 WHERE name = ? AND parent_id = ? AND id != ?
                 """,
-                (new_name, new_parent_id, old_module.id),
+                (self._stored_name(new_name), new_parent_id, old_module.id),
             )
         
         if cursor.fetchone() is not None:
@@ -811,40 +2412,60 @@ class Database:
         now = datetime.now().isoformat()
 
         try:
+            cursor.execute("BEGIN IMMEDIATE")
+            tree_ids = self._module_tree_ids_with_cursor(cursor, old_module.id)
+            placeholders = ",".join("?" for _ in tree_ids)
+            cursor.execute(
+                f"SELECT id FROM snippets WHERE module_id IN ({placeholders})", tuple(tree_ids)
+            )
+            snippet_ids = [row[0] for row in cursor.fetchall()]
+            self._queue_sync_deletions(cursor, snippet_ids)
+            for snippet_id in snippet_ids:
+                self._mark_pending_with_cursor(cursor, snippet_id)
+
             # Update the module's name and parent
             cursor.execute(
                 """
                 UPDATE modules
-                SET name = ?, parent_id = ?, updated_at = ?
+                SET name = ?, name_encrypted = ?, parent_id = ?, updated_at = ?
                 WHERE id = ?
                 """,
-                (new_name, new_parent_id, now, old_module.id),
+                self._name_columns(new_name) + (new_parent_id, now, old_module.id),
             )
 
             conn.commit()
             conn.close()
             return True
-        except sqlite3.IntegrityError:
+        except self._sqlite.IntegrityError:
             # Name conflict at new location (same name already exists in that parent)
+            conn.rollback()
             conn.close()
             return False
 
     def rename_snippet(self, old_path: str, new_path: str) -> bool:
         """
         Rename (or move) a snippet from old_path to new_path.
+        Missing modules along new_path are created. created_at and the
+        snippet id (and so its attachments) are preserved. A synced personal
+        snippet is queued for deletion on the server under its old path.
         Returns True if successful, False if snippet not found or new path conflicts.
+
+        Raises:
+            ValueError: If new_path is not a valid name (see validate_name)
         """
+        new_path = validate_name(new_path)
         snippet = self.get_snippet(old_path)
         if snippet is None:
             return False
 
-        # Check if new path already exists
+        # Check if new path already exists (an expired snippet there is dropped)
+        self.prune_expired()
         if self.get_snippet(new_path) is not None:
             return False
 
         # Parse new path
         new_module_path, new_snippet_name = self._split_path(new_path)
-        new_module = self._resolve_module_path(new_module_path, create=False)
+        new_module = self._resolve_module_path(new_module_path, create=True)
         new_module_id = new_module.id if new_module is not None else None
 
         # Parse old path to get old module
@@ -857,24 +2478,95 @@ class Database:
         now = datetime.now().isoformat()
 
         try:
+            cursor.execute("BEGIN IMMEDIATE")
+            self._queue_sync_deletions(cursor, [snippet.id])
             # Update snippet name and module_id
             cursor.execute(
                 """
                 UPDATE snippets
-                SET name = ?, module_id = ?, updated_at = ?
+                SET name = ?, name_encrypted = ?, module_id = ?, updated_at = ?
                 WHERE id = ?
                 """,
-                (new_snippet_name, new_module_id, now, snippet.id),
+                self._name_columns(new_snippet_name) + (new_module_id, now, snippet.id),
             )
 
             conn.commit()
             conn.close()
             return True
-        except sqlite3.IntegrityError:
+        except self._sqlite.IntegrityError:
             # Name conflict at new location
+            conn.rollback()
             conn.close()
             return False
 
+    def copy_snippet(self, src_path: str, dst_path: str) -> bool:
+        """
+        Copy a snippet (content, type, kind, tags, description, expiry and
+        attachments) to dst_path.
+        The encrypted data is copied as is, without decrypting. Missing
+        modules along dst_path are created; the copy gets fresh timestamps.
+        Returns True if successful, False if src is not found or dst exists.
+
+        Raises:
+            ValueError: If dst_path is not a valid name (see validate_name)
+        """
+        dst_path = validate_name(dst_path)
+        dst_module_path, dst_name = self._split_path(dst_path)
+
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        try:
+            cursor.execute("BEGIN IMMEDIATE")
+            self._delete_expired_with_cursor(cursor)
+            src_id = self._find_snippet_id_with_cursor(cursor, src_path)
+            if src_id is None or self._find_snippet_id_with_cursor(cursor, dst_path) is not None:
+                return False
+
+            dst_module = self._resolve_module_path_with_cursor(
+                cursor, dst_module_path, create=True
+            )
+            now = datetime.now().isoformat()
+            cursor.execute(
+                """
+                INSERT INTO snippets (
+                    name, name_encrypted, module_id, entity_type,
+                    content_encrypted, is_shared, content_type, content_type_explicit,
+                    kind, language, expires_at, protected, byte_len, line_count,
+                    content_hash, description, created_at, updated_at
+                )
+                SELECT ?, ?, ?, 'snippet', content_encrypted, 0, content_type,
+                       content_type_explicit, kind, language, expires_at, protected,
+                       byte_len, line_count, content_hash, description, ?, ?
+                FROM snippets WHERE id = ?
+                """,
+                self._name_columns(dst_name)
+                + (dst_module.id if dst_module is not None else None, now, now, src_id),
+            )
+            dst_id = cursor.lastrowid
+            cursor.execute(
+                """
+                INSERT INTO snippet_versions (snippet_id, content_encrypted, created_at)
+                SELECT ?, content_encrypted, ? FROM snippets WHERE id = ?
+                """,
+                (dst_id, now, dst_id),
+            )
+            cursor.execute(
+                "INSERT INTO snippet_tags (snippet_id, tag) SELECT ?, tag FROM snippet_tags "
+                "WHERE snippet_id = ?",
+                (dst_id, src_id),
+            )
+            cursor.execute(
+                """
+                INSERT INTO attachments (snippet_id, filename, data_encrypted, created_at)
+                SELECT ?, filename, data_encrypted, ? FROM attachments WHERE snippet_id = ?
+                """,
+                (dst_id, now, src_id),
+            )
+            conn.commit()
+            return True
+        finally:
+            conn.close()
+
     # ------------------------------------------------------------------
     # Sharing operations
     # ------------------------------------------------------------------
@@ -892,6 +2584,8 @@ class Database:
         """
         module_path, snippet_name = self._split_path(full_path)
         module = self._resolve_module_path(module_path, create=False)
+        if module_path and module is None:
+            return False
         module_id = module.id if module is not None else None
 
         conn = self._get_connection()
@@ -904,7 +2598,7 @@ class Database:
                 SET is_shared = ?
                 WHERE name = ? AND module_id IS NULL
                 """,
-                (1 if is_shared else 0, snippet_name),
+                (1 if is_shared else 0, self._stored_name(snippet_name)),
             )
         else:
             cursor.execute(
@@ -913,7 +2607,7 @@ class Database:
                 SET is_shared = ?
                 WHERE name = ? AND module_id = ?
                 """,
-                (1 if is_shared else 0, snippet_name, module_id),
+                (1 if is_shared else 0, self._stored_name(snippet_name), module_id),
             )
 
         updated = cursor.rowcount > 0
@@ -921,6 +2615,59 @@ class Database:
         conn.close()
         return updated
 
+    def set_snippet_team(
+        self, full_path: str, team_id: Optional[str], team_name: Optional[str] = None
+    ) -> bool:
+        """
+        Scope a snippet to a team (shared, and pushed to that team's space by
+        snip sync push --team), or with team_id None make it personal again.
+        Either way it is pending: it has not been synced to its new space.
+        Returns False if the snippet is not found.
+        """
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        snippet_id = self._find_snippet_id_with_cursor(cursor, full_path)
+        if snippet_id is not None:
+            cursor.execute(
+                """
+                UPDATE snippets SET is_shared = ?, team_id = ?, team_name = ?,
+                    sync_status = NULL, synced_at = NULL, synced_hash = NULL,
+                    conflict_content_encrypted = NULL
+                WHERE id = ?
+                """,
+                (1 if team_id else 0, team_id, team_name if team_id else None, snippet_id),
+            )
+            conn.commit()
+        conn.close()
+        return snippet_id is not None
+
+    def set_snippet_pinned(self, full_path: str, pinned: bool) -> bool:
+        """
+        Pin a snippet (listed first by list and interactive search) or unpin it.
+        Returns False if the snippet is not found.
+        """
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        snippet_id = self._find_snippet_id_with_cursor(cursor, full_path)
+        if snippet_id is not None:
+            cursor.execute(
+                "UPDATE snippets SET pinned = ? WHERE id = ?", (1 if pinned else 0, snippet_id)
+            )
+            conn.commit()
+        conn.close()
+        return snippet_id is not None
+
+    def get_pinned_snippets(self) -> List[str]:
+        """Full paths of all pinned snippets, sorted."""
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        cursor.execute(
+            f"SELECT id, name, module_id FROM snippets WHERE pinned = 1 AND {LIVE_SQL}"
+        )
+        rows = cursor.fetchall()
+        conn.close()
+        return sorted(self._build_snippet_full_path_rows(rows))
+
     def get_shared_snippets(self) -> List[str]:
         """
         Get list of all shared snippet full paths.
@@ -932,16 +2679,44 @@ class Database:
         cursor = conn.cursor()
 
         cursor.execute(
-            """
+            f"""
             SELECT id, name, module_id, content_encrypted, is_shared, created_at, updated_at
             FROM snippets
-            WHERE is_shared = 1
+            WHERE is_shared = 1 AND {LIVE_SQL}
             ORDER BY name
             """
         )
         rows = cursor.fetchall()
         conn.close()
-        return self._build_snippet_full_path_rows(rows)
+        return self._sorted_by_name(self._build_snippet_full_path_rows(rows))
+
+    def get_team_snippets(self, team: Optional[str] = None) -> List[str]:
+        """
+        Full paths of the snippets scoped to a team (by id or name), or to
+        any team with team None, sorted.
+        """
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        query = f"SELECT id, name, module_id FROM snippets WHERE team_id IS NOT NULL AND {LIVE_SQL}"
+        params: tuple = ()
+        if team is not None:
+            query += f" AND {TEAM_MATCH_SQL}"
+            params = (team, team)
+        cursor.execute(query, params)
+        rows = cursor.fetchall()
+        conn.close()
+        return sorted(self._build_snippet_full_path_rows(rows))
+
+    def get_personal_snippets(self) -> List[str]:
+        """Full paths of the snippets not shared with any team, sorted."""
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        cursor.execute(
+            f"SELECT id, name, module_id FROM snippets WHERE is_shared = 0 AND {LIVE_SQL}"
+        )
+        rows = cursor.fetchall()
+        conn.close()
+        return sorted(self._build_snippet_full_path_rows(rows))
 
     def is_snippet_shared(self, full_path: str) -> bool:
         """
@@ -958,4 +2733,219 @@ class Database:
             return False
         return snippet.is_shared
 
+    # ------------------------------------------------------------------
+    # Sync state
+    # ------------------------------------------------------------------
+
+    def get_sync_states(
+        self, shared: Optional[bool] = None, team: Optional[str] = None
+    ) -> Dict[str, Tuple[str, Optional[datetime], datetime]]:
+        """
+        Map each snippet full path to its (status, synced_at, updated_at),
+        where status is one of SYNC_STATUSES. With shared, only shared
+        (True) or personal (False) snippets are included; with team, only
+        those scoped to that team (by id or name).
+        """
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        query = f"""
+            SELECT id, name, module_id, {SYNC_STATUS_SQL}, synced_at, updated_at
+            FROM snippets
+            WHERE {LIVE_SQL}
+            """
+        params: tuple = ()
+        if shared is not None:
+            query += " AND is_shared = ?"
+            params = (int(shared),)
+        if team is not None:
+            query += f" AND {TEAM_MATCH_SQL}"
+            params += (team, team)
+        cursor.execute(query, params)
+        rows = cursor.fetchall()
+        conn.close()
+
+        paths = self._build_snippet_full_path_rows([row[:3] for row in rows])
+        return {
+            path: (row[3], parse_timestamp(row[4]) if row[4] else None, parse_timestamp(row[5]))
+            for row, path in zip(rows, paths)
+        }
+
+    def mark_pending(self, full_path: str) -> bool:
+        """
+        Record that a snippet changed since its last sync, so the next push
+        sends it; a snippet in conflict stays so. Saving and editing a
+        snippet do this. Returns False if not found.
+        """
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        snippet_id = self._find_snippet_id_with_cursor(cursor, full_path)
+        if snippet_id is not None:
+            self._mark_pending_with_cursor(cursor, snippet_id)
+            conn.commit()
+        conn.close()
+        return snippet_id is not None
+
+    def _mark_pending_with_cursor(self, cursor: sqlite3.Cursor, snippet_id: int) -> None:
+        cursor.execute(
+            "UPDATE snippets SET sync_status = NULL WHERE id = ? AND sync_status = 'synced'",
+            (snippet_id,),
+        )
+
+    def list_pending(
+        self, shared: Optional[bool] = None, team: Optional[str] = None
+    ) -> List[str]:
+        """
+        Full paths of the snippets the next push would send (pending, see
+        SYNC_STATUS_SQL), sorted; shared and team select as in get_sync_states.
+        """
+        states = self.get_sync_states(shared=shared, team=team)
+        return sorted(path for path, (status, _, _) in states.items() if status == "pending")
+
+    def _queue_sync_deletions(self, cursor: sqlite3.Cursor, snippet_ids: List[int]) -> None:
+        """
+        Before deleting snippets (caller deletes and commits), queue the
+        synced personal ones for deletion on the server. Team snippets are
+        only removed from a team space by snip unshare.
+        """
+        if not snippet_ids:
+            return
+        placeholders = ",".join("?" for _ in snippet_ids)
+        cursor.execute(
+            f"""
+            SELECT id, name, module_id FROM snippets
+            WHERE id IN ({placeholders}) AND synced_at IS NOT NULL AND team_id IS NULL
+            """,
+            tuple(snippet_ids),
+        )
+        rows = cursor.fetchall()
+        now = datetime.now().isoformat()
+        for path in self._build_snippet_full_path_rows(rows):
+            if self._name_key is not None:
+                # Encrypted names stay encrypted here too, as Fernet token text
+                path = self.fernet.encrypt(path.encode("utf-8")).decode("ascii")
+            cursor.execute(
+                "INSERT INTO sync_deletions (path, deleted_at) VALUES (?, ?)", (path, now)
+            )
+
+    def get_sync_deletions(self) -> List[Tuple[int, str]]:
+        """
+        (id, full path) of each deletion queued for the server, oldest first;
+        clear each with clear_sync_deletion once the server has deleted it.
+        """
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        cursor.execute("SELECT id, path FROM sync_deletions ORDER BY id")
+        rows = cursor.fetchall()
+        conn.close()
+        if self._name_key is not None:
+            rows = [
+                (deletion_id, self.fernet.decrypt(path.encode("ascii")).decode("utf-8"))
+                for deletion_id, path in rows
+            ]
+        return rows
+
+    def clear_sync_deletion(self, deletion_id: int) -> None:
+        """Drop a queued server deletion (done, or overtaken by a new snippet there)."""
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        cursor.execute("DELETE FROM sync_deletions WHERE id = ?", (deletion_id,))
+        conn.commit()
+        conn.close()
+
+    def mark_synced(self, full_path: str, content: str) -> bool:
+        """
+        Record that `content` (the snippet's current content) now matches the
+        server: the snippet is synced as of now (no longer in conflict), and
+        a keyed hash of the content is kept for is_last_synced. Returns False
+        if not found.
+        """
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        snippet_id = self._find_snippet_id_with_cursor(cursor, full_path)
+        if snippet_id is not None:
+            cursor.execute(
+                """
+                UPDATE snippets SET sync_status = 'synced', synced_at = ?, synced_hash = ?,
+                    conflict_content_encrypted = NULL
+                WHERE id = ?
+                """,
+                (datetime.now().isoformat(), self._keyed_hash(cursor, content), snippet_id),
+            )
+            conn.commit()
+        conn.close()
+        return snippet_id is not None
+
+    def set_sync_conflict(
+        self, full_path: str, conflict: bool, remote_content: Optional[str] = None
+    ) -> bool:
+        """
+        Mark a snippet as in conflict with the server (skipped by push),
+        keeping the server's `remote_content` encrypted for snip resolve; or
+        clear that so the snippet is pending and pushed again. When clearing,
+        a given remote_content is recorded as the server's last synced
+        content, so the next pull does not see it as a new change.
+        Returns False if the snippet is not found.
+        """
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        snippet_id = self._find_snippet_id_with_cursor(cursor, full_path)
+        if snippet_id is not None:
+            if conflict:
+                cursor.execute(
+                    """
+                    UPDATE snippets SET sync_status = 'conflict', conflict_content_encrypted = ?
+                    WHERE id = ?
+                    """,
+                    (self._encrypt_content(remote_content) if remote_content is not None
+                     else None, snippet_id),
+                )
+            else:
+                cursor.execute(
+                    """
+                    UPDATE snippets SET sync_status = NULL, conflict_content_encrypted = NULL,
+                        synced_hash = COALESCE(?, synced_hash)
+                    WHERE id = ?
+                    """,
+                    (self._keyed_hash(cursor, remote_content) if remote_content is not None
+                     else None, snippet_id),
+                )
+            conn.commit()
+        conn.close()
+        return snippet_id is not None
+
+    def get_sync_conflict(self, full_path: str) -> Optional[str]:
+        """
+        The server's content kept for a snippet in conflict (decrypted), or
+        None if the snippet is not in conflict or none was kept.
+        """
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        snippet_id = self._find_snippet_id_with_cursor(cursor, full_path)
+        row = None
+        if snippet_id is not None:
+            cursor.execute(
+                "SELECT conflict_content_encrypted FROM snippets "
+                "WHERE id = ? AND sync_status = 'conflict'",
+                (snippet_id,),
+            )
+            row = cursor.fetchone()
+        conn.close()
+        if row is None or row[0] is None:
+            return None
+        return self._decrypt_content(row[0])
 
+    def is_last_synced(self, full_path: str, content: str) -> bool:
+        """True if `content` is what the snippet held when it was last synced."""
+        conn = self._get_connection()
+        cursor = conn.cursor()
+        snippet_id = self._find_snippet_id_with_cursor(cursor, full_path)
+        synced_hash = None
+        if snippet_id is not None:
+            cursor.execute("SELECT synced_hash FROM snippets WHERE id = ?", (snippet_id,))
+            synced_hash = cursor.fetchone()[0]
+        matches = synced_hash is not None and hmac.compare_digest(
+            synced_hash, self._keyed_hash(cursor, content)
+        )
+        conn.commit()
+        conn.close()
+        return matches
@@ -0,0 +1,127 @@
+"""Heuristic content-type and language detection for snippets."""
+
+import json
+import re
+from typing import Optional
+
+
+CONTENT_TYPES = ("json", "yaml", "shell", "sql", "url", "base64", "binary", "plaintext")
+
+SQL_PATTERN = re.compile(
+    r"^\s*(SELECT|INSERT|UPDATE|DELETE|CREATE|ALTER|DROP|WITH|TRUNCATE)\b",
+    re.IGNORECASE,
+)
+URL_PATTERN = re.compile(r"^[a-zA-Z][a-zA-Z0-9+.-]*://\S+$")
+YAML_LINE_PATTERN = re.compile(r"^\s*(- )?[\w.-]+:(\s|$)")
+BASE64_PATTERN = re.compile(r"^[A-Za-z0-9+/_-]+={0,2}$")
+SHELL_COMMANDS = {
+    "apt", "brew", "cat", "cd", "chmod", "chown", "cp", "curl", "docker",
+    "echo", "export", "find", "git", "grep", "helm", "kubectl", "ls", "make",
+    "mkdir", "mv", "npm", "npx", "pip", "pip3", "python", "python3", "rm",
+    "rsync", "scp", "sed", "ssh", "sudo", "tar", "terraform", "wget", "yarn",
+}
+SHELL_OPERATORS = ("&&", "||", "$(", " | ", " > ", " >> ")
+
+
+def detect_content_type(content: str) -> str:
+    """
+    Guess a coarse content type for snippet content.
+    Returns one of CONTENT_TYPES; falls back to 'plaintext'.
+    """
+    text = content.strip()
+    if not text:
+        return "plaintext"
+
+    # Control characters (other than common whitespace) suggest binary-ish data
+    if any(ord(ch) < 32 and ch not in "\t\n\r" for ch in text):
+        return "binary"
+
+    lines = text.splitlines()
+
+    if len(lines) == 1 and URL_PATTERN.match(text):
+        return "url"
+
+    if text[0] in "{[":
+        try:
+            json.loads(text)
+            return "json"
+        except ValueError:
+            pass
+
+    if SQL_PATTERN.match(text):
+        return "sql"
+
+    if lines[0].startswith("#!") and re.search(r"\b(ba|z|fi|k)?sh\b", lines[0]):
+        return "shell"
+
+    if text.startswith("---") or (
+        len(lines) >= 2 and all(YAML_LINE_PATTERN.match(line) or not line.strip()
+                                or line.lstrip().startswith("#") for line in lines)
+    ):
+        return "yaml"
+
+    if len(lines) == 1 and len(text) >= 24 and len(text) % 4 == 0 and BASE64_PATTERN.match(text):
+        return "base64"
+
+    first_word = lines[0].split()[0]
+    if first_word in SHELL_COMMANDS or any(op in text for op in SHELL_OPERATORS):
+        return "shell"
+
+    return "plaintext"
+
+
+# Interpreters named in a shebang line, mapped to a language
+SHEBANG_LANGUAGES = {
+    "sh": "bash", "bash": "bash", "zsh": "zsh", "fish": "fish", "python": "python",
+    "node": "javascript", "deno": "typescript", "ruby": "ruby", "perl": "perl", "php": "php",
+}
+# Checked in order; the first language with a matching line wins
+LANGUAGE_PATTERNS = (
+    ("rust", re.compile(r"^\s*(pub )?(fn \w+|impl\b|use \w+::|let mut \w+|#\[derive)", re.M)),
+    ("go", re.compile(r"^\s*(package \w+\s*$|func (\(\w+ \*?\w+\) )?\w+\()", re.M)),
+    ("python", re.compile(r"^\s*(def \w+\(|class \w+[:(]|from [\w.]+ import |import [\w.]+\s*$)", re.M)),
+    ("javascript", re.compile(r"^\s*(const \w+ = |function \w+\(|module\.exports|export (default|const|function) )", re.M)),
+)
+# Content types that already name a language
+CONTENT_TYPE_LANGUAGES = {"shell": "bash", "sql": "sql", "json": "json", "yaml": "yaml"}
+# File extensions mapped to a language (for files imported as snippets)
+EXTENSION_LANGUAGES = {
+    ".sh": "bash", ".bash": "bash", ".zsh": "zsh", ".fish": "fish", ".sql": "sql",
+    ".py": "python", ".rs": "rust", ".go": "go", ".js": "javascript", ".mjs": "javascript",
+    ".ts": "typescript", ".rb": "ruby", ".pl": "perl", ".php": "php", ".json": "json",
+    ".yaml": "yaml", ".yml": "yaml", ".toml": "toml", ".md": "markdown",
+}
+
+
+def language_from_filename(filename: str) -> Optional[str]:
+    """Language implied by a file's extension, or None if it is not known."""
+    _, dot, extension = filename.rpartition(".")
+    if not dot:
+        return None
+    return EXTENSION_LANGUAGES.get(f".{extension.lower()}")
+
+
+def detect_language(content: str) -> Optional[str]:
+    """
+    Guess the programming language of snippet content from its shebang line
+    or common keywords. Returns None if nothing matches.
+    """
+    text = content.strip()
+    if not text:
+        return None
+
+    first_line = text.splitlines()[0]
+    if first_line.startswith("#!"):
+        words = [word for word in first_line[2:].split() if not word.startswith("-")]
+        if words and words[0].rsplit("/", 1)[-1] == "env":
+            words = words[1:]
+        if words:
+            interpreter = re.sub(r"[\d.]+$", "", words[0].rsplit("/", 1)[-1])
+            if interpreter in SHEBANG_LANGUAGES:
+                return SHEBANG_LANGUAGES[interpreter]
+
+    for language, pattern in LANGUAGE_PATTERNS:
+        if pattern.search(text):
+            return language
+
+    return CONTENT_TYPE_LANGUAGES.get(detect_content_type(content))
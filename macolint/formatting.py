@@ -0,0 +1,168 @@
+"""Output formatting helpers for Macolint listings."""
+
+import re
+from datetime import datetime
+from typing import List, Optional, Tuple
+
+from macolint.crypto import is_protected
+from macolint.models import SnippetInfo
+
+
+FORMAT_TOKEN_PATTERN = re.compile(r"\{(\w+)\}")
+
+# Timestamp styles for the time_format setting (see format_timestamp)
+TIME_FORMATS = ("local", "iso", "relative")
+RELATIVE_UNITS = (
+    ("year", 365 * 86400),
+    ("month", 30 * 86400),
+    ("week", 7 * 86400),
+    ("day", 86400),
+    ("hour", 3600),
+    ("minute", 60),
+)
+
+
+def relative_time(value: datetime, now: Optional[datetime] = None) -> str:
+    """Describe a local timestamp relative to now, e.g. '3 hours ago' or 'in 2 days'."""
+    seconds = int(((now or datetime.now()) - value).total_seconds())
+    if abs(seconds) < 60:
+        return "just now"
+    for unit, size in RELATIVE_UNITS:
+        if abs(seconds) >= size:
+            count = abs(seconds) // size
+            text = f"{count} {unit}{'' if count == 1 else 's'}"
+            return f"{text} ago" if seconds > 0 else f"in {text}"
+    return "just now"
+
+
+def format_timestamp(value: datetime, style: str = "local") -> str:
+    """
+    Render a local timestamp in one of TIME_FORMATS: 'local' (2024-05-01 14:30),
+    'iso' (2024-05-01T14:30:00+02:00) or 'relative' (3 hours ago).
+    """
+    if style == "iso":
+        return value.astimezone().isoformat(timespec="seconds")
+    if style == "relative":
+        return relative_time(value)
+    return value.strftime("%Y-%m-%d %H:%M")
+
+
+def format_snippet_line(template: str, info: SnippetInfo, time_format: str = "local") -> str:
+    """
+    Render a user-supplied format string for a snippet.
+
+    Recognized tokens: {name}, {id}, {created}, {updated}, {tags}, {type}, {kind}, {lang},
+    {bytes}, {lines}, {desc}. Unknown tokens are left in the output literally; an
+    unknown size is empty. Dates are rendered with format_timestamp.
+    """
+    values = {
+        "name": info.path,
+        "id": str(info.id),
+        "created": format_timestamp(info.created_at, time_format),
+        "updated": format_timestamp(info.updated_at, time_format),
+        "tags": ",".join(info.tags),
+        "type": info.content_type or "",
+        "kind": info.kind,
+        "lang": info.language or "",
+        "bytes": "" if info.byte_len is None else str(info.byte_len),
+        "lines": "" if info.line_count is None else str(info.line_count),
+        "desc": info.description or "",
+    }
+
+    def replace(match: "re.Match") -> str:
+        token = match.group(1)
+        return values.get(token, match.group(0))
+
+    return FORMAT_TOKEN_PATTERN.sub(replace, template)
+
+
+def snippet_info_to_dict(info: SnippetInfo) -> dict:
+    """
+    Serialize snippet metadata for machine-readable output.
+    Only metadata is included; SnippetInfo never carries content.
+    """
+    return {
+        "name": info.path,
+        "type": info.content_type,
+        "kind": info.kind,
+        "language": info.language,
+        "tags": info.tags,
+        "description": info.description,
+        "shared": info.is_shared,
+        "team": info.team,
+        "pinned": info.pinned,
+        "protected": info.protected,
+        "bytes": info.byte_len,
+        "lines": info.line_count,
+        "created_at": info.created_at.isoformat(),
+        "updated_at": info.updated_at.isoformat(),
+        "expires_at": info.expires_at.isoformat() if info.expires_at else None,
+        "last_accessed_at": (
+            info.last_accessed_at.isoformat() if info.last_accessed_at else None
+        ),
+    }
+
+
+# Shown in place of content that must not appear on screen: by show,
+# pickers, the browse pane and fzf previews (snip get still copies it)
+SECRET_MASK = "•••••• (hidden, use get to copy)"
+PROTECTED_MASK = "•••••• (protected, use get and enter its passphrase)"
+
+
+def content_mask(content: str, kind: str) -> Optional[str]:
+    """
+    The placeholder to display instead of a snippet's content (SECRET_MASK
+    or PROTECTED_MASK), or None if the content may be shown.
+    """
+    if kind == "secret":
+        return SECRET_MASK
+    if is_protected(content):
+        return PROTECTED_MASK
+    return None
+
+
+def preview_text(content: str, kind: str, max_lines: int = 3, width: int = 60) -> str:
+    """
+    One-line preview of a snippet's first lines, for pickers.
+    Secrets and protected snippets are masked (see content_mask).
+    """
+    mask = content_mask(content, kind)
+    if mask is not None:
+        return mask
+    lines = [line.strip() for line in content.splitlines() if line.strip()]
+    preview = " ⏎ ".join(lines[:max_lines])
+    if len(lines) > max_lines or len(preview) > width:
+        preview = preview[:width - 1].rstrip() + "…"
+    return preview
+
+
+def tree_lines(module_paths: List[str], snippet_paths: List[str]) -> List[Tuple[str, str]]:
+    """
+    Lay out '/'-separated module and snippet paths as an indented tree.
+    Returns (indent, label) pairs in display order; module labels end with
+    '/'. Entries at each level are sorted by name.
+    """
+    tree: dict = {}
+    for path in module_paths:
+        node = tree
+        for part in path.split("/"):
+            node = node.setdefault(part + "/", {})
+    for path in snippet_paths:
+        *parents, name = path.split("/")
+        node = tree
+        for part in parents:
+            node = node.setdefault(part + "/", {})
+        node.setdefault(name, None)
+
+    lines: List[Tuple[str, str]] = []
+
+    def walk(node: dict, indent: str):
+        labels = sorted(node, key=lambda label: label.rstrip("/"))
+        for idx, label in enumerate(labels):
+            last = idx == len(labels) - 1
+            lines.append((indent + ("└── " if last else "├── "), label))
+            if node[label]:
+                walk(node[label], indent + ("    " if last else "│   "))
+
+    walk(tree, "")
+    return lines
@@ -1,21 +1,31 @@
 """Interactive terminal UI utilities for Macolint."""
 
 import sys
-from typing import Optional, List
+from datetime import datetime
+from typing import Callable, Optional, List, Dict, Set, Tuple
 from prompt_toolkit import PromptSession
-from prompt_toolkit.completion import FuzzyCompleter, WordCompleter
+from prompt_toolkit.completion import Completer, Completion, FuzzyCompleter, WordCompleter
 from prompt_toolkit.key_binding import KeyBindings
 from prompt_toolkit.keys import Keys
 from prompt_toolkit.formatted_text import FormattedText
 from prompt_toolkit.shortcuts import prompt
 from rich.console import Console
+from rich.markup import escape
 from rich.table import Table
 from macolint.database import Database
+from macolint.ranking import DEFAULT_AUTO_SELECT_GAP, is_clear_winner, score_candidates
 
 
 console = Console()
 
 
+class NoSelectionError(Exception):
+    """Raised when an interactive prompt hits end of input (Ctrl+D or empty stdin)."""
+
+    def __init__(self, message: str = "No selection made (end of input)."):
+        super().__init__(message)
+
+
 def fuzzy_match(query: str, candidates: List[str]) -> List[str]:
     """Simple fuzzy matching algorithm."""
     if not query:
@@ -38,6 +48,91 @@ def fuzzy_match(query: str, candidates: List[str]) -> List[str]:
     return matches
 
 
+def annotated_name(
+    name: str, tags: List[str], language: Optional[str], description: Optional[str] = None
+) -> str:
+    """'name  [tag1, tag2]  (lang)  - description' for display, leaving out what is missing."""
+    label = name
+    if tags:
+        label += f"  [{', '.join(tags)}]"
+    if language:
+        label += f"  ({language})"
+    if description:
+        label += f"  - {description}"
+    return label
+
+
+def search_terms(
+    search: str, tags: List[str], language: Optional[str], description: Optional[str]
+) -> List[str]:
+    """The terms matched besides the name for a `search` (one of SEARCH_FIELDS)."""
+    terms: List[str] = []
+    if search in ("tags", "all"):
+        terms += tags + ([language] if language else [])
+    if search in ("description", "all") and description:
+        terms.append(description)
+    return terms
+
+
+class RankedCompleter(Completer):
+    """
+    Fuzzy completer that orders matches by match quality, recency and usage,
+    with pinned snippets first.
+    With a preview function, each match shows its result beside it; it is
+    only called for the matches actually displayed.
+    With any `search` but "name", `terms` (name -> (tags, language,
+    description)) are matched as well, as `search` picks (see search_terms),
+    and shown beside each name; completing still inserts the bare name.
+    """
+
+    def __init__(
+        self,
+        snippet_names: List[str],
+        metadata: Dict[str, Tuple[datetime, int]],
+        weights: Dict[str, float],
+        preview: Optional[Callable[[str], str]] = None,
+        pinned: Optional[Set[str]] = None,
+        terms: Optional[Dict[str, Tuple[List[str], Optional[str], Optional[str]]]] = None,
+        search: str = "name",
+    ):
+        self.snippet_names = snippet_names
+        self.metadata = metadata
+        self.weights = weights
+        self.preview = preview
+        self.pinned = pinned or set()
+        self.terms = terms or {}
+        self.search = search
+        self._term_lists = {
+            name: search_terms(search, *name_terms) for name, name_terms in self.terms.items()
+        }
+
+    def label(self, name: str) -> str:
+        """How `name` is displayed among the suggestions."""
+        if self.search == "name" or name not in self.terms:
+            return name
+        return annotated_name(name, *self.terms[name])
+
+    def score(self, query: str) -> List[Tuple[float, str]]:
+        return score_candidates(
+            query, self.snippet_names, self.metadata, self.weights, pinned=self.pinned,
+            terms=self._term_lists, search=self.search,
+        )
+
+    def rank(self, query: str) -> List[str]:
+        return [name for _, name in self.score(query)]
+
+    def get_completions(self, document, complete_event):
+        text = document.text_before_cursor
+        for name in self.rank(text.strip()):
+            display_meta = None
+            if self.preview is not None:
+                display_meta = lambda name=name: self.preview(name)
+            yield Completion(
+                name, start_position=-len(text), display=self.label(name),
+                display_meta=display_meta,
+            )
+
+
 def display_snippet_suggestions(query: str, matches: List[str], max_display: int = 10):
     """Display snippet suggestions in a formatted list."""
     if not matches:
@@ -125,10 +220,11 @@ def prompt_snippet_name(snippet_names: List[str]) -> Optional[str]:
         return None
 
 
-def prompt_snippet_content(existing_content: Optional[str] = None) -> Optional[str]:
+def prompt_snippet_content(existing_content: Optional[str] = None, strip: bool = True) -> Optional[str]:
     """
     Interactive prompt for entering snippet content.
-    Returns the content or None if cancelled.
+    Returns the content (with surrounding whitespace removed unless strip
+    is False) or None if cancelled.
     Supports multi-line input. Press Ctrl+D to finish, Esc to cancel.
     """
     if existing_content:
@@ -165,24 +261,120 @@ def prompt_snippet_content(existing_content: Optional[str] = None) -> Optional[s
         if content is None:
             return None
         
-        return content.strip()
+        return content.strip() if strip else content
         
     except (KeyboardInterrupt, EOFError):
         # Esc or Ctrl+C pressed
         return None
 
 
-def prompt_snippet_name_simple(snippet_names: List[str]) -> Optional[str]:
+class EditorError(Exception):
+    """Raised when the external editor cannot be run or exits with an error."""
+
+
+def edit_in_external_editor(content: str) -> str:
+    """
+    Open content in $VISUAL/$EDITOR (falling back to vi, or notepad on Windows)
+    and return the edited text.
+    The temporary file is only readable by the user and is always removed.
+
+    Raises:
+        EditorError: If the editor cannot be started or exits non-zero
+    """
+    import os
+    import shlex
+    import subprocess
+    import tempfile
+
+    editor = os.environ.get("VISUAL") or os.environ.get("EDITOR")
+    if not editor:
+        editor = "notepad" if sys.platform.startswith("win") else "vi"
+
+    # mkstemp creates the file with 0600 permissions
+    fd, path = tempfile.mkstemp(prefix="snip-", suffix=".txt")
+    try:
+        with os.fdopen(fd, "w", encoding="utf-8") as f:
+            f.write(content)
+
+        try:
+            result = subprocess.run(shlex.split(editor) + [path])
+        except OSError as e:
+            raise EditorError(f"Could not start editor '{editor}': {e}.")
+        if result.returncode != 0:
+            raise EditorError(f"Editor '{editor}' exited with status {result.returncode}.")
+
+        with open(path, "r", encoding="utf-8") as f:
+            return f.read()
+    finally:
+        os.unlink(path)
+
+
+def _choose_suggestion(
+    session: PromptSession, matches: List[str], label: Callable[[str], str] = str
+) -> Optional[str]:
+    """
+    List `matches` numbered (on stderr, each shown as `label(name)`) and ask
+    for a number or a name.
+    Input that is neither a listed number nor one of the matches asks again;
+    empty input, Esc or Ctrl+C cancels.
+    """
+    err_console = Console(stderr=True)
+    shown = matches[:10]
+    for number, match in enumerate(shown, 1):
+        err_console.print(f"  [cyan]{number}[/cyan] {escape(label(match))}")
+    while True:
+        answer = session.prompt(f"Select 1-{len(shown)} or a name > ")
+        if not answer or not answer.strip():
+            return None
+        answer = answer.strip()
+        if answer.isdigit():
+            if 1 <= int(answer) <= len(shown):
+                return shown[int(answer) - 1]
+            err_console.print(f"[red]No suggestion {answer}; pick 1-{len(shown)}.[/red]")
+            continue
+        if answer in matches:
+            return answer
+        narrowed = fuzzy_match(answer, matches)
+        if len(narrowed) == 1:
+            return narrowed[0]
+        err_console.print(f"[red]'{answer}' does not pick one of the suggestions.[/red]")
+
+
+def prompt_snippet_name_simple(
+    snippet_names: List[str],
+    ranking: Optional[Tuple[Dict[str, Tuple[datetime, int]], Dict[str, float]]] = None,
+    preview: Optional[Callable[[str], str]] = None,
+    pinned: Optional[Set[str]] = None,
+    auto_select_gap: float = DEFAULT_AUTO_SELECT_GAP,
+    terms: Optional[Dict[str, Tuple[List[str], Optional[str], Optional[str]]]] = None,
+    search: str = "name",
+) -> Optional[str]:
     """
     Interactive prompt for snippet name selection.
     Shows suggestions as user types and allows tab completion.
+    If ranking is given as (metadata, weights), suggestions are ordered by a
+    blend of match quality, recency and usage (see macolint.ranking), with
+    `pinned` names first, and `preview(name)` (if given) is shown beside
+    each suggestion.
+    Text that is not an exact name picks the best match when it clearly
+    leads (by `auto_select_gap`, see is_clear_winner); otherwise, or for a
+    single character, the matches are listed to choose from.
+    With ranking, `search` (one of SEARCH_FIELDS) chooses whether the
+    snippets' `terms` (name -> (tags, language, description)) are matched too; the
+    returned name never carries the annotations shown beside it.
+    Raises NoSelectionError on end of input (Ctrl+D or empty stdin).
     """
     if not snippet_names:
         console.print("[yellow]No snippets found.[/yellow]")
         return None
     
     # Create completer with fuzzy matching
-    completer = FuzzyCompleter(WordCompleter(snippet_names, ignore_case=True))
+    if ranking is not None:
+        completer = RankedCompleter(
+            snippet_names, *ranking, preview=preview, pinned=pinned, terms=terms, search=search
+        )
+    else:
+        completer = FuzzyCompleter(WordCompleter(snippet_names, ignore_case=True))
     
     # Custom key bindings for Esc
     kb = KeyBindings()
@@ -229,17 +421,28 @@ def prompt_snippet_name_simple(snippet_names: List[str]) -> Optional[str]:
             return result
         
         # Try fuzzy match to find best match
-        matches = fuzzy_match(result, snippet_names)
-        if matches:
-            # Return the first (best) match
+        if isinstance(completer, RankedCompleter):
+            scored = completer.score(result)
+            matches = [name for _, name in scored]
+            clear_winner = is_clear_winner(result, scored, auto_select_gap)
+        else:
+            matches = fuzzy_match(result, snippet_names)
+            clear_winner = len(matches) == 1 and len(result) > 1
+        if clear_winner:
             return matches[0]
+        if matches:
+            label = completer.label if isinstance(completer, RankedCompleter) else str
+            return _choose_suggestion(session, matches, label)
         
         # If no fuzzy match, return what user typed (might be a new name for save)
         return result
         
-    except (KeyboardInterrupt, EOFError):
-        # Esc or Ctrl+C pressed
+    except KeyboardInterrupt:
+        # Ctrl+C pressed
         return None
+    except EOFError:
+        # Ctrl+D or stdin exhausted: there is nothing left to read
+        raise NoSelectionError()
     except Exception as e:
         # If prompt_toolkit fails, check if it's a TTY issue
         import sys
@@ -256,6 +459,121 @@ def prompt_snippet_name_simple(snippet_names: List[str]) -> Optional[str]:
         raise
 
 
+def prompt_name_variant(name: str, variants: List[str]) -> Optional[str]:
+    """
+    Ask which of several snippets was meant by `name`, when they differ from
+    it only in case or accents. Returns the chosen name, or None if cancelled.
+    """
+    from prompt_toolkit.output import create_output
+
+    Console(stderr=True).print(
+        f"[yellow]'{escape(name)}' matches {len(variants)} snippets:[/yellow]"
+    )
+    try:
+        output = create_output(stdout=sys.stderr)
+    except Exception:
+        output = None
+    session = PromptSession(
+        completer=FuzzyCompleter(WordCompleter(variants, sentence=True)),
+        output=output,
+    )
+    try:
+        return _choose_suggestion(session, variants)
+    except KeyboardInterrupt:
+        return None
+    except EOFError:
+        raise NoSelectionError()
+
+
+class MultiSelectCompleter(Completer):
+    """
+    Completes the last entry of a comma-separated list of snippet names,
+    leaving out names already picked earlier in the list.
+    """
+
+    def __init__(self, completer: Completer):
+        self.completer = completer
+
+    def get_completions(self, document, complete_event):
+        from prompt_toolkit.document import Document
+
+        text = document.text_before_cursor
+        picked = {part.strip() for part in text.split(",")[:-1]}
+        segment = text.split(",")[-1].lstrip()
+        for completion in self.completer.get_completions(Document(segment), complete_event):
+            if completion.text not in picked:
+                yield completion
+
+
+def prompt_snippet_names_multi(
+    snippet_names: List[str],
+    ranking: Optional[Tuple[Dict[str, Tuple[datetime, int]], Dict[str, float]]] = None,
+    preview: Optional[Callable[[str], str]] = None,
+    pinned: Optional[Set[str]] = None,
+) -> List[str]:
+    """
+    Interactive prompt for selecting several snippets at once.
+    Names are entered comma-separated, each completed like in
+    prompt_snippet_name_simple; every entry resolves to its best match.
+    Returns the selected names in the order given, without duplicates,
+    or an empty list if cancelled.
+    Raises NoSelectionError on end of input (Ctrl+D or empty stdin).
+    """
+    if not snippet_names:
+        console.print("[yellow]No snippets found.[/yellow]")
+        return []
+
+    if ranking is not None:
+        inner = RankedCompleter(snippet_names, *ranking, preview=preview, pinned=pinned)
+    else:
+        inner = FuzzyCompleter(WordCompleter(snippet_names, ignore_case=True))
+
+    kb = KeyBindings()
+
+    @kb.add(Keys.Escape)
+    def _(event):
+        event.app.exit(result=None)
+
+    from prompt_toolkit.output import create_output
+
+    # Prompt on stderr so it stays visible when stdout is captured
+    try:
+        output = create_output(stdout=sys.stderr)
+    except Exception:
+        output = None
+
+    session = PromptSession(
+        completer=MultiSelectCompleter(inner),
+        complete_while_typing=True,
+        key_bindings=kb,
+        mouse_support=False,
+        output=output,
+    )
+
+    try:
+        result = session.prompt("(comma-separated) > ")
+    except KeyboardInterrupt:
+        return []
+    except EOFError:
+        raise NoSelectionError()
+
+    selected: List[str] = []
+    for entry in (result or "").split(","):
+        entry = entry.strip()
+        if not entry:
+            continue
+        if entry not in snippet_names:
+            if isinstance(inner, RankedCompleter):
+                matches = inner.rank(entry)
+            else:
+                matches = fuzzy_match(entry, snippet_names)
+            # Keep unmatched entries so the caller can report them
+            entry = matches[0] if matches else entry
+        if entry not in selected:
+            selected.append(entry)
+    return selected
+
+
 def display_snippet_list(snippets: List[str], keyword: Optional[str] = None):
     """Display a formatted list of snippets."""
     if not snippets:
@@ -343,8 +661,10 @@ def prompt_save_location(db: Database) -> Optional[str]:
             console.print(f"[cyan]Save in: {path_label}[/cyan]")
             console.print("[dim]Select a module (ends with '/') or type a snippet name[/dim]")
             selection = session.prompt("> ")
-        except (KeyboardInterrupt, EOFError):
+        except KeyboardInterrupt:
             selection = "__ESC__"
+        except EOFError:
+            raise NoSelectionError()
 
         if selection == "__ESC__" or not selection:
             if len(module_stack) == 1:
@@ -482,9 +802,11 @@ def browse_module_tree(db: Database, root_module_path: Optional[str] = None) ->
         try:
             console.print(f"[cyan]Module: {path_label}[/cyan]")
             selection = session.prompt("> ")
-        except (KeyboardInterrupt, EOFError):
-            # Treat these like Esc at the current level
+        except KeyboardInterrupt:
+            # Treat Ctrl+C like Esc at the current level
             selection = "__ESC__"
+        except EOFError:
+            raise NoSelectionError()
 
         if selection == "__ESC__" or not selection:
             if len(module_stack) == 1:
@@ -0,0 +1,395 @@
+"""
+Schema migrations, applied in order when the database is opened.
+
+The schema version is SQLite's PRAGMA user_version: the number of entries of
+MIGRATIONS the database has been through. Opening a database runs the ones
+after its version, each followed by a bump of the version, in the caller's
+transaction (so a failure leaves the database as it was).
+
+Databases created before versions were recorded are at version 0 with any
+mix of the older steps applied, so every migration checks what is already
+there and does only what is missing. To change the schema, append a
+migration; never edit or reorder the existing ones.
+"""
+
+import sqlite3
+from typing import TYPE_CHECKING, Callable, List
+
+from macolint.crypto import token_to_blob
+from macolint.detect import detect_content_type, detect_language
+
+if TYPE_CHECKING:
+    from macolint.database import Database
+
+
+class SchemaVersionError(ValueError):
+    """The database was written by a newer version of Macolint."""
+
+
+# BLOB columns holding Fernet tokens as raw bytes (meta keeps token text)
+ENCRYPTED_BLOB_COLUMNS = (
+    ("snippets", "content_encrypted"),
+    ("snippets", "name_encrypted"),
+    ("snippets", "conflict_content_encrypted"),
+    ("modules", "name_encrypted"),
+    ("snippet_versions", "content_encrypted"),
+    ("attachments", "data_encrypted"),
+    ("quarantined_snippets", "content_encrypted"),
+)
+
+# Columns added to snippets after the module migration, in the order they
+# were introduced; existing rows get NULL or the default
+SNIPPET_COLUMNS = (
+    ("content_type", "TEXT NULL"),
+    ("kind", "TEXT NOT NULL DEFAULT 'note'"),
+    ("use_count", "INTEGER NOT NULL DEFAULT 0"),
+    ("language", "TEXT NULL"),
+    ("expires_at", "TEXT NULL"),
+    ("pinned", "INTEGER NOT NULL DEFAULT 0"),
+    ("protected", "INTEGER NOT NULL DEFAULT 0"),
+    # Filled in on save, and for older rows on their next get (see get_snippet)
+    ("byte_len", "INTEGER NULL"),
+    ("line_count", "INTEGER NULL"),
+    # Set by record_snippet_use; NULL until a snippet is first retrieved
+    ("last_accessed_at", "TEXT NULL"),
+    # A short note about the snippet; unencrypted like tags, so listings never decrypt
+    ("description", "TEXT NULL"),
+    # Set by snip sync (see SYNC_STATUS_SQL); NULL until a snippet is first synced
+    ("sync_status", "TEXT NULL"),
+    ("synced_at", "TEXT NULL"),
+    ("synced_hash", "TEXT NULL"),
+    # The server's content of a snippet in conflict, kept until snip resolve
+    ("conflict_content_encrypted", "BLOB NULL"),
+    # The team a shared snippet belongs to (snip share): the server's team id,
+    # plus its name so listings can filter offline
+    ("team_id", "TEXT NULL"),
+    ("team_name", "TEXT NULL"),
+)
+
+SNIPPETS_TABLE_SQL = """
+    CREATE TABLE {table} (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL,
+        module_id INTEGER NULL,
+        entity_type TEXT NOT NULL DEFAULT 'snippet',
+        content_encrypted BLOB NOT NULL,
+        is_shared INTEGER NOT NULL DEFAULT 0,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    )
+"""
+
+
+def _columns(cursor: sqlite3.Cursor, table: str) -> List[str]:
+    cursor.execute(f"PRAGMA table_info({table})")
+    return [row[1] for row in cursor.fetchall()]
+
+
+def _table_exists(cursor: sqlite3.Cursor, table: str) -> bool:
+    cursor.execute("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?", (table,))
+    return cursor.fetchone() is not None
+
+
+def _create_modules(db: "Database", cursor: sqlite3.Cursor) -> None:
+    """Modules: hierarchical containers for snippets."""
+    cursor.execute(
+        """
+        CREATE TABLE IF NOT EXISTS modules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            parent_id INTEGER NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )
+        """
+    )
+    cursor.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_modules_parent_name ON modules(parent_id, name)"
+    )
+
+
+def _create_snippets(db: "Database", cursor: sqlite3.Cursor) -> None:
+    """
+    Snippets, unique by (module_id, name). The first versions had neither
+    modules nor is_shared; their rows are copied to root level.
+    """
+    if not _table_exists(cursor, "snippets"):
+        cursor.execute(SNIPPETS_TABLE_SQL.format(table="snippets"))
+        cursor.execute(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_snippets_module_name "
+            "ON snippets(module_id, name)"
+        )
+        return
+
+    cols = _columns(cursor, "snippets")
+    if "is_shared" not in cols:
+        cursor.execute("ALTER TABLE snippets ADD COLUMN is_shared INTEGER NOT NULL DEFAULT 0")
+    if "module_id" in cols and "entity_type" in cols:
+        return
+    cursor.execute(SNIPPETS_TABLE_SQL.format(table="snippets_new"))
+    # The old table used the name as the full identifier
+    cursor.execute(
+        """
+        INSERT INTO snippets_new (
+            id, name, module_id, entity_type,
+            content_encrypted, is_shared, created_at, updated_at
+        )
+        SELECT id, name, NULL, 'snippet', content_encrypted, is_shared, created_at, updated_at
+        FROM snippets
+        """
+    )
+    cursor.execute("DROP TABLE snippets")
+    cursor.execute("ALTER TABLE snippets_new RENAME TO snippets")
+    # Created after the rename: index names are global, and the old one went with its table
+    cursor.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_snippets_module_name ON snippets(module_id, name)"
+    )
+
+
+def _add_snippet_columns(db: "Database", cursor: sqlite3.Cursor) -> None:
+    """The columns of SNIPPET_COLUMNS that are missing."""
+    cols = _columns(cursor, "snippets")
+    for column, declaration in SNIPPET_COLUMNS:
+        if column in cols:
+            continue
+        cursor.execute(f"ALTER TABLE snippets ADD COLUMN {column} {declaration}")
+        if column == "language":
+            _backfill_languages(db, cursor)
+
+
+def _backfill_languages(db: "Database", cursor: sqlite3.Cursor) -> None:
+    """Store a detected language for snippets saved before languages existed."""
+    cursor.execute("SELECT id, content_encrypted FROM snippets")
+    for snippet_id, encrypted in cursor.fetchall():
+        try:
+            language = detect_language(db._decrypt_content(encrypted))
+        except Exception:
+            # Undecryptable rows keep no language rather than blocking startup
+            continue
+        cursor.execute("UPDATE snippets SET language = ? WHERE id = ?", (language, snippet_id))
+
+
+def _create_attachments(db: "Database", cursor: sqlite3.Cursor) -> None:
+    """Attachments: encrypted files bundled with a snippet."""
+    cursor.execute(
+        """
+        CREATE TABLE IF NOT EXISTS attachments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            snippet_id INTEGER NOT NULL,
+            filename TEXT NOT NULL,
+            data_encrypted BLOB NOT NULL,
+            created_at TEXT NOT NULL,
+            UNIQUE(snippet_id, filename)
+        )
+        """
+    )
+
+
+def _create_tags(db: "Database", cursor: sqlite3.Cursor) -> None:
+    """Tags: free-form lowercase labels attached to snippets."""
+    cursor.execute(
+        """
+        CREATE TABLE IF NOT EXISTS snippet_tags (
+            snippet_id INTEGER NOT NULL,
+            tag TEXT NOT NULL,
+            PRIMARY KEY (snippet_id, tag)
+        )
+        """
+    )
+    cursor.execute("CREATE INDEX IF NOT EXISTS idx_snippet_tags_tag ON snippet_tags(tag)")
+
+
+def _create_versions(db: "Database", cursor: sqlite3.Cursor) -> None:
+    """
+    Versions: every saved content of a snippet; snippets keeps the current
+    one. Existing snippets start with their current content as version 1.
+    """
+    if _table_exists(cursor, "snippet_versions"):
+        return
+    cursor.execute(
+        """
+        CREATE TABLE snippet_versions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            snippet_id INTEGER NOT NULL,
+            content_encrypted BLOB NOT NULL,
+            created_at TEXT NOT NULL
+        )
+        """
+    )
+    cursor.execute(
+        "CREATE INDEX IF NOT EXISTS idx_snippet_versions_snippet ON snippet_versions(snippet_id)"
+    )
+    cursor.execute(
+        """
+        INSERT INTO snippet_versions (snippet_id, content_encrypted, created_at)
+        SELECT id, content_encrypted, updated_at FROM snippets
+        """
+    )
+
+
+def _create_quarantine(db: "Database", cursor: sqlite3.Cursor) -> None:
+    """Snippets whose content could not be decrypted (snip doctor --quarantine)."""
+    cursor.execute(
+        """
+        CREATE TABLE IF NOT EXISTS quarantined_snippets (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            path TEXT NOT NULL,
+            content_encrypted BLOB NOT NULL,
+            content_type TEXT NULL,
+            kind TEXT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            quarantined_at TEXT NOT NULL
+        )
+        """
+    )
+
+
+def _create_sync_deletions(db: "Database", cursor: sqlite3.Cursor) -> None:
+    """
+    Personal snippets deleted here after being synced, and so still on the
+    server until the next push deletes them there (snip sync push).
+    """
+    cursor.execute(
+        """
+        CREATE TABLE IF NOT EXISTS sync_deletions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            path TEXT NOT NULL,
+            deleted_at TEXT NOT NULL
+        )
+        """
+    )
+
+
+def _create_meta(db: "Database", cursor: sqlite3.Cursor) -> None:
+    """
+    Meta: the key check, the name key (encrypt_names setting) and the
+    content hash key. Every value is encrypted with the master key.
+    """
+    cursor.execute("CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT NOT NULL)")
+
+
+def _add_name_columns(db: "Database", cursor: sqlite3.Cursor) -> None:
+    """The encrypted name beside each hashed name, while encrypt_names is on."""
+    for table in ("modules", "snippets"):
+        if "name_encrypted" not in _columns(cursor, table):
+            cursor.execute(f"ALTER TABLE {table} ADD COLUMN name_encrypted BLOB NULL")
+
+
+def _add_content_hashes(db: "Database", cursor: sqlite3.Cursor) -> None:
+    """Keyed hash of the plaintext, to spot identical content on save."""
+    if "content_hash" not in _columns(cursor, "snippets"):
+        cursor.execute("ALTER TABLE snippets ADD COLUMN content_hash TEXT NULL")
+        cursor.execute("SELECT id, content_encrypted FROM snippets")
+        for snippet_id, encrypted in cursor.fetchall():
+            try:
+                content = db._decrypt_content(encrypted)
+            except Exception:
+                # Undecryptable rows keep no hash rather than blocking startup
+                continue
+            cursor.execute(
+                "UPDATE snippets SET content_hash = ? WHERE id = ?",
+                (db._content_hash(cursor, content), snippet_id),
+            )
+    cursor.execute(
+        "CREATE INDEX IF NOT EXISTS idx_snippets_content_hash ON snippets(content_hash)"
+    )
+
+
+def _store_tokens_as_bytes(db: "Database", cursor: sqlite3.Cursor) -> None:
+    """
+    Rewrite encrypted values still stored as Fernet token text (base64)
+    as the raw token bytes (see crypto.token_to_blob), which take a quarter
+    less space. Only rows in the old form are touched; a value that is not
+    a valid token is left for snip doctor to report.
+    """
+    for table, column in ENCRYPTED_BLOB_COLUMNS:
+        cursor.execute(
+            f"SELECT id, {column} FROM {table} "
+            f"WHERE hex(substr({column}, 1, 6)) = hex('gAAAAA')"
+        )
+        rows = []
+        for row_id, data in cursor.fetchall():
+            token = data.encode("ascii", "replace") if isinstance(data, str) else data
+            try:
+                rows.append((token_to_blob(token), row_id))
+            except ValueError:
+                continue
+        cursor.executemany(f"UPDATE {table} SET {column} = ? WHERE id = ?", rows)
+
+
+def _add_explicit_content_types(db: "Database", cursor: sqlite3.Cursor) -> None:
+    """
+    Whether a snippet's content type was given (save --type) rather than
+    detected; a detected type is detected again when the content changes.
+    Older rows count as given when their type is not the one detected.
+    """
+    if "content_type_explicit" in _columns(cursor, "snippets"):
+        return
+    cursor.execute(
+        "ALTER TABLE snippets ADD COLUMN content_type_explicit INTEGER NOT NULL DEFAULT 0"
+    )
+    cursor.execute(
+        "SELECT id, content_encrypted, content_type FROM snippets "
+        "WHERE content_type IS NOT NULL AND protected = 0"
+    )
+    for snippet_id, encrypted, content_type in cursor.fetchall():
+        try:
+            detected = detect_content_type(db._decrypt_content(encrypted))
+        except Exception:
+            # Undecryptable rows keep their type as detected rather than blocking startup
+            continue
+        if detected != content_type:
+            cursor.execute(
+                "UPDATE snippets SET content_type_explicit = 1 WHERE id = ?", (snippet_id,)
+            )
+
+
+Migration = Callable[["Database", sqlite3.Cursor], None]
+
+# Migration N (counting from 1) takes a database from version N-1 to N
+MIGRATIONS: List[Migration] = [
+    _create_modules,
+    _create_snippets,
+    _add_snippet_columns,
+    _create_attachments,
+    _create_tags,
+    _create_versions,
+    _create_quarantine,
+    _create_sync_deletions,
+    _create_meta,
+    _add_name_columns,
+    _add_content_hashes,
+    _store_tokens_as_bytes,
+    _add_explicit_content_types,
+]
+
+# The version a database is at once opened by this version of Macolint
+SCHEMA_VERSION = len(MIGRATIONS)
+
+
+def get_schema_version(cursor: sqlite3.Cursor) -> int:
+    """The database's schema version (0 if it predates versioning, or is new)."""
+    cursor.execute("PRAGMA user_version")
+    return cursor.fetchone()[0]
+
+
+def migrate(db: "Database", cursor: sqlite3.Cursor) -> None:
+    """
+    Bring the database up to SCHEMA_VERSION. Runs inside the caller's
+    transaction; the caller commits (or rolls back on an error).
+
+    Raises:
+        SchemaVersionError: If a newer version of Macolint wrote the database
+    """
+    version = get_schema_version(cursor)
+    if version > SCHEMA_VERSION:
+        raise SchemaVersionError(
+            f"The snippet database was written by a newer version of Macolint "
+            f"(schema version {version}; this one reads up to {SCHEMA_VERSION}). "
+            f"Run 'snip update' to open it."
+        )
+    for number, migration in enumerate(MIGRATIONS[version:], start=version + 1):
+        migration(db, cursor)
+        # PRAGMA takes no parameters; number is an int from enumerate
+        cursor.execute(f"PRAGMA user_version = {number}")
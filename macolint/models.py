@@ -1,8 +1,107 @@
 """Data models for Macolint."""
 
-from dataclasses import dataclass
-from datetime import datetime
-from typing import Optional
+import re
+import unicodedata
+from dataclasses import dataclass, field
+from datetime import datetime, timedelta
+from typing import Optional, Dict, List
+
+
+# Snippet kinds: secrets are copied (and auto-cleared) rather than printed
+SNIPPET_KINDS = ("note", "secret")
+
+# Longest accepted snippet or module path, in characters
+MAX_NAME_LENGTH = 255
+
+
+def parse_timestamp(value: str) -> datetime:
+    """
+    Parse a stored timestamp into a naive local datetime.
+    Timestamps are written in local time without an offset; values that
+    carry one (or a trailing 'Z') are converted to local time.
+    """
+    parsed = datetime.fromisoformat(value.replace("Z", "+00:00"))
+    if parsed.tzinfo is not None:
+        parsed = parsed.astimezone().replace(tzinfo=None)
+    return parsed
+
+
+def validate_name(name: str) -> str:
+    """
+    Check a snippet or module path and return it with surrounding whitespace
+    trimmed (for each path segment too).
+
+    Raises:
+        ValueError: If the name is empty, too long, has an empty path segment
+            (e.g. 'a//b') or contains control characters such as newlines
+    """
+    if any(ord(ch) < 32 or 127 <= ord(ch) < 160 for ch in name):
+        raise ValueError("Names cannot contain newlines or other control characters.")
+
+    cleaned = "/".join(segment.strip() for segment in name.strip().split("/"))
+    if not cleaned:
+        raise ValueError("Name cannot be empty.")
+    if len(cleaned) > MAX_NAME_LENGTH:
+        raise ValueError(f"Name is longer than {MAX_NAME_LENGTH} characters.")
+    if "" in cleaned.split("/"):
+        raise ValueError(f"Name '{cleaned}' has an empty path segment.")
+    return cleaned
+
+
+def fold_name(name: str) -> str:
+    """
+    A name with case and accents folded away ('Café/Deploy' -> 'cafe/deploy'),
+    for lookups that ignore both (see the name_lookup setting).
+    """
+    decomposed = unicodedata.normalize("NFKD", name)
+    return "".join(ch for ch in decomposed if not unicodedata.combining(ch)).casefold()
+
+
+# Units accepted by parse_duration, in seconds
+DURATION_UNITS = {"s": 1, "m": 60, "h": 3600, "d": 86400}
+DURATION_PATTERN = re.compile(r"(\d+)([smhd])")
+
+
+def parse_duration(text: str) -> timedelta:
+    """
+    Parse a duration such as '30s', '15m', '1h', '7d' or '1h30m'.
+
+    Raises:
+        ValueError: If the text is not a positive duration in those units
+    """
+    cleaned = text.strip().lower()
+    parts = DURATION_PATTERN.findall(cleaned)
+    if not parts or "".join(number + unit for number, unit in parts) != cleaned:
+        raise ValueError(
+            f"Invalid duration '{text}'. Use a number with s, m, h or d (e.g. 30m, 1h, 7d)."
+        )
+    seconds = sum(int(number) * DURATION_UNITS[unit] for number, unit in parts)
+    if seconds <= 0:
+        raise ValueError("Duration must be greater than zero.")
+    return timedelta(seconds=seconds)
+
+
+def parse_date_bound(text: str, end: bool = False) -> datetime:
+    """
+    Parse a local date ('2024-01-31') or date and time ('2024-01-31 14:30',
+    '2024-01-31T14:30:00') for a date range. With end, a bare date means the
+    end of that day, so the result is the (exclusive) start of the next day.
+
+    Raises:
+        ValueError: If the text is not in one of those forms
+    """
+    cleaned = text.strip()
+    try:
+        value = datetime.fromisoformat(cleaned)
+    except ValueError:
+        raise ValueError(
+            f"Invalid date '{text}'. Use YYYY-MM-DD or 'YYYY-MM-DD HH:MM'."
+        )
+    if value.tzinfo is not None:
+        value = value.astimezone().replace(tzinfo=None)
+    if end and len(cleaned) == len("YYYY-MM-DD"):
+        value += timedelta(days=1)
+    return value
 
 
 @dataclass
@@ -14,6 +113,17 @@ class Snippet:
     is_shared: bool
     created_at: datetime
     updated_at: datetime
+    kind: str = "note"
+    tags: List[str] = field(default_factory=list)
+    language: Optional[str] = None
+    expires_at: Optional[datetime] = None  # None = never expires
+    pinned: bool = False
+    protected: bool = False  # content needs a passphrase (crypto.unprotect)
+    byte_len: Optional[int] = None  # plaintext size; None if unknown (e.g. protected)
+    line_count: Optional[int] = None
+    content_type: Optional[str] = None
+    last_accessed_at: Optional[datetime] = None  # None = never retrieved
+    description: Optional[str] = None  # short note for listings; never copied with content
 
     @classmethod
     def from_row(cls, row: tuple, content: str, is_shared: bool = False) -> "Snippet":
@@ -31,8 +141,8 @@ class Snippet:
                 name=row[1],
                 content=content,
                 is_shared=bool(row[is_shared_idx]) if is_shared_idx >= 0 and is_shared_idx < len(row) else is_shared,
-                created_at=datetime.fromisoformat(row[created_idx]),
-                updated_at=datetime.fromisoformat(row[updated_idx])
+                created_at=parse_timestamp(row[created_idx]),
+                updated_at=parse_timestamp(row[updated_idx])
             )
         else:
             # Old format: id, name, content_encrypted, created_at, updated_at
@@ -41,8 +151,8 @@ class Snippet:
                 name=row[1],
                 content=content,
                 is_shared=is_shared,
-                created_at=datetime.fromisoformat(row[3]),
-                updated_at=datetime.fromisoformat(row[4])
+                created_at=parse_timestamp(row[3]),
+                updated_at=parse_timestamp(row[4])
             )
 
 
@@ -63,11 +173,98 @@ class Module:
             id=row[0],
             name=row[1],
             parent_id=row[2],
-            created_at=datetime.fromisoformat(row[3]),
-            updated_at=datetime.fromisoformat(row[4]),
+            created_at=parse_timestamp(row[3]),
+            updated_at=parse_timestamp(row[4]),
         )
 
 
+@dataclass
+class SnippetInfo:
+    """Snippet metadata without decrypted content (used for listings)."""
+    id: int
+    path: str
+    is_shared: bool
+    created_at: datetime
+    updated_at: datetime
+    content_type: Optional[str] = None
+    kind: str = "note"
+    tags: List[str] = field(default_factory=list)
+    language: Optional[str] = None
+    expires_at: Optional[datetime] = None
+    pinned: bool = False
+    protected: bool = False
+    byte_len: Optional[int] = None
+    line_count: Optional[int] = None
+    last_accessed_at: Optional[datetime] = None
+    description: Optional[str] = None
+    team: Optional[str] = None
+
+    @classmethod
+    def from_row(cls, row: tuple, path: str) -> "SnippetInfo":
+        """
+        Create a SnippetInfo from an (id, name, module_id, is_shared,
+        created_at, updated_at, content_type, kind, language, expires_at,
+        pinned, protected, byte_len, line_count, last_accessed_at,
+        description, team_name) row.
+        """
+        return cls(
+            id=row[0],
+            path=path,
+            is_shared=bool(row[3]),
+            created_at=parse_timestamp(row[4]),
+            updated_at=parse_timestamp(row[5]),
+            content_type=row[6],
+            kind=row[7] or "note",
+            language=row[8],
+            expires_at=parse_timestamp(row[9]) if row[9] else None,
+            pinned=bool(row[10]),
+            protected=bool(row[11]),
+            byte_len=row[12],
+            line_count=row[13],
+            last_accessed_at=parse_timestamp(row[14]) if row[14] else None,
+            description=row[15],
+            team=row[16],
+        )
+
+
+@dataclass
+class SnippetStats:
+    """Aggregate metrics about the local snippet store."""
+
+    snippet_count: int
+    module_count: int
+    total_size: int  # Bytes of encrypted content
+    oldest_created_at: Optional[datetime]
+    newest_created_at: Optional[datetime]
+    last_updated_at: Optional[datetime]
+    by_module: Dict[str, int] = field(default_factory=dict)
+    by_tag: Dict[str, int] = field(default_factory=dict)
+    by_language: Dict[str, int] = field(default_factory=dict)  # "" = unknown
+    content_bytes: int = 0  # Plaintext bytes of snippets with a known size
+    content_lines: int = 0
+    unsized_count: int = 0  # Snippets without a recorded size (protected or not yet read)
+
+    def to_dict(self) -> dict:
+        """Convert to a JSON-serializable dictionary."""
+        def iso(value: Optional[datetime]) -> Optional[str]:
+            return value.isoformat() if value is not None else None
+
+        return {
+            "snippet_count": self.snippet_count,
+            "module_count": self.module_count,
+            "total_size": self.total_size,
+            "oldest_created_at": iso(self.oldest_created_at),
+            "newest_created_at": iso(self.newest_created_at),
+            "last_updated_at": iso(self.last_updated_at),
+            "by_module": dict(self.by_module),
+            "by_tag": dict(self.by_tag),
+            "by_language": dict(self.by_language),
+            "content_bytes": self.content_bytes,
+            "content_lines": self.content_lines,
+            "unsized_count": self.unsized_count,
+        }
+
+
 @dataclass
 class Team:
     """Represents a team for sharing snippets."""
@@ -109,3 +306,11 @@ class TeamMember:
             joined_at=datetime.fromisoformat(data["joined_at"].replace("Z", "+00:00")),
         )
 
+
+@dataclass
+class SnippetVersion:
+    """One saved version of a snippet's content (numbered from 1, oldest first)."""
+    number: int
+    created_at: datetime
+    size: int  # Characters of decrypted content
+    is_current: bool = False
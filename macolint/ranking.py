@@ -0,0 +1,149 @@
+"""Ranking of interactive search results by match quality, recency and usage."""
+
+import math
+from datetime import datetime
+from typing import Dict, List, Optional, Set, Tuple
+
+# Days after which the recency score of an untouched snippet halves
+RECENCY_HALF_LIFE_DAYS = 7.0
+
+# How far the best score must lead the runner-up for a typed query to pick
+# it without asking (the fuzzy_auto_select_gap setting)
+DEFAULT_AUTO_SELECT_GAP = 0.15
+
+# What interactive search matches a query against (snip get --search): the
+# name, the tags and language, the description, or all of them
+SEARCH_FIELDS = ("name", "tags", "description", "all")
+# A tag, language or description match counts this much of a name match, so
+# with "all" a snippet named after the query ranks above one merely tagged with it
+TERM_MATCH_WEIGHT = 0.7
+
+
+def match_score(query: str, candidate: str) -> Optional[float]:
+    """
+    Score how well `query` matches `candidate`, from 0 to 1.
+    Substrings score highest (more so at the start of a path segment);
+    other in-order character matches score by how tightly they cluster.
+    Returns None if the query does not match at all.
+    """
+    if not query:
+        return 1.0
+
+    query_lower = query.lower()
+    candidate_lower = candidate.lower()
+
+    position = candidate_lower.find(query_lower)
+    if position != -1:
+        at_boundary = position == 0 or candidate_lower[position - 1] in "/_-. "
+        return 1.0 if at_boundary else 0.9
+
+    first = last = -1
+    query_idx = 0
+    for idx, char in enumerate(candidate_lower):
+        if char == query_lower[query_idx]:
+            if first == -1:
+                first = idx
+            last = idx
+            query_idx += 1
+            if query_idx == len(query_lower):
+                break
+
+    if query_idx < len(query_lower):
+        return None
+    return 0.8 * len(query_lower) / (last - first + 1)
+
+
+def field_match_score(
+    query: str, candidate: str, terms: List[str], search: str = "name"
+) -> Optional[float]:
+    """
+    Score `query` against a candidate's name and/or its search `terms` (tags,
+    language or description), as chosen by `search` (one of SEARCH_FIELDS). A term
+    match scores TERM_MATCH_WEIGHT of the same name match; with "all" the
+    better of the two counts. Returns None if nothing matches.
+    """
+    if not query:
+        return 1.0
+    scores = []
+    if search in ("name", "all"):
+        scores.append(match_score(query, candidate))
+    if search != "name":
+        scores.extend(
+            None if score is None else TERM_MATCH_WEIGHT * score
+            for score in (match_score(query, term) for term in terms)
+        )
+    scores = [score for score in scores if score is not None]
+    return max(scores) if scores else None
+
+
+def score_candidates(
+    query: str,
+    candidates: List[str],
+    metadata: Dict[str, Tuple[datetime, int]],
+    weights: Dict[str, float],
+    now: Optional[datetime] = None,
+    pinned: Optional[Set[str]] = None,
+    terms: Optional[Dict[str, List[str]]] = None,
+    search: str = "name",
+) -> List[Tuple[float, str]]:
+    """
+    Return (score, candidate) for the candidates matching `query`, best first.
+
+    The score blends match quality with recency (from updated_at) and usage
+    count, each weighted by weights["match"], ["recency"] and ["usage"].
+    `metadata` maps a candidate to its (updated_at, use_count). Matching
+    `pinned` candidates come before all others. With any `search` but
+    "name", `terms` (candidate -> the terms for that search) are matched
+    too (see field_match_score).
+    """
+    terms = terms or {}
+    pinned = pinned or set()
+    now = now or datetime.now()
+    max_uses = max((uses for _, uses in metadata.values()), default=0)
+
+    scored = []
+    for candidate in candidates:
+        match = field_match_score(query, candidate, terms.get(candidate, []), search)
+        if match is None:
+            continue
+
+        updated_at, uses = metadata.get(candidate, (None, 0))
+        recency = 0.0
+        if updated_at is not None:
+            age_days = max((now - updated_at).total_seconds(), 0) / 86400
+            recency = 0.5 ** (age_days / RECENCY_HALF_LIFE_DAYS)
+        usage = math.log1p(uses) / math.log1p(max_uses) if max_uses else 0.0
+
+        score = (
+            weights.get("match", 1.0) * match
+            + weights.get("recency", 0.0) * recency
+            + weights.get("usage", 0.0) * usage
+        )
+        scored.append((score, candidate))
+
+    scored.sort(key=lambda item: (item[1] not in pinned, -item[0], item[1]))
+    return scored
+
+
+def rank_candidates(
+    query: str,
+    candidates: List[str],
+    metadata: Dict[str, Tuple[datetime, int]],
+    weights: Dict[str, float],
+    now: Optional[datetime] = None,
+    pinned: Optional[Set[str]] = None,
+) -> List[str]:
+    """Return the candidates matching `query`, best first (see score_candidates)."""
+    scored = score_candidates(query, candidates, metadata, weights, now=now, pinned=pinned)
+    return [candidate for _, candidate in scored]
+
+
+def is_clear_winner(query: str, scored: List[Tuple[float, str]], gap: float) -> bool:
+    """
+    Whether the first of `scored` (from score_candidates) is a safe pick for
+    `query`: it is the only match, or leads the next by at least `gap`.
+    Single-character queries match too loosely and never qualify.
+    """
+    if not scored or len(query.strip()) < 2:
+        return False
+    return len(scored) == 1 or scored[0][0] - scored[1][0] >= gap
@@ -0,0 +1,34 @@
+"""Running shell snippets as commands (snip run)."""
+
+import os
+import subprocess
+
+from macolint.models import Snippet
+
+# Languages of snippets that snip run accepts without the runnable tag
+SHELL_LANGUAGES = ("sh", "bash", "zsh", "fish")
+RUNNABLE_TAG = "runnable"
+
+
+def is_runnable(snippet: Snippet) -> bool:
+    """
+    True for snippets meant to be run: tagged 'runnable', or shell code by
+    language or detected content type. A SQL query or a note never is.
+    """
+    return (
+        RUNNABLE_TAG in snippet.tags
+        or (snippet.language or "").lower() in SHELL_LANGUAGES
+        or snippet.content_type == "shell"
+    )
+
+
+def run_in_shell(command: str) -> int:
+    """
+    Run command with `$SHELL -c` (/bin/sh without SHELL; cmd on Windows),
+    attached to this terminal so output streams as it is produced.
+    Returns the command's exit status.
+    """
+    if os.name == "nt":
+        return subprocess.run(command, shell=True).returncode
+    shell = os.environ.get("SHELL") or "/bin/sh"
+    return subprocess.run([shell, "-c", command]).returncode
@@ -0,0 +1,58 @@
+"""Full-text search over decrypted snippet content (in memory only)."""
+
+import re
+from typing import List, Pattern, Tuple
+
+# One hunk of output: (line_number, line, is_match) entries
+Hunk = List[Tuple[int, str, bool]]
+
+
+def find_hunks(content: str, query: str, context: int = 1) -> List[Hunk]:
+    """
+    Find lines of `content` containing `query` (case-insensitive), grep-style.
+    Each hunk holds the matching line(s) plus up to `context` lines around
+    them; overlapping or adjacent hunks are merged. Line numbers start at 1.
+    """
+    query_lower = query.lower()
+    lines = content.splitlines()
+    matches = [idx for idx, line in enumerate(lines) if query_lower in line.lower()]
+
+    hunks: List[Hunk] = []
+    last_end = -1
+    for idx in matches:
+        start = max(idx - context, 0)
+        end = min(idx + context, len(lines) - 1)
+        if hunks and start <= last_end + 1:
+            # Extend the previous hunk instead of starting a new one
+            start = last_end + 1
+        else:
+            hunks.append([])
+        for line_idx in range(start, end + 1):
+            hunks[-1].append(
+                (line_idx + 1, lines[line_idx], query_lower in lines[line_idx].lower())
+            )
+        last_end = max(last_end, end)
+
+    return hunks
+
+
+def find_regex_lines(content: str, pattern: Pattern) -> List[Tuple[int, str]]:
+    """(line_number, line) for each line of `content` that `pattern` matches anywhere."""
+    return [
+        (idx, line)
+        for idx, line in enumerate(content.splitlines(), start=1)
+        if pattern.search(line)
+    ]
+
+
+def compile_pattern(pattern: str, ignore_case: bool = False) -> Pattern:
+    """
+    Compile a grep pattern (Python re syntax).
+
+    Raises:
+        ValueError: If the pattern is not a valid regular expression
+    """
+    try:
+        return re.compile(pattern, re.IGNORECASE if ignore_case else 0)
+    except re.error as e:
+        raise ValueError(f"Invalid regular expression '{pattern}': {e}.")
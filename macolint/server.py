@@ -0,0 +1,171 @@
+"""Local HTTP API for editor and browser integrations (snip serve)."""
+
+import hmac
+import json
+import secrets
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+from typing import Optional, Tuple
+from urllib.parse import parse_qs, unquote, urlsplit
+
+from macolint.api import (
+    Config, SnippetExistsError, SnippetNotFoundError, get_snippet, list_snippets, save_snippet,
+)
+from macolint.crypto import DecryptionError
+from macolint.models import SNIPPET_KINDS
+
+# The server only ever listens here: snippets are served decrypted, so
+# nothing but this machine may reach it
+HOST = "127.0.0.1"
+DEFAULT_PORT = 8765
+# Largest request body accepted by a save, in bytes
+MAX_BODY_BYTES = 10 * 1024 * 1024
+SNIPPETS_PATH = "/snippets"
+
+
+def new_token() -> str:
+    """A random token that clients must send as 'Authorization: Bearer TOKEN'."""
+    return secrets.token_urlsafe(32)
+
+
+class ApiError(Exception):
+    """A request that fails with an HTTP status and a {"error", "kind"} body."""
+
+    def __init__(self, status: int, message: str, kind: str = "error"):
+        super().__init__(message)
+        self.status = status
+        self.kind = kind
+
+
+def make_handler(config: Config, token: str, port: int):
+    """A request handler class serving the snippet store described by config."""
+    allowed_hosts = {f"{HOST}:{port}", f"localhost:{port}"}
+
+    class Handler(BaseHTTPRequestHandler):
+        server_version = "macolint"
+
+        def do_GET(self):
+            self._handle(self._get)
+
+        def do_PUT(self):
+            self._handle(self._put)
+
+        def log_message(self, format, *args):
+            # Request lines name snippets; keep them out of the terminal
+            pass
+
+        def _handle(self, action) -> None:
+            try:
+                self._check_request()
+                status, body = action()
+            except ApiError as e:
+                status, body = e.status, {"error": str(e), "kind": e.kind}
+            except DecryptionError as e:
+                status, body = 500, {"error": str(e), "kind": "decrypt"}
+            except Exception as e:
+                status, body = 500, {"error": f"Error: {e}", "kind": "internal"}
+            data = json.dumps(body).encode("utf-8")
+            self.send_response(status)
+            self.send_header("Content-Type", "application/json; charset=utf-8")
+            self.send_header("Content-Length", str(len(data)))
+            self.send_header("Cache-Control", "no-store")
+            self.end_headers()
+            self.wfile.write(data)
+
+        def _check_request(self) -> None:
+            # A page on another site could point its own host name at
+            # 127.0.0.1 (DNS rebinding); its requests carry that name
+            if self.headers.get("Host") not in allowed_hosts:
+                raise ApiError(403, "Requests must be addressed to localhost.", "usage")
+            scheme, _, sent = (self.headers.get("Authorization") or "").partition(" ")
+            if scheme.lower() != "bearer" or not hmac.compare_digest(sent.strip(), token):
+                raise ApiError(401, "Missing or wrong token.", "usage")
+
+        def _route(self) -> Tuple[Optional[str], dict]:
+            """(snippet name, or None for the collection; query parameters)."""
+            url = urlsplit(self.path)
+            query = {key: values[-1] for key, values in parse_qs(url.query).items()}
+            if url.path in (SNIPPETS_PATH, SNIPPETS_PATH + "/"):
+                return None, query
+            if url.path.startswith(SNIPPETS_PATH + "/"):
+                return unquote(url.path[len(SNIPPETS_PATH) + 1:]), query
+            raise ApiError(404, f"No such endpoint: {url.path}", "not_found")
+
+        def _get(self) -> Tuple[int, dict]:
+            name, query = self._route()
+            if name is None:
+                names = sorted(list_snippets(config))
+                prefix = query.get("module")
+                if prefix:
+                    names = [n for n in names if n.startswith(prefix.rstrip("/") + "/")]
+                return 200, {"ok": True, "snippets": names}
+            try:
+                snippet = get_snippet(config, name)
+            except SnippetNotFoundError as e:
+                raise ApiError(404, str(e), "not_found")
+            except ValueError as e:
+                # A protected snippet: its passphrase is only ever typed into snip
+                raise ApiError(403, str(e), "usage")
+            if snippet.kind == "secret" and query.get("reveal") not in ("1", "true"):
+                raise ApiError(403, f"'{name}' is a secret; add ?reveal=1 to fetch it.", "usage")
+            return 200, {
+                "ok": True,
+                "name": name,
+                "content": snippet.content,
+                "kind": snippet.kind,
+                "language": snippet.language,
+                "tags": snippet.tags,
+                "description": snippet.description,
+                "updated_at": snippet.updated_at.isoformat(),
+            }
+
+        def _put(self) -> Tuple[int, dict]:
+            name, _ = self._route()
+            if name is None:
+                raise ApiError(405, "Save a snippet with PUT /snippets/NAME.", "usage")
+            try:
+                length = int(self.headers.get("Content-Length") or 0)
+            except ValueError:
+                length = -1
+            if not 0 < length <= MAX_BODY_BYTES:
+                raise ApiError(400, "Send the snippet as a JSON body.", "usage")
+            try:
+                body = json.loads(self.rfile.read(length).decode("utf-8"))
+            except (UnicodeDecodeError, ValueError):
+                raise ApiError(400, "The body is not valid JSON.", "usage")
+            if not isinstance(body, dict) or not isinstance(body.get("content"), str):
+                raise ApiError(400, 'The body needs a "content" string.', "usage")
+            kind = body.get("kind")
+            if kind is not None and kind not in SNIPPET_KINDS:
+                raise ApiError(400, f"Unknown kind: {kind}", "usage")
+            tags = body.get("tags")
+            if tags is not None and not (
+                isinstance(tags, list) and all(isinstance(tag, str) for tag in tags)
+            ):
+                raise ApiError(400, '"tags" must be a list of strings.', "usage")
+            description = body.get("description")
+            if description is not None and (
+                not isinstance(description, str) or "\n" in description
+            ):
+                raise ApiError(400, '"description" must be a single line.', "usage")
+            try:
+                created = save_snippet(
+                    config, name, body["content"], kind=kind, tags=tags,
+                    language=body.get("language"), description=description,
+                    overwrite=bool(body.get("overwrite")),
+                )
+            except SnippetExistsError as e:
+                raise ApiError(409, f"{e} Send \"overwrite\": true to replace it.", "conflict")
+            except ValueError as e:
+                raise ApiError(400, str(e), "usage")
+            return (201 if created else 200), {"ok": True, "name": name, "created": created}
+
+    return Handler
+
+
+def serve(config: Config, port: int, token: str) -> None:
+    """Serve the API on HOST:port until interrupted (KeyboardInterrupt)."""
+    httpd = ThreadingHTTPServer((HOST, port), make_handler(config, token, port))
+    try:
+        httpd.serve_forever()
+    finally:
+        httpd.server_close()
@@ -13,18 +13,17 @@ console = Console()
 db = Database()
 
 
-def share_snippet(full_path: str, team_name: str, passphrase: str) -> bool:
+def share_snippet(full_path: str, team_name: str) -> bool:
     """
     Share a snippet with a team.
     
-    This function:
-    1. Marks snippet as shared in local DB
-    2. Pushes snippet to team space in Supabase
+    This function scopes the snippet to the team in the local DB. The next
+    'snip sync push --team' uploads it to the team space, encrypted under
+    the team key instead of the user's personal key.
     
     Args:
         full_path: Full path to the snippet
         team_name: Name of the team to share with
-        passphrase: User passphrase for encryption
     
     Returns:
         True if successful, False otherwise
@@ -56,14 +55,8 @@ def share_snippet(full_path: str, team_name: str, passphrase: str) -> bool:
         raise RuntimeError(f"You are not a member of team '{team_name}'.")
     
     try:
-        # Mark as shared in local DB
-        db.mark_snippet_shared(full_path, True)
-        
-        # Push to team space (this will be handled by sync_push with team_id)
-        # For now, we'll use a workaround: push the snippet with team_id set
-        # We need to update sync_push to accept team_id parameter
-        # For MVP, let's just mark it as shared locally and note that
-        # user needs to run 'snip sync push --team <team_name>' to actually share
+        # Scope to the team in local DB; sync_push with its team_id uploads it
+        db.set_snippet_team(full_path, team.id, team.name)
         
         console.print(f"[green]Snippet '{full_path}' marked as shared with team '{team_name}'.[/green]")
         console.print(f"[yellow]Run 'snip sync push --team {team_name}' to upload to team space.[/yellow]")
@@ -143,7 +136,7 @@ def unshare_snippet(full_path: str, team_name: str) -> bool:
         # Check if snippet is shared with other teams
         # For now, we'll just unmark it locally
         # In a full implementation, we'd check all teams
-        db.mark_snippet_shared(full_path, False)
+        db.set_snippet_team(full_path, None)
         
         console.print(f"[green]Snippet '{full_path}' unshared from team '{team_name}'.[/green]")
         
@@ -1,12 +1,12 @@
 """Session storage utilities for authentication."""
 
-import os
 import json
-import stat
 from pathlib import Path
 from typing import Optional, Dict, Any
 
-SESSION_PATH = Path.home() / ".macolint" / "session.json"
+from macolint.config import CONFIG_DIR, write_private_file
+
+SESSION_PATH = CONFIG_DIR / "session.json"
 
 
 def ensure_session_dir():
@@ -25,9 +25,8 @@ def save_session(data: Dict[str, Any]) -> None:
         IOError: If file cannot be written
     """
     ensure_session_dir()
-    SESSION_PATH.write_text(json.dumps(data, indent=2))
-    # Set file permissions to 600 (read/write for owner only)
-    os.chmod(SESSION_PATH, stat.S_IRUSR | stat.S_IWUSR)
+    # Written atomically with 600 permissions (read/write for owner only)
+    write_private_file(SESSION_PATH, json.dumps(data, indent=2).encode("utf-8"))
 
 
 def load_session() -> Optional[Dict[str, Any]]:
@@ -1,19 +1,40 @@
 """Sync functions for pushing and pulling snippets to/from Supabase."""
 
-from typing import List, Tuple, Optional
+import socket
+from datetime import datetime
+from typing import Dict, List, Tuple, Optional
 from rich.console import Console
 from rich.progress import Progress, SpinnerColumn, TextColumn
 
 from macolint.supabase_client import get_client, get_authenticated_client
-from macolint.crypto import derive_key, encrypt, decrypt, gen_salt, b64, ub64
+from macolint.crypto import derive_key, derive_team_key, encrypt, decrypt, gen_salt, b64, ub64
 from macolint.storage import load_session
-from macolint.database import Database
+from macolint.database import SYNC_STRATEGIES, Database
+from macolint.models import parse_timestamp
 from macolint.auth import get_access_token, is_authenticated
 
 console = Console()
 db = Database()
 
 
+class SyncUnreachable(RuntimeError):
+    """The sync server could not be reached; what was not sent stays pending."""
+
+
+def is_network_error(error: Exception) -> bool:
+    """
+    True for a failure to reach the server (offline, DNS, timeout), as
+    opposed to an error the server returned.
+    """
+    try:
+        import httpx
+    except ImportError:
+        httpx = None
+    if httpx is not None and isinstance(error, httpx.TransportError):
+        return True
+    return isinstance(error, (ConnectionError, TimeoutError, socket.gaierror))
+
+
 def ensure_user_salt(user_id: str, session_token: str) -> bytes:
     """
     Get or create user's salt in users_meta table.
@@ -96,12 +117,12 @@ def list_shared_snippets_for_team(team_id: str) -> List[Tuple[str, str]]:
     Returns:
         List of tuples (full_path, content) for shared snippets
     """
-    shared_paths = db.get_shared_snippets()
+    shared_paths = db.get_team_snippets(team_id)
     snippets = []
     
     for path in shared_paths:
         snippet = db.get_snippet(path)
-        if snippet and snippet.is_shared:
+        if snippet:
             snippets.append((path, snippet.content))
     
     return snippets
@@ -114,25 +135,40 @@ def list_personal_snippets() -> List[Tuple[str, str]]:
     Returns:
         List of tuples (full_path, content) for personal snippets
     """
-    all_paths = db.list_snippets()
+    personal_paths = db.get_personal_snippets()
     snippets = []
     
-    for path in all_paths:
+    for path in personal_paths:
         snippet = db.get_snippet(path)
-        if snippet and not snippet.is_shared:
+        if snippet:
             snippets.append((path, snippet.content))
     
     return snippets
 
 
-def sync_push(passphrase: str, team_id: Optional[str] = None) -> Tuple[int, int]:
+def sync_push(
+    passphrase: str, team_id: Optional[str] = None, push_all: bool = False
+) -> Tuple[int, int]:
     """
     Push local snippets to Supabase (encrypted).
+    Only snippets pending a sync (new, or changed since they were last
+    synced) are sent, unless push_all; snippets in conflict never are.
+    Each pushed snippet is marked synced. Personal snippets deleted since
+    they were synced are then deleted on the server.
+    
+    If the server cannot be reached, pushing stops with SyncUnreachable:
+    the snippets not sent stay pending (and deletions queued), so the next
+    push retries them.
+    
+    Team snippets are encrypted under the team key (see derive_team_key)
+    with a fresh salt, so every member with the team passphrase can
+    decrypt them; personal snippets under the user's own key.
     
     Args:
-        passphrase: User passphrase for encryption
-        team_id: Optional team ID. If provided, pushes only shared snippets for that team.
-                 If None, pushes only personal snippets (team_id IS NULL).
+        passphrase: User passphrase for encryption, or the team passphrase with team_id
+        team_id: Optional team ID. If provided, pushes only snippets shared with that team
+                 (snip share --team). If None, pushes only personal snippets.
+        push_all: Push every snippet, including those already synced
     
     Returns:
         Tuple of (pushed_count, error_count)
@@ -150,25 +186,52 @@ def sync_push(passphrase: str, team_id: Optional[str] = None) -> Tuple[int, int]
     user_id = session["user"]["id"]
     access_token = session["access_token"]
     
-    # Get or create user salt
+    # Derive encryption key: the team's, or the user's with their salt
     with console.status("[cyan]Setting up encryption...[/cyan]"):
-        salt = ensure_user_salt(user_id, access_token)
-    
-    # Derive encryption key
-    key = derive_key(passphrase, salt)
+        if team_id:
+            salt = gen_salt()
+            key = derive_team_key(passphrase, team_id, salt)
+        else:
+            salt = ensure_user_salt(user_id, access_token)
+            key = derive_key(passphrase, salt)
     
-    # Get local snippets based on team_id
+    # Snippets shared with this team, personal ones otherwise
     if team_id:
-        # Push only shared snippets for this team
-        local_snippets = list_shared_snippets_for_team(team_id)
+        states = db.get_sync_states(team=team_id)
+        unscoped = sorted(set(db.get_shared_snippets()) - set(db.get_team_snippets()))
+        if unscoped:
+            console.print(
+                f"[yellow]{len(unscoped)} shared snippet(s) belong to no team and are not "
+                f"pushed: {', '.join(unscoped)}. Run 'snip share NAME --team TEAM' to "
+                f"choose one.[/yellow]"
+            )
     else:
-        # Push only personal snippets (not shared)
-        local_snippets = list_personal_snippets()
-    
-    if not local_snippets:
-        if team_id:
+        states = db.get_sync_states(shared=False)
+    deletions = [] if team_id else db.get_sync_deletions()
+    conflicts = sorted(path for path, (status, _, _) in states.items() if status == "conflict")
+    paths = sorted(
+        path for path, (status, _, _) in states.items()
+        if status == "pending" or (push_all and status == "synced")
+    )
+    local_snippets = []
+    for path in paths:
+        snippet = db.get_snippet(path)
+        if snippet:
+            local_snippets.append((path, snippet.content))
+    updated_times = {path: states[path][2] for path in paths}
+
+    if conflicts:
+        console.print(
+            f"[yellow]Skipping {len(conflicts)} snippet(s) in conflict with the server: "
+            f"{', '.join(conflicts)}. Settle them with 'snip resolve NAME "
+            f"--take local|remote|merge'.[/yellow]"
+        )
+    if not local_snippets and not deletions:
+        if states and not conflicts:
+            console.print("[yellow]No local changes to push; everything is synced.[/yellow]")
+        elif team_id and not states:
             console.print("[yellow]No shared snippets to sync for this team.[/yellow]")
-        else:
+        elif not states:
             console.print("[yellow]No local snippets to sync.[/yellow]")
         return (0, 0)
     
@@ -208,7 +271,9 @@ def sync_push(passphrase: str, team_id: Optional[str] = None) -> Tuple[int, int]
                     "name": name,
                     "content_encrypted": b64(ciphertext),  # Base64 encode for JSON
                     "nonce": b64(nonce),  # Base64 encode for JSON
-                    "salt": b64(salt)  # Base64 encode for JSON
+                    "salt": b64(salt),  # Base64 encode for JSON
+                    # Compared by pull --strategy newer on other devices
+                    "updated_at": updated_times[full_path].astimezone().isoformat(),
                 }
                 
                 # Add team_id if pushing to team space
@@ -221,15 +286,51 @@ def sync_push(passphrase: str, team_id: Optional[str] = None) -> Tuple[int, int]
                 # Upsert to Supabase (update if exists, insert if not)
                 # Note: Supabase upsert uses the unique constraint automatically
                 sb.table("snippets").upsert(snippet_data).execute()
+                db.mark_synced(full_path, content)
                 
                 pushed_count += 1
                 progress.update(task, advance=1)
                 
             except Exception as e:
+                if is_network_error(e):
+                    raise SyncUnreachable(
+                        f"Cannot reach the sync server ({e}). "
+                        f"{len(local_snippets) - pushed_count} snippet(s) stay pending; "
+                        f"run 'snip sync push' again when back online."
+                    ) from e
                 error_count += 1
                 console.print(f"[red]Error pushing '{full_path}': {e}[/red]")
                 progress.update(task, advance=1)
     
+    deleted_count = 0
+    for deletion_id, full_path in deletions:
+        if db.snippet_exists(full_path):
+            # Saved again since: the push above replaces the server's copy
+            db.clear_sync_deletion(deletion_id)
+            continue
+        module, _, name = full_path.rpartition("/")
+        try:
+            delete_query = sb.table("snippets").delete().eq("user_id", user_id)
+            delete_query = delete_query.is_("team_id", "null").eq("name", name)
+            if module:
+                delete_query = delete_query.eq("module", module)
+            else:
+                delete_query = delete_query.is_("module", "null")
+            delete_query.execute()
+        except Exception as e:
+            if is_network_error(e):
+                raise SyncUnreachable(
+                    f"Cannot reach the sync server ({e}). Deleting '{full_path}' there "
+                    f"stays queued; run 'snip sync push' again when back online."
+                ) from e
+            error_count += 1
+            console.print(f"[red]Error deleting '{full_path}' from the server: {e}[/red]")
+            continue
+        db.clear_sync_deletion(deletion_id)
+        deleted_count += 1
+    if deleted_count:
+        console.print(f"[green]Deleted {deleted_count} snippet(s) from the server.[/green]")
+    
     # Update device last_sync (optional - create device entry if needed)
     try:
         import socket
@@ -257,17 +358,37 @@ def sync_push(passphrase: str, team_id: Optional[str] = None) -> Tuple[int, int]
     return (pushed_count, error_count)
 
 
-def sync_pull(passphrase: str, team_id: Optional[str] = None) -> Tuple[int, int]:
+def sync_pull(
+    passphrase: str,
+    team_id: Optional[str] = None,
+    strategy: str = "newer",
+    team_name: Optional[str] = None,
+    legacy_passphrase: Optional[str] = None,
+) -> Tuple[int, int]:
     """
     Pull snippets from Supabase and decrypt into local database.
+    New snippets are saved, and local snippets unchanged since their last
+    sync take the server's content. A snippet changed on both sides is a
+    conflict, settled by `strategy` (see SYNC_STRATEGIES and apply_remote).
+    
+    Team snippets are decrypted with the team key (see derive_team_key),
+    and new ones are saved scoped to the team. Those shared before team
+    keys were encrypted with the sharer's personal key: with
+    legacy_passphrase they are decrypted with it and marked pending, so
+    the next push re-encrypts them under the team key.
     
     Args:
-        passphrase: User passphrase for decryption
+        passphrase: User passphrase for decryption, or the team passphrase with team_id
         team_id: Optional team ID. If provided, pulls only snippets for that team.
                  If None, pulls only personal snippets (team_id IS NULL).
+        strategy: How to settle conflicts: "newer", "local" or "remote"
+        team_name: The team's name, recorded on new team snippets
+        legacy_passphrase: With team_id, the user's sync passphrase, for team
+                           snippets shared before team keys
     
     Returns:
-        Tuple of (pulled_count, error_count)
+        Tuple of (pulled_count, error_count); pulled_count includes
+        local snippets updated from the server
     
     Raises:
         RuntimeError: If not authenticated or sync fails
@@ -302,6 +423,11 @@ def sync_pull(passphrase: str, team_id: Optional[str] = None) -> Tuple[int, int]
         response = query.execute()
         remote_snippets = response.data
     except Exception as e:
+        if is_network_error(e):
+            raise SyncUnreachable(
+                f"Cannot reach the sync server ({e}). Local changes stay pending; "
+                f"run 'snip sync' again when back online."
+            ) from e
         raise RuntimeError(f"Failed to fetch snippets from server: {e}")
     
     if not remote_snippets:
@@ -311,12 +437,17 @@ def sync_pull(passphrase: str, team_id: Optional[str] = None) -> Tuple[int, int]
             console.print("[yellow]No snippets found on server.[/yellow]")
         return (0, 0)
 
-    # Collect existing local snippet paths so we don't pull duplicates.
-    # We use the same full-path convention as elsewhere (e.g. "module/name").
-    local_paths = set(db.list_snippets())
+    # Local sync state by full path (the same "module/name" convention)
+    states = db.get_sync_states()
+    # Deleted here but not yet on the server: not pulled back
+    deleted = set() if team_id else {path for _, path in db.get_sync_deletions()}
+    # Deriving a key is slow on purpose; snippets pushed together share a salt
+    keys: Dict[bytes, bytes] = {}
 
     pulled_count = 0
     error_count = 0
+    conflicts = []
+    legacy = []
     
     with Progress(
         SpinnerColumn(),
@@ -339,10 +470,7 @@ def sync_pull(passphrase: str, team_id: Optional[str] = None) -> Tuple[int, int]
                     full_path = f"{module}/{name}"
                 else:
                     full_path = name
-
-                # Skip snippets that already exist locally (by full path / name).
-                # We do not overwrite or create duplicates in this case.
-                if full_path in local_paths:
+                if full_path in deleted and full_path not in states:
                     progress.update(task, advance=1)
                     continue
                 
@@ -500,8 +628,31 @@ def sync_pull(passphrase: str, team_id: Optional[str] = None) -> Tuple[int, int]
                     else:
                         salt_bytes = user_salt
                     
-                    # Derive decryption key for this snippet
-                    snippet_key = derive_key(passphrase, salt_bytes)
+                    # Keys to try: team snippets under the team key, then (with
+                    # legacy_passphrase) the personal key team snippets were
+                    # encrypted with before team keys; those may have no salt
+                    candidates = []
+                    if team_id and snippet_salt:
+                        candidates.append(("team", salt_bytes))
+                    if not team_id or legacy_passphrase:
+                        candidates.append(("personal", salt_bytes))
+                    if not candidates:
+                        raise ValueError(
+                            "team snippet has no salt: it was shared before team keys. "
+                            "Pull with --legacy to decrypt it with your sync passphrase"
+                        )
+                    
+                    # Derive decryption keys for this snippet
+                    snippet_keys = []
+                    for kind, key_salt in candidates:
+                        if (kind, key_salt) not in keys:
+                            if kind == "team":
+                                keys[(kind, key_salt)] = derive_team_key(passphrase, team_id, key_salt)
+                            else:
+                                keys[(kind, key_salt)] = derive_key(
+                                    legacy_passphrase if team_id else passphrase, key_salt
+                                )
+                        snippet_keys.append((kind, keys[(kind, key_salt)]))
                 except Exception as e:
                     error_count += 1
                     console.print(f"[red]Invalid encrypted content for '{full_path}': {e}[/red]")
@@ -510,23 +661,41 @@ def sync_pull(passphrase: str, team_id: Optional[str] = None) -> Tuple[int, int]
                 
                 # Decrypt content
                 try:
-                    plaintext_bytes = decrypt(ciphertext, nonce_bytes, snippet_key)
+                    for index, (kind, snippet_key) in enumerate(snippet_keys):
+                        try:
+                            plaintext_bytes = decrypt(ciphertext, nonce_bytes, snippet_key)
+                            break
+                        except Exception:
+                            if index == len(snippet_keys) - 1:
+                                raise
                     content = plaintext_bytes.decode('utf-8')
                 except Exception as e:
                     error_count += 1
                     console.print(f"[red]Decryption failed for '{full_path}': {e}[/red]")
-                    console.print("[yellow]This usually means the passphrase is incorrect.[/yellow]")
+                    if team_id and not legacy_passphrase:
+                        console.print(
+                            "[yellow]This usually means the team passphrase is incorrect, or the "
+                            "snippet was shared before team keys (pull with --legacy).[/yellow]"
+                        )
+                    elif team_id:
+                        console.print("[yellow]This usually means the team passphrase is incorrect.[/yellow]")
+                    else:
+                        console.print("[yellow]This usually means the passphrase is incorrect.[/yellow]")
                     progress.update(task, advance=1)
                     continue
                 
-                # Save to local database
-                db.save_snippet(full_path, content)
-                
-                # Mark as shared if pulling from team space
-                if team_id:
-                    db.mark_snippet_shared(full_path, True)
-                
-                pulled_count += 1
+                outcome = apply_remote(
+                    full_path, content, row.get("updated_at"), states.get(full_path),
+                    strategy, team_id=team_id, team_name=team_name,
+                )
+                if outcome in ("pulled", "updated"):
+                    pulled_count += 1
+                elif outcome == "conflict":
+                    conflicts.append(full_path)
+                # Re-encrypted under the team key by the next push --team
+                if team_id and kind == "personal":
+                    db.mark_pending(full_path)
+                    legacy.append(full_path)
                 progress.update(task, advance=1)
                 
             except Exception as e:
@@ -534,5 +703,79 @@ def sync_pull(passphrase: str, team_id: Optional[str] = None) -> Tuple[int, int]
                 console.print(f"[red]Error pulling '{row.get('name', 'unknown')}': {e}[/red]")
                 progress.update(task, advance=1)
     
+    if conflicts:
+        console.print(
+            f"[yellow]{len(conflicts)} snippet(s) changed both here and on the server: "
+            f"{', '.join(sorted(conflicts))}. The server has no update time for them, so "
+            f"they were left as they are. See 'snip conflicts', then 'snip resolve NAME "
+            f"--take local|remote|merge'.[/yellow]"
+        )
+    if legacy:
+        console.print(
+            f"[yellow]{len(legacy)} snippet(s) were shared before team keys: "
+            f"{', '.join(sorted(legacy))}. Run 'snip sync push --team {team_name or team_id}' to re-encrypt "
+            f"them under the team key.[/yellow]"
+        )
     return (pulled_count, error_count)
 
+
+def apply_remote(
+    full_path: str,
+    content: str,
+    remote_updated_at: Optional[str],
+    state: Optional[Tuple[str, Optional[datetime], datetime]],
+    strategy: str = "newer",
+    team_id: Optional[str] = None,
+    team_name: Optional[str] = None,
+) -> str:
+    """
+    Merge one decrypted server snippet into the local database, given the
+    local sync `state` (see Database.get_sync_states; None if there is no
+    local snippet). Returns what happened:
+
+    'pulled'    a new local snippet was saved
+    'updated'   the local snippet took the server's content
+    'unchanged' both sides already agree, or only the local side changed
+                (it stays pending and is sent by the next push)
+    'kept'      a conflict was settled in favour of the local content
+    'conflict'  a conflict could not be settled: it is marked as such, with
+                the server's content kept for snip resolve
+
+    A conflict (both sides changed since the last sync) is settled by
+    `strategy`: "local" or "remote" always picks that side; "newer" picks
+    the later updated_at, and without a server update time leaves the
+    snippet marked as a conflict. A new snippet pulled from a team space
+    (team_id) is scoped to that team.
+    """
+    if state is None:
+        db.save_snippet(full_path, content)
+        if team_id:
+            db.set_snippet_team(full_path, team_id, team_name)
+        db.mark_synced(full_path, content)
+        return "pulled"
+
+    local = db.get_snippet(full_path)
+    status, _, local_updated_at = state
+    if local.content == content:
+        db.mark_synced(full_path, content)
+        return "unchanged"
+    if status == "synced":
+        side = "remote"
+    elif db.is_last_synced(full_path, content):
+        # The server still has what was last synced; the local edit wins
+        return "unchanged"
+    elif strategy == "newer":
+        if not remote_updated_at:
+            db.set_sync_conflict(full_path, True, remote_content=content)
+            return "conflict"
+        newer = parse_timestamp(remote_updated_at) > local_updated_at
+        side = "remote" if newer else "local"
+    else:
+        side = strategy
+
+    if side == "local":
+        db.set_sync_conflict(full_path, False, remote_content=content)
+        return "kept"
+    db.save_snippet(full_path, content)
+    db.mark_synced(full_path, content)
+    return "updated"
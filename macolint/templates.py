@@ -0,0 +1,26 @@
+"""{{placeholder}} templates in snippet content, filled in by snip get."""
+
+import re
+from typing import Dict, List
+
+PLACEHOLDER_PATTERN = re.compile(r"\{\{\s*([A-Za-z_][\w.-]*)\s*\}\}")
+
+
+def find_placeholders(content: str) -> List[str]:
+    """Names of the {{placeholder}} tokens in content, in order of first use."""
+    names: List[str] = []
+    for match in PLACEHOLDER_PATTERN.finditer(content):
+        if match.group(1) not in names:
+            names.append(match.group(1))
+    return names
+
+
+def fill_placeholders(content: str, values: Dict[str, str]) -> str:
+    """
+    Replace each {{name}} token with values[name]. Tokens without a value are
+    left as they are; nothing else in the content changes.
+    """
+    def replace(match: "re.Match") -> str:
+        return values.get(match.group(1), match.group(0))
+
+    return PLACEHOLDER_PATTERN.sub(replace, content)
@@ -13,7 +13,7 @@ setup(
     description="A cloud-synced terminal snippet manager",
     long_description=long_description,
     long_description_content_type="text/markdown",
-    packages=find_packages(),
+    packages=find_packages(exclude=["tests", "tests.*"]),
     classifiers=[
         "Development Status :: 3 - Alpha",
         "Intended Audience :: Developers",
@@ -25,6 +25,12 @@ setup(
     ],
     python_requires=">=3.8",
     install_requires=requirements,
+    extras_require={
+        # Whole-file database encryption (the encrypt_database setting)
+        "sqlcipher": ["sqlcipher3"],
+        # YAML documents for snip save-batch
+        "yaml": ["pyyaml"],
+    },
     entry_points={
         "console_scripts": [
             "snip=macolint.cli:main",
@@ -0,0 +1,37 @@
+"""
+Tests for macolint. Run them from the repository root with:
+
+    python3 -m unittest
+
+All local data goes to a temporary MACOLINT_DATA_DIR, with the master key
+from MACOLINT_MASTER_KEY so the system keyring is never touched. Both are
+set here, before any test imports macolint (config reads them at import).
+"""
+
+import atexit
+import os
+import shutil
+import sqlite3
+import tempfile
+
+from cryptography.fernet import Fernet
+
+DATA_DIR = tempfile.mkdtemp(prefix="macolint-tests-")
+atexit.register(shutil.rmtree, DATA_DIR, ignore_errors=True)
+
+os.environ["MACOLINT_DATA_DIR"] = DATA_DIR
+os.environ["MACOLINT_MASTER_KEY"] = Fernet.generate_key().decode()
+
+
+def corrupt_content(db_path, content: str = "echo hello") -> None:
+    """
+    Re-encrypt every snippet's content under a throwaway master key, as if
+    written with another key (e.g. before a rotation): it no longer decrypts.
+    """
+    from macolint.crypto import pack_content, token_to_blob
+
+    token = Fernet(Fernet.generate_key()).encrypt(pack_content(content))
+    conn = sqlite3.connect(db_path)
+    conn.execute("UPDATE snippets SET content_encrypted = ?", (token_to_blob(token),))
+    conn.commit()
+    conn.close()
@@ -0,0 +1,68 @@
+"""Tests for the library API (macolint.api) against a temporary store."""
+
+import os
+import tempfile
+import unittest
+from pathlib import Path
+
+from cryptography.fernet import Fernet
+
+from macolint import api
+from tests import DATA_DIR, corrupt_content
+
+
+class ApiTest(unittest.TestCase):
+    def setUp(self):
+        directory = tempfile.TemporaryDirectory()
+        self.addCleanup(directory.cleanup)
+        self.config = api.Config(
+            db_path=Path(directory.name) / "snippets.db", master_key=Fernet.generate_key()
+        )
+
+    def test_vault_config_uses_data_dir(self):
+        config = api.Config.for_vault()
+        self.assertEqual(os.path.dirname(config.db_path), DATA_DIR)
+        self.assertEqual(config.master_key, os.environ["MACOLINT_MASTER_KEY"].encode())
+
+    def test_save_list_get_delete(self):
+        self.assertTrue(api.save_snippet(self.config, "ssh/login", "ssh {{user}}@prod01"))
+        self.assertTrue(api.save_snippet(self.config, "greet", "echo hello"))
+        self.assertEqual(sorted(api.list_snippets(self.config)), ["greet", "ssh/login"])
+
+        self.assertEqual(api.get_snippet(self.config, "greet").content, "echo hello")
+        filled = api.get_snippet(self.config, "ssh/login", fill={"user": "me"})
+        self.assertEqual(filled.content, "ssh me@prod01")
+
+        api.delete_snippet(self.config, "greet")
+        self.assertEqual(api.list_snippets(self.config), ["ssh/login"])
+        with self.assertRaises(api.SnippetNotFoundError):
+            api.get_snippet(self.config, "greet")
+
+    def test_save_existing_needs_overwrite(self):
+        api.save_snippet(self.config, "greet", "echo hello")
+        with self.assertRaises(api.SnippetExistsError):
+            api.save_snippet(self.config, "greet", "echo bye")
+        self.assertFalse(api.save_snippet(self.config, "greet", "echo bye", overwrite=True))
+        self.assertEqual(api.get_snippet(self.config, "greet").content, "echo bye")
+
+    def test_save_existing_does_not_decrypt_it(self):
+        api.save_snippet(self.config, "greet", "echo hello")
+        corrupt_content(self.config.db_path)
+
+        with self.assertRaises(api.SnippetExistsError):
+            api.save_snippet(self.config, "greet", "echo bye")
+        api.save_snippet(self.config, "greet", "echo bye", overwrite=True)
+        self.assertEqual(api.get_snippet(self.config, "greet").content, "echo bye")
+
+    def test_empty_store(self):
+        self.assertEqual(api.list_snippets(self.config), [])
+
+    def test_missing_snippet(self):
+        with self.assertRaises(api.SnippetNotFoundError):
+            api.get_snippet(self.config, "missing")
+        with self.assertRaises(api.SnippetNotFoundError):
+            api.delete_snippet(self.config, "missing")
+
+
+if __name__ == "__main__":
+    unittest.main()
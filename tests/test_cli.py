@@ -0,0 +1,82 @@
+"""Tests for the snip command line, run in-process with click's CliRunner."""
+
+import unittest
+
+from click.testing import CliRunner
+
+from macolint import cli
+from tests import corrupt_content
+
+
+class CliTest(unittest.TestCase):
+    def setUp(self):
+        self.runner = CliRunner()
+
+    def tearDown(self):
+        for path in cli.db.list_snippets():
+            cli.db.delete_snippet(path)
+
+    def snip(self, *args, input=None):
+        return self.runner.invoke(cli.cli, list(args), input=input)
+
+    def test_save_from_stdin(self):
+        result = self.snip("save", "greet", input="echo hello\n")
+        self.assertEqual(result.exit_code, 0, result.output)
+        self.assertEqual(cli.db.get_snippet("greet").content, "echo hello")
+
+    def test_save_empty_stdin(self):
+        result = self.snip("save", "greet", input="")
+        self.assertEqual(result.exit_code, 1)
+        self.assertIn("Snippet content cannot be empty", result.output)
+        self.assertFalse(cli.db.snippet_exists("greet"))
+
+    def test_save_existing_needs_force_without_decrypting(self):
+        self.snip("save", "greet", input="echo hello\n")
+        corrupt_content(cli.db.db_path)
+
+        result = self.snip("save", "greet", input="echo bye\n")
+        self.assertEqual(result.exit_code, 4, result.output)
+        self.assertIn("already exists", result.output)
+        result = self.snip("save", "greet", "--force", input="echo bye\n")
+        self.assertEqual(result.exit_code, 0, result.output)
+        self.assertEqual(cli.db.get_snippet("greet").content, "echo bye")
+
+    def test_get_leaves_braces_without_fill(self):
+        self.snip("save", "ci/sha", input="run: echo ${{ github.sha }}\n")
+        result = self.snip("get", "ci/sha", "--print", input="")
+        self.assertEqual(result.exit_code, 0, result.output)
+        self.assertIn("${{ github.sha }}", result.output)
+
+    def test_get_fill_needs_every_value(self):
+        self.snip("save", "ssh/login", input="ssh {{user}}@{{host}}\n")
+        result = self.snip("get", "ssh/login", "--print", "--fill", "host=prod01", input="")
+        self.assertEqual(result.exit_code, 2)
+        self.assertIn("No value for user", result.output)
+
+        result = self.snip(
+            "get", "ssh/login", "--print", "--fill", "host=prod01", "--fill", "user=deploy",
+            input="",
+        )
+        self.assertEqual(result.exit_code, 0, result.output)
+        self.assertIn("ssh deploy@prod01", result.output)
+
+    def test_global_dry_run_clip_history_prune(self):
+        self.snip("save", "clip/20240501-143000", input="copied\n")
+        result = self.snip("--dry-run", "clip-history", "prune", "--keep", "0")
+        self.assertEqual(result.exit_code, 0, result.output)
+        self.assertIn("Would delete: clip/20240501-143000", result.output)
+        self.assertTrue(cli.db.snippet_exists("clip/20240501-143000"))
+
+        result = self.snip("--dry-run", "clip-history", "list")
+        self.assertEqual(result.exit_code, 2)
+        self.assertIn("not supported by 'snip clip-history list'", result.output)
+
+    def test_finder_end_of_input(self):
+        self.snip("save", "greet", input="echo hello\n")
+        result = self.snip("get", input="")
+        self.assertEqual(result.exit_code, 1)
+        self.assertIn("No selection made", result.output)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,99 @@
+"""Tests for content encryption: protect() and the master-key (Fernet) layer."""
+
+import os
+import sqlite3
+import tempfile
+import unittest
+
+from cryptography.exceptions import InvalidTag
+from cryptography.fernet import Fernet
+
+from macolint.crypto import DecryptionError, b64, decrypt, encrypt, protect, ub64, unprotect
+from macolint.database import Database
+
+
+def flip_byte(data: bytes, index: int) -> bytes:
+    return data[:index] + bytes([data[index] ^ 0x01]) + data[index + 1:]
+
+
+class ProtectTest(unittest.TestCase):
+    def test_round_trip(self):
+        token = protect("psql -U admin", "hunter2")
+        self.assertNotIn("psql", token)
+        self.assertEqual(unprotect(token, "hunter2"), "psql -U admin")
+
+    def test_wrong_passphrase(self):
+        with self.assertRaises(DecryptionError):
+            unprotect(protect("psql -U admin", "hunter2"), "hunter3")
+
+    def test_tampered_ciphertext(self):
+        key = os.urandom(32)
+        ciphertext, nonce = encrypt(b"psql -U admin", key)
+        with self.assertRaises(InvalidTag):
+            decrypt(flip_byte(ciphertext, 0), nonce, key)
+
+        token = protect("psql -U admin", "hunter2")
+        head, ciphertext = token.rsplit(":", 1)
+        tampered = f"{head}:{b64(flip_byte(ub64(ciphertext), 0))}"
+        with self.assertRaises(DecryptionError):
+            unprotect(tampered, "hunter2")
+
+    def test_same_plaintext_encrypts_differently(self):
+        self.assertNotEqual(protect("psql", "hunter2"), protect("psql", "hunter2"))
+        key = os.urandom(32)
+        self.assertNotEqual(encrypt(b"psql", key), encrypt(b"psql", key))
+
+
+class MasterKeyEncryptionTest(unittest.TestCase):
+    def setUp(self):
+        directory = tempfile.TemporaryDirectory()
+        self.addCleanup(directory.cleanup)
+        self.db_path = os.path.join(directory.name, "snippets.db")
+        self.master_key = Fernet.generate_key()
+        self.db = Database(
+            self.db_path, self.master_key, encrypt_names=False, encrypt_database=False
+        )
+
+    def stored_content(self) -> bytes:
+        conn = sqlite3.connect(self.db_path)
+        (content,) = conn.execute("SELECT content_encrypted FROM snippets").fetchone()
+        conn.close()
+        return content
+
+    def test_round_trip(self):
+        # Long enough to be compressed before encryption
+        for content in ("echo hello", "echo hello\n" * 100):
+            self.assertEqual(self.db._decrypt_content(self.db._encrypt_content(content)), content)
+
+        self.db.save_snippet("greet", "echo hello")
+        self.assertNotIn(b"echo hello", self.stored_content())
+        self.assertEqual(self.db.get_snippet("greet").content, "echo hello")
+
+    def test_tampered_content(self):
+        self.db.save_snippet("greet", "echo hello")
+        tampered = flip_byte(self.stored_content(), -1)
+        conn = sqlite3.connect(self.db_path)
+        conn.execute("UPDATE snippets SET content_encrypted = ?", (tampered,))
+        conn.commit()
+        conn.close()
+
+        with self.assertRaises(DecryptionError):
+            self.db.get_snippet("greet")
+
+    def test_other_master_key(self):
+        encrypted = self.db._encrypt_content("echo hello")
+        other = Database(
+            os.path.join(os.path.dirname(self.db_path), "other.db"), Fernet.generate_key(),
+            encrypt_names=False, encrypt_database=False,
+        )
+        with self.assertRaises(DecryptionError):
+            other._decrypt_content(encrypted)
+
+    def test_same_plaintext_encrypts_differently(self):
+        self.assertNotEqual(
+            self.db._encrypt_content("echo hello"), self.db._encrypt_content("echo hello")
+        )
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,180 @@
+"""Tests for Database against a temporary database file."""
+
+import os
+import sqlite3
+import tempfile
+import unittest
+from unittest import mock
+
+from cryptography.fernet import Fernet
+
+from macolint.archive import build_archive, open_archive, read_archive_snippets
+from macolint.crypto import unpack_content
+from macolint.database import Database
+from macolint.migrations import SCHEMA_VERSION
+
+
+class ContentTypeTest(unittest.TestCase):
+    def setUp(self):
+        directory = tempfile.TemporaryDirectory()
+        self.addCleanup(directory.cleanup)
+        self.directory = directory.name
+        self.master_key = Fernet.generate_key()
+        self.db = self.open("snippets.db")
+
+    def open(self, filename):
+        return Database(
+            os.path.join(self.directory, filename), self.master_key,
+            encrypt_names=False, encrypt_database=False,
+        )
+
+    def content_type(self, name):
+        return self.db.get_snippet(name).content_type
+
+    def test_detected_on_save(self):
+        self.db.save_snippet("config", '{"port": 8080}')
+        self.assertEqual(self.content_type("config"), "json")
+
+    def test_detected_again_when_content_changes(self):
+        self.db.save_snippet("config", '{"port": 8080}')
+        self.db.save_snippet("config", "#!/bin/bash\necho $HOME")
+        self.assertEqual(self.content_type("config"), "shell")
+        self.db.update_snippet("config", '{"port": 8080}')
+        self.assertEqual(self.content_type("config"), "json")
+
+    def test_given_type_kept_by_later_saves_and_edits(self):
+        self.db.save_snippet("query", "count rows", content_type="sql")
+        self.db.save_snippet("query", '{"port": 8080}')
+        self.assertEqual(self.content_type("query"), "sql")
+        self.db.update_snippet("query", "echo $HOME")
+        self.assertEqual(self.content_type("query"), "sql")
+
+        self.db.save_snippet("query", '{"port": 8080}', content_type="json")
+        self.assertEqual(self.content_type("query"), "json")
+
+    def test_older_rows_given_when_not_detected(self):
+        self.db.save_snippet("config", '{"port": 8080}')
+        self.db.save_snippet("query", '{"port": 8080}')
+        # As a database from before content_type_explicit
+        conn = sqlite3.connect(self.db.db_path)
+        conn.execute("ALTER TABLE snippets DROP COLUMN content_type_explicit")
+        conn.execute("UPDATE snippets SET content_type = 'sql' WHERE name = 'query'")
+        conn.execute(f"PRAGMA user_version = {SCHEMA_VERSION - 1}")
+        conn.commit()
+        conn.close()
+
+        self.db = self.open("snippets.db")
+        self.db.save_snippet("config", "#!/bin/bash\necho $HOME")
+        self.db.save_snippet("query", "#!/bin/bash\necho $HOME")
+        self.assertEqual(self.content_type("config"), "shell")
+        self.assertEqual(self.content_type("query"), "sql")
+
+    def test_archive_keeps_whether_type_was_given(self):
+        self.db.save_snippet("config", '{"port": 8080}')
+        self.db.save_snippet("query", '{"port": 8080}', content_type="sql")
+        archive = build_archive(self.db.get_export_records(), self.master_key, "hunter2")
+        records = read_archive_snippets(archive, open_archive(archive, "hunter2"))
+
+        self.db = self.open("imported.db")
+        self.db.import_snippets(records)
+        self.db.update_snippet("config", "#!/bin/bash\necho $HOME")
+        self.db.update_snippet("query", "#!/bin/bash\necho $HOME")
+        self.assertEqual(self.content_type("config"), "shell")
+        self.assertEqual(self.content_type("query"), "sql")
+
+
+class RenameSyncTest(unittest.TestCase):
+    def setUp(self):
+        directory = tempfile.TemporaryDirectory()
+        self.addCleanup(directory.cleanup)
+        self.db = Database(
+            os.path.join(directory.name, "snippets.db"), Fernet.generate_key(),
+            encrypt_names=False, encrypt_database=False,
+        )
+
+    def save_synced(self, name, content):
+        self.db.save_snippet(name, content)
+        self.db.mark_synced(name, content)
+
+    def deletions(self):
+        return [path for _, path in self.db.get_sync_deletions()]
+
+    def statuses(self):
+        return {path: status for path, (status, _, _) in self.db.get_sync_states().items()}
+
+    def test_rename_snippet_queues_old_path(self):
+        self.save_synced("greet", "echo hello")
+        self.assertTrue(self.db.rename_snippet("greet", "shell/greet"))
+        self.assertEqual(self.deletions(), ["greet"])
+        self.assertEqual(self.statuses(), {"shell/greet": "pending"})
+
+    def test_rename_module_queues_old_paths(self):
+        self.save_synced("git/log", "git log --oneline")
+        self.save_synced("git/remote/push", "git push")
+        self.assertTrue(self.db.rename_module("git", "vcs"))
+        self.assertEqual(sorted(self.deletions()), ["git/log", "git/remote/push"])
+        self.assertEqual(
+            self.statuses(), {"vcs/log": "pending", "vcs/remote/push": "pending"}
+        )
+
+    def test_rename_unsynced_snippet_queues_nothing(self):
+        self.db.save_snippet("greet", "echo hello")
+        self.db.rename_snippet("greet", "hello")
+        self.assertEqual(self.deletions(), [])
+
+
+class ReencryptTest(unittest.TestCase):
+    def setUp(self):
+        directory = tempfile.TemporaryDirectory()
+        self.addCleanup(directory.cleanup)
+        self.db_path = os.path.join(directory.name, "snippets.db")
+        self.db = Database(self.db_path, Fernet.generate_key(), encrypt_names=True,
+                           encrypt_database=False)
+
+    def quarantined_paths(self):
+        conn = sqlite3.connect(self.db_path)
+        paths = [path for (path,) in conn.execute("SELECT path FROM quarantined_snippets")]
+        conn.close()
+        return [unpack_content(self.db.fernet.decrypt(path.encode("ascii"))) for path in paths]
+
+    def test_rotation_with_encrypted_names(self):
+        self.db.save_snippet("git/log", "git log --oneline")
+        self.db.save_snippet("old/greet", "echo hello")
+        self.db.mark_synced("old/greet", "echo hello")
+        self.db.delete_snippet("old/greet")
+        self.db.save_snippet("broken/token", "echo broken")
+        self.db.quarantine_snippets([self.db.get_snippet("broken/token").id])
+
+        new_key = Fernet.generate_key()
+        self.assertEqual(self.db.reencrypt_all(new_key), (1, 0))
+
+        self.db = Database(self.db_path, new_key, encrypt_names=True, encrypt_database=False)
+        self.assertEqual(self.db.get_snippet("git/log").content, "git log --oneline")
+        self.assertEqual([path for _, path in self.db.get_sync_deletions()], ["old/greet"])
+        self.assertEqual(self.quarantined_paths(), ["broken/token"])
+
+
+class QuarantineTest(unittest.TestCase):
+    def setUp(self):
+        directory = tempfile.TemporaryDirectory()
+        self.addCleanup(directory.cleanup)
+        self.db = Database(
+            os.path.join(directory.name, "snippets.db"), Fernet.generate_key(),
+            encrypt_names=False, encrypt_database=False,
+        )
+
+    def test_failure_rolls_back_and_unlocks(self):
+        self.db.save_snippet("greet", "echo hello")
+        snippet_id = self.db.get_snippet("greet").id
+        with mock.patch.object(self.db, "_delete_orphans", side_effect=sqlite3.OperationalError):
+            with self.assertRaises(sqlite3.OperationalError):
+                self.db.quarantine_snippets([snippet_id])
+
+        self.assertEqual(self.db.get_snippet("greet").content, "echo hello")
+        # The write lock is gone: another write goes through at once
+        self.db.save_snippet("bye", "echo bye")
+        self.assertEqual(self.db.quarantine_snippets([snippet_id]), 1)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,138 @@
+"""Tests for team sync, against an in-memory stand-in for Supabase."""
+
+import types
+import unittest
+from unittest import mock
+
+from macolint import sync
+from macolint.crypto import b64, decrypt, derive_key, derive_team_key, encrypt, gen_salt, ub64
+
+USER_SALT = gen_salt()
+TEAM_ID = "team-1"
+TEAM_PASSPHRASE = "team secret"
+SYNC_PASSPHRASE = "my secret"
+
+
+class FakeQuery:
+    """The part of the Supabase query builder sync uses, over server.rows."""
+
+    def __init__(self, server, table):
+        self.server = server
+        self.table = table
+        self.data = None
+
+    def select(self, *args):
+        return self
+
+    def upsert(self, data):
+        self.data = data
+        return self
+
+    insert = upsert
+
+    def eq(self, *args):
+        return self
+
+    def is_(self, *args):
+        return self
+
+    def execute(self):
+        if self.table != "snippets":
+            return types.SimpleNamespace(data=[])
+        if self.data is None:
+            return types.SimpleNamespace(data=[dict(row) for row in self.server.rows.values()])
+        self.server.rows[(self.data["module"], self.data["name"])] = dict(self.data)
+        return types.SimpleNamespace(data=[])
+
+
+class FakeServer:
+    def __init__(self):
+        self.rows = {}
+
+    def table(self, name):
+        return FakeQuery(self, name)
+
+
+def legacy_row(name, content, salt=None):
+    """A team row as pushed before team keys: under the sharer's personal key."""
+    ciphertext, nonce = encrypt(content.encode(), derive_key(SYNC_PASSPHRASE, salt or USER_SALT))
+    row = {
+        "module": None,
+        "name": name,
+        "content_encrypted": b64(ciphertext),
+        "nonce": b64(nonce),
+        "team_id": TEAM_ID,
+    }
+    if salt:
+        row["salt"] = b64(salt)
+    return row
+
+
+class TeamPullTest(unittest.TestCase):
+    def setUp(self):
+        self.server = FakeServer()
+        patches = [
+            mock.patch.object(sync, "get_authenticated_client", lambda: self.server),
+            mock.patch.object(sync, "is_authenticated", lambda: True),
+            mock.patch.object(
+                sync, "load_session", lambda: {"user": {"id": "u1"}, "access_token": "t"}
+            ),
+            mock.patch.object(sync, "ensure_user_salt", lambda *args: USER_SALT),
+        ]
+        for patch in patches:
+            patch.start()
+            self.addCleanup(patch.stop)
+
+    def tearDown(self):
+        for path in sync.db.list_snippets():
+            sync.db.delete_snippet(path)
+
+    def pull(self, legacy_passphrase=None):
+        return sync.sync_pull(
+            TEAM_PASSPHRASE, team_id=TEAM_ID, team_name="dev",
+            legacy_passphrase=legacy_passphrase,
+        )
+
+    def test_pulls_team_row(self):
+        salt = gen_salt()
+        ciphertext, nonce = encrypt(b"echo team", derive_team_key(TEAM_PASSPHRASE, TEAM_ID, salt))
+        self.server.rows[(None, "current")] = {
+            "module": None, "name": "current", "content_encrypted": b64(ciphertext),
+            "nonce": b64(nonce), "salt": b64(salt), "team_id": TEAM_ID,
+        }
+
+        self.assertEqual(self.pull(), (1, 0))
+        self.assertEqual(sync.db.get_snippet("current").content, "echo team")
+        self.assertEqual(sync.db.get_team_snippets(TEAM_ID), ["current"])
+
+    def test_legacy_row_needs_sync_passphrase(self):
+        self.server.rows[(None, "old")] = legacy_row("old", "echo old")
+
+        self.assertEqual(self.pull(), (0, 1))
+        self.assertIsNone(sync.db.get_snippet("old"))
+
+    def test_pulls_legacy_rows_with_sync_passphrase(self):
+        self.server.rows[(None, "old")] = legacy_row("old", "echo old")
+        self.server.rows[(None, "salted")] = legacy_row("salted", "echo salted", gen_salt())
+
+        self.assertEqual(self.pull(SYNC_PASSPHRASE), (2, 0))
+        self.assertEqual(sync.db.get_snippet("old").content, "echo old")
+        self.assertEqual(sync.db.get_snippet("salted").content, "echo salted")
+        self.assertEqual(sync.db.get_team_snippets(TEAM_ID), ["old", "salted"])
+
+    def test_push_re_encrypts_legacy_rows_under_team_key(self):
+        self.server.rows[(None, "old")] = legacy_row("old", "echo old")
+        self.pull(SYNC_PASSPHRASE)
+        states = sync.db.get_sync_states(team=TEAM_ID)
+        self.assertEqual(states["old"][0], "pending")
+
+        self.assertEqual(sync.sync_push(TEAM_PASSPHRASE, team_id=TEAM_ID), (1, 0))
+        row = self.server.rows[(None, "old")]
+        key = derive_team_key(TEAM_PASSPHRASE, TEAM_ID, ub64(row["salt"]))
+        content = decrypt(ub64(row["content_encrypted"]), ub64(row["nonce"]), key)
+        self.assertEqual(content, b"echo old")
+        self.assertEqual(self.pull(), (0, 0))
+
+
+if __name__ == "__main__":
+    unittest.main()